@@ -0,0 +1,465 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Drawing to the frame buffers of the bitmap modes.
+//!
+//! Modes 3 and 5 store a 16-bit color for each pixel, drawn with a
+//! [`Bitmap16`]. Mode 4 stores an 8-bit palette index for each pixel, drawn
+//! with a [`Bitmap8`]. VRAM cannot be written a byte at a time, so a
+//! `Bitmap8` writes aligned pairs of pixels together, and merges a single
+//! pixel with its neighbor.
+//!
+//! Areas may extend past the edges of the frame buffer, and are clipped to
+//! it.
+
+use core::ops::Range;
+
+/// Memory that holds a frame buffer, accessed a halfword at a time.
+pub trait Buffer {
+    /// Reads the halfword at `index`.
+    fn read(&self, index: usize) -> u16;
+
+    /// Writes the halfword at `index`.
+    fn write(&mut self, index: usize, value: u16);
+}
+
+impl Buffer for [u16] {
+    fn read(&self, index: usize) -> u16 {
+        self[index]
+    }
+
+    fn write(&mut self, index: usize, value: u16) {
+        self[index] = value;
+    }
+}
+
+impl<B: Buffer + ?Sized> Buffer for &mut B {
+    fn read(&self, index: usize) -> u16 {
+        (**self).read(index)
+    }
+
+    fn write(&mut self, index: usize, value: u16) {
+        (**self).write(index, value);
+    }
+}
+
+/// A rectangle of pixels, which may extend past the frame buffer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Area {
+    pub const fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+/// Returns the part of `len` units at `start` that is within `0..size`.
+fn clip(start: i32, len: u32, size: usize) -> Range<usize> {
+    let size = size as i64;
+    let end = i64::from(start) + i64::from(len);
+    let start = i64::from(start).clamp(0, size);
+    (start as usize)..(end.clamp(start, size) as usize)
+}
+
+/// The size of a frame buffer and the pixels of an area within it.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    width: usize,
+    height: usize,
+}
+
+impl Frame {
+    /// Returns the index of the pixel at (x, y), if it is visible.
+    fn position(self, x: i64, y: i64) -> Option<usize> {
+        let x = usize::try_from(x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < self.height)?;
+        Some(x + (y * self.width))
+    }
+
+    /// Returns the pixel index ranges of each visible row of `area`.
+    fn rows(self, area: Area) -> impl Iterator<Item = Range<usize>> {
+        let columns = clip(area.x, area.width, self.width);
+        clip(area.y, area.height, self.height).map(move |y| {
+            let row = y * self.width;
+            (row + columns.start)..(row + columns.end)
+        })
+    }
+
+    /// Returns the position of each pixel of `area` in order, or `None` for
+    /// pixels that are not visible.
+    fn pixels(self, area: Area) -> impl Iterator<Item = Option<usize>> {
+        (0..i64::from(area.height)).flat_map(move |dy| {
+            let y = i64::from(area.y) + dy;
+            (0..i64::from(area.width)).map(move |dx| self.position(i64::from(area.x) + dx, y))
+        })
+    }
+}
+
+/// A frame buffer of 16-bit colors, as used by modes 3 and 5.
+#[derive(Debug)]
+pub struct Bitmap16<B: Buffer> {
+    buffer: B,
+    frame: Frame,
+}
+
+impl<B: Buffer> Bitmap16<B> {
+    /// Draws to `buffer`, which holds `width * height` pixels.
+    pub const fn new(buffer: B, width: usize, height: usize) -> Self {
+        Self {
+            buffer,
+            frame: Frame { width, height },
+        }
+    }
+
+    /// Releases the buffer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    /// Sets the pixel at (x, y), if it is visible.
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: u16) {
+        if let Some(pos) = self.frame.position(i64::from(x), i64::from(y)) {
+            self.buffer.write(pos, color);
+        }
+    }
+
+    /// Sets the pixels of `area` to `colors`, in rows from the top left.
+    ///
+    /// Colors of pixels that are not visible are skipped. Stops early if
+    /// there are too few colors.
+    pub fn fill_contiguous(&mut self, area: Area, colors: impl IntoIterator<Item = u16>) {
+        for (pos, color) in self.frame.pixels(area).zip(colors) {
+            if let Some(pos) = pos {
+                self.buffer.write(pos, color);
+            }
+        }
+    }
+
+    /// Sets all pixels of `area` to `color`.
+    pub fn fill_solid(&mut self, area: Area, color: u16) {
+        for row in self.frame.rows(area) {
+            for pos in row {
+                self.buffer.write(pos, color);
+            }
+        }
+    }
+}
+
+/// A frame buffer of 8-bit palette indexes, as used by mode 4.
+#[derive(Debug)]
+pub struct Bitmap8<B: Buffer> {
+    buffer: B,
+    frame: Frame,
+}
+
+impl<B: Buffer> Bitmap8<B> {
+    /// Draws to `buffer`, which holds `width * height` pixels in
+    /// `width * height / 2` halfwords.
+    pub const fn new(buffer: B, width: usize, height: usize) -> Self {
+        Self {
+            buffer,
+            frame: Frame { width, height },
+        }
+    }
+
+    /// Releases the buffer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    /// Sets the pixel at (x, y), if it is visible.
+    pub fn set_pixel(&mut self, x: i32, y: i32, index: u8) {
+        if let Some(pos) = self.frame.position(i64::from(x), i64::from(y)) {
+            self.set(pos, index);
+        }
+    }
+
+    /// Sets the pixels of `area` to `indexes`, in rows from the top left.
+    ///
+    /// Indexes of pixels that are not visible are skipped. Stops early if
+    /// there are too few indexes.
+    pub fn fill_contiguous(&mut self, area: Area, indexes: impl IntoIterator<Item = u8>) {
+        let mut pixels = self.frame.pixels(area).peekable();
+        let mut indexes = indexes.into_iter();
+
+        while let Some(pos) = pixels.next() {
+            let Some(first) = indexes.next() else {
+                return;
+            };
+            let Some(pos) = pos else {
+                continue;
+            };
+
+            // Write an aligned pair in one access if the next pixel of the
+            // area follows it on the same row.
+            if (pos & 1) == 0 && pixels.peek() == Some(&Some(pos + 1)) {
+                pixels.next();
+                let Some(second) = indexes.next() else {
+                    self.set(pos, first);
+                    return;
+                };
+                self.buffer.write(pos / 2, pair(first, second));
+            } else {
+                self.set(pos, first);
+            }
+        }
+    }
+
+    /// Sets all pixels of `area` to `index`.
+    pub fn fill_solid(&mut self, area: Area, index: u8) {
+        for row in self.frame.rows(area) {
+            let mut pos = row.start;
+            if (pos & 1) == 1 && pos < row.end {
+                self.set(pos, index);
+                pos += 1;
+            }
+
+            while pos + 1 < row.end {
+                self.buffer.write(pos / 2, pair(index, index));
+                pos += 2;
+            }
+
+            if pos < row.end {
+                self.set(pos, index);
+            }
+        }
+    }
+
+    /// Sets the pixel at `pos`, keeping the other pixel of its halfword.
+    fn set(&mut self, pos: usize, index: u8) {
+        let prev = self.buffer.read(pos / 2);
+        let value = if (pos & 1) == 1 {
+            (prev & 0x00FF) | (u16::from(index) << 8)
+        } else {
+            (prev & 0xFF00) | u16::from(index)
+        };
+        self.buffer.write(pos / 2, value);
+    }
+}
+
+/// The halfword holding two pixels, with the first in the low byte.
+fn pair(first: u8, second: u8) -> u16 {
+    u16::from_le_bytes([first, second])
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::cell::Cell;
+    use std::vec::Vec;
+
+    use super::*;
+
+    // The frame buffers of modes 3, 4, and 5.
+    const MODE3: (usize, usize) = (240, 160);
+    const MODE4: (usize, usize) = (240, 160);
+    const MODE5: (usize, usize) = (160, 128);
+
+    /// A buffer that counts reads, as a pixel merged with its neighbor is
+    /// read before it is written.
+    struct Counting {
+        data: Vec<u16>,
+        reads: Cell<usize>,
+    }
+
+    impl Buffer for Counting {
+        fn read(&self, index: usize) -> u16 {
+            self.reads.set(self.reads.get() + 1);
+            self.data[index]
+        }
+
+        fn write(&mut self, index: usize, value: u16) {
+            self.data[index] = value;
+        }
+    }
+
+    /// Areas with odd and even edges, clipped on each side.
+    fn areas((width, height): (usize, usize)) -> Vec<Area> {
+        let (w, h) = (width as i32, height as i32);
+        let mut areas = Vec::new();
+        for x in [-3, -2, 0, 1, 2, 7, w - 5, w - 4, w - 1, w] {
+            for len in [0, 1, 2, 3, 4, 5, 8] {
+                for (y, rows) in [(0, 1), (3, 2), (-2, 4), (h - 2, 5), (-5, 3), (h, 1)] {
+                    areas.push(Area::new(x, y, len, rows));
+                }
+            }
+        }
+        areas.push(Area::new(-10, -10, width as u32 + 20, height as u32 + 20));
+        areas
+    }
+
+    /// The pixels before drawing, all different from those drawn.
+    fn background(len: usize) -> Vec<u16> {
+        (0..len).map(|pos| 0x8000 | pos as u16).collect()
+    }
+
+    /// Sets the pixels of `area` in `pixels` one at a time, to `color(i)` for
+    /// the i-th pixel of the area.
+    fn draw<T: Copy>(
+        pixels: &mut [T],
+        (width, height): (usize, usize),
+        area: Area,
+        color: impl Fn(usize) -> T,
+    ) {
+        let mut i = 0;
+        for dy in 0..i64::from(area.height) {
+            for dx in 0..i64::from(area.width) {
+                let x = i64::from(area.x) + dx;
+                let y = i64::from(area.y) + dy;
+                if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                    pixels[x as usize + (y as usize * width)] = color(i);
+                }
+                i += 1;
+            }
+        }
+    }
+
+    fn bytes(buffer: &[u16]) -> Vec<u8> {
+        buffer.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn set_pixel16() {
+        for size in [MODE3, MODE5] {
+            let (w, h) = (size.0 as i32, size.1 as i32);
+            let mut buffer = background(size.0 * size.1);
+            let mut expected = buffer.clone();
+
+            let mut bitmap = Bitmap16::new(&mut buffer[..], size.0, size.1);
+            for (x, y) in [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1), (5, 3)] {
+                bitmap.set_pixel(x, y, 0x1234);
+                draw(&mut expected, size, Area::new(x, y, 1, 1), |_| 0x1234);
+            }
+            for (x, y) in [(-1, 0), (0, -1), (w, 0), (0, h), (i32::MIN, i32::MAX)] {
+                bitmap.set_pixel(x, y, 0x1234);
+            }
+
+            assert_eq!(buffer, expected);
+        }
+    }
+
+    #[test]
+    fn fill_solid16() {
+        for size in [MODE3, MODE5] {
+            for area in areas(size) {
+                let mut buffer = background(size.0 * size.1);
+                let mut expected = buffer.clone();
+                draw(&mut expected, size, area, |_| 0x7C1F);
+
+                Bitmap16::new(&mut buffer[..], size.0, size.1).fill_solid(area, 0x7C1F);
+                assert!(buffer == expected, "{area:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn fill_contiguous16() {
+        for size in [MODE3, MODE5] {
+            for area in areas(size) {
+                let mut buffer = background(size.0 * size.1);
+                let mut expected = buffer.clone();
+                draw(&mut expected, size, area, |i| i as u16);
+
+                let colors = (0..).map(|i: usize| i as u16);
+                Bitmap16::new(&mut buffer[..], size.0, size.1).fill_contiguous(area, colors);
+                assert!(buffer == expected, "{area:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn fill_contiguous16_short() {
+        let mut buffer = background(MODE5.0 * MODE5.1);
+        let mut expected = buffer.clone();
+        draw(&mut expected, MODE5, Area::new(0, 0, 4, 1), |_| 1);
+        draw(&mut expected, MODE5, Area::new(0, 1, 1, 1), |_| 1);
+
+        let mut bitmap = Bitmap16::new(&mut buffer[..], MODE5.0, MODE5.1);
+        bitmap.fill_contiguous(Area::new(0, 0, 4, 4), [1; 5]);
+        assert_eq!(buffer, expected);
+    }
+
+    #[test]
+    fn set_pixel8() {
+        let mut buffer = background(MODE4.0 * MODE4.1 / 2);
+        let mut expected = bytes(&buffer);
+
+        let mut bitmap = Bitmap8::new(&mut buffer[..], MODE4.0, MODE4.1);
+        // Odd and even pixels keep the other pixel of their halfword.
+        for (x, y) in [(0, 0), (1, 1), (238, 159), (239, 159)] {
+            bitmap.set_pixel(x, y, 0xAB);
+            draw(&mut expected, MODE4, Area::new(x, y, 1, 1), |_| 0xAB);
+        }
+        for (x, y) in [(-1, 0), (0, -1), (240, 0), (0, 160)] {
+            bitmap.set_pixel(x, y, 0xAB);
+        }
+
+        assert_eq!(bytes(&buffer), expected);
+    }
+
+    #[test]
+    fn fill_solid8() {
+        for area in areas(MODE4) {
+            let mut buffer = background(MODE4.0 * MODE4.1 / 2);
+            let mut expected = bytes(&buffer);
+            draw(&mut expected, MODE4, area, |_| 0x42);
+
+            Bitmap8::new(&mut buffer[..], MODE4.0, MODE4.1).fill_solid(area, 0x42);
+            assert!(bytes(&buffer) == expected, "{area:?}");
+        }
+    }
+
+    #[test]
+    fn fill_contiguous8() {
+        for area in areas(MODE4) {
+            let mut buffer = background(MODE4.0 * MODE4.1 / 2);
+            let mut expected = bytes(&buffer);
+            draw(&mut expected, MODE4, area, |i| (i % 251) as u8);
+
+            let indexes = (0..).map(|i: usize| (i % 251) as u8);
+            Bitmap8::new(&mut buffer[..], MODE4.0, MODE4.1).fill_contiguous(area, indexes);
+            assert!(bytes(&buffer) == expected, "{area:?}");
+        }
+    }
+
+    #[test]
+    fn fill_contiguous8_short() {
+        // The last index is the first of an aligned pair.
+        let mut buffer = background(MODE4.0 * MODE4.1 / 2);
+        let mut expected = bytes(&buffer);
+        draw(&mut expected, MODE4, Area::new(0, 0, 3, 1), |_| 1);
+
+        let mut bitmap = Bitmap8::new(&mut buffer[..], MODE4.0, MODE4.1);
+        bitmap.fill_contiguous(Area::new(0, 0, 4, 1), [1; 3]);
+        assert_eq!(bytes(&buffer), expected);
+    }
+
+    #[test]
+    fn pairs8() {
+        // Only pixels at odd edges are merged with their neighbors.
+        let reads = |area: Area, contiguous: bool| {
+            let data = background(MODE4.0 * MODE4.1 / 2);
+            let buffer = Counting { data, reads: Cell::new(0) };
+            let mut bitmap = Bitmap8::new(buffer, MODE4.0, MODE4.1);
+            if contiguous {
+                bitmap.fill_contiguous(area, core::iter::repeat(7));
+            } else {
+                bitmap.fill_solid(area, 7);
+            }
+            bitmap.into_inner().reads.get()
+        };
+
+        for contiguous in [false, true] {
+            assert_eq!(reads(Area::new(2, 0, 8, 3), contiguous), 0);
+            assert_eq!(reads(Area::new(1, 0, 8, 3), contiguous), 6);
+            assert_eq!(reads(Area::new(1, 0, 9, 3), contiguous), 3);
+            assert_eq!(reads(Area::new(0, 0, 240, 160), contiguous), 0);
+            assert_eq!(reads(Area::new(-1, 0, 4, 1), contiguous), 1);
+        }
+    }
+}
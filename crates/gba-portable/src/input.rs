@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Keys, control-pad directions, and command recognition.
+//!
+//! The hardware keys are read by `gba::input`, which re-exports these.
+
+use core::{fmt, ops};
+
+pub mod command;
+
+/// A bit field that represents the raw state of hardware keys.
+///
+/// Bits 0-9 are used to represent each key's state.
+/// Bits 10-15 are not used.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct Keys(u16);
+
+impl Keys {
+    /// A button
+    pub const A: Self = Self(1 << 0);
+    /// B button
+    pub const B: Self = Self(1 << 1);
+    /// Select button
+    pub const SELECT: Self = Self(1 << 2);
+    /// Start button
+    pub const START: Self = Self(1 << 3);
+    /// Control-pad right
+    pub const RIGHT: Self = Self(1 << 4);
+    /// Control-pad left
+    pub const LEFT: Self = Self(1 << 5);
+    /// Control-pad up
+    pub const UP: Self = Self(1 << 6);
+    /// Control-pad down
+    pub const DOWN: Self = Self(1 << 7);
+    /// Right shoulder button
+    pub const R: Self = Self(1 << 8);
+    /// Left shoulder button
+    pub const L: Self = Self(1 << 9);
+
+    /// The mask of used bits from the register.
+    const MASK: u16 = 0b0000_0011_1111_1111;
+}
+
+impl Keys {
+    /// The control-pad keys.
+    pub const DPAD: Self = Self(0b0000_0000_1111_0000);
+    /// All keys.
+    pub const ALL: Self = Self(Self::MASK);
+
+    /// Returns the keys of the set bits of `bits`, ignoring unused bits.
+    pub const fn from_bits(bits: u16) -> Self {
+        Self(bits & Self::MASK)
+    }
+
+    /// The bits of the keys.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Checks if no bits are set.
+    pub const fn is_empty(self) -> bool {
+        (self.0 & Self::MASK) == 0
+    }
+
+    /// Checks if all bits set in `keys` are set.
+    pub const fn contains(self, keys: Self) -> bool {
+        (self.0 & keys.0) == keys.0
+    }
+
+    /// Returns -1 if only `minus` is set, 1 if only `plus` is set, and 0
+    /// otherwise.
+    pub const fn tribool(self, minus: Self, plus: Self) -> i32 {
+        let minus = (self.0 & minus.0) != 0;
+        let plus = (self.0 & plus.0) != 0;
+        match (minus, plus) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => 0,
+        }
+    }
+
+    /// The horizontal axis of the control-pad: -1 for left, 1 for right.
+    pub const fn horizontal(self) -> i32 {
+        self.tribool(Self::LEFT, Self::RIGHT)
+    }
+
+    /// The vertical axis of the control-pad: -1 for up, 1 for down.
+    ///
+    /// This matches screen coordinates, where y increases downwards.
+    pub const fn vertical(self) -> i32 {
+        self.tribool(Self::UP, Self::DOWN)
+    }
+
+    /// The control-pad as an x/y vector, with each axis -1, 0, or 1.
+    pub const fn dpad(self) -> (i32, i32) {
+        (self.horizontal(), self.vertical())
+    }
+
+    /// The direction of the control-pad, if any.
+    pub const fn direction(self) -> Option<Direction> {
+        Direction::from_vector(self.horizontal(), self.vertical())
+    }
+
+    /// Returns an iterator over each key whose bit is set.
+    pub const fn iter(self) -> Iter {
+        Iter(self.0 & Self::MASK)
+    }
+}
+
+impl Default for Keys {
+    /// Returns the default value of all keys released.
+    fn default() -> Self {
+        Self(Self::MASK)
+    }
+}
+
+impl From<u16> for Keys {
+    fn from(value: u16) -> Self {
+        Self(value & Keys::MASK)
+    }
+}
+
+impl From<Keys> for u16 {
+    fn from(value: Keys) -> Self {
+        value.0
+    }
+}
+
+impl ops::BitAnd for Keys {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl ops::BitOr for Keys {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitXor for Keys {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl ops::Not for Keys {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0 & Keys::MASK)
+    }
+}
+
+impl fmt::Binary for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+}
+
+impl fmt::LowerHex for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl IntoIterator for Keys {
+    type Item = Keys;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over each key set in [`Keys`], from [`Keys::A`] to
+/// [`Keys::L`].
+#[derive(Debug, Clone)]
+pub struct Iter(u16);
+
+impl Iterator for Iter {
+    type Item = Keys;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let key = self.0 & self.0.wrapping_neg();
+        self.0 &= !key;
+        Some(Keys(key))
+    }
+}
+
+/// A direction of the control-pad.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    /// Returns the direction of an x/y vector, using the sign of each axis.
+    ///
+    /// Returns `None` for the zero vector.
+    pub const fn from_vector(x: i32, y: i32) -> Option<Self> {
+        match (x.signum(), y.signum()) {
+            (0, -1) => Some(Self::Up),
+            (1, -1) => Some(Self::UpRight),
+            (1, 0) => Some(Self::Right),
+            (1, 1) => Some(Self::DownRight),
+            (0, 1) => Some(Self::Down),
+            (-1, 1) => Some(Self::DownLeft),
+            (-1, 0) => Some(Self::Left),
+            (-1, -1) => Some(Self::UpLeft),
+            _ => None,
+        }
+    }
+
+    /// The x/y vector of the direction, with each axis -1, 0, or 1.
+    pub const fn vector(self) -> (i32, i32) {
+        match self {
+            Self::Up => (0, -1),
+            Self::UpRight => (1, -1),
+            Self::Right => (1, 0),
+            Self::DownRight => (1, 1),
+            Self::Down => (0, 1),
+            Self::DownLeft => (-1, 1),
+            Self::Left => (-1, 0),
+            Self::UpLeft => (-1, -1),
+        }
+    }
+
+    /// The control-pad keys for the direction.
+    pub const fn keys(self) -> Keys {
+        match self {
+            Self::Up => Keys::UP,
+            Self::UpRight => Keys(Keys::UP.0 | Keys::RIGHT.0),
+            Self::Right => Keys::RIGHT,
+            Self::DownRight => Keys(Keys::DOWN.0 | Keys::RIGHT.0),
+            Self::Down => Keys::DOWN,
+            Self::DownLeft => Keys(Keys::DOWN.0 | Keys::LEFT.0),
+            Self::Left => Keys::LEFT,
+            Self::UpLeft => Keys(Keys::UP.0 | Keys::LEFT.0),
+        }
+    }
+
+    /// The opposite direction.
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::UpRight => Self::DownLeft,
+            Self::Right => Self::Left,
+            Self::DownRight => Self::UpLeft,
+            Self::Down => Self::Up,
+            Self::DownLeft => Self::UpRight,
+            Self::Left => Self::Right,
+            Self::UpLeft => Self::DownRight,
+        }
+    }
+}
@@ -0,0 +1,504 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Command and motion recognition.
+//!
+//! An [`InputBuffer`] keeps the keys pressed on each of the last `N` frames.
+//! A [`Command`] is a sequence of [`Step`]s, such as the directions of a
+//! motion followed by a button press, that is checked against the buffer.
+//!
+//! Directions in steps are written for a character facing right, so
+//! [`Direction::Right`] is forward. They are mirrored when checking a command
+//! for a character facing left.
+//!
+//! Each step must happen on a later frame than the step before it, within
+//! the command's leniency. The last step must happen on the current frame, so
+//! a command ending with a button press matches only once.
+//!
+//! ```rust
+//! use gba::input::command::{Command, Facing, InputBuffer, Step};
+//! use gba::input::{Direction, Input, Keys};
+//!
+//! // Down, down-forward, forward + A
+//! const FIREBALL: Command = Command::new(&[
+//!     Step::direction(Direction::Down),
+//!     Step::direction(Direction::DownRight),
+//!     Step::direction(Direction::Right).with_press(Keys::A),
+//! ]);
+//! // Charge back for 40 frames, then forward + B
+//! const DASH: Command = Command::new(&[
+//!     Step::charge(Direction::Left, 40),
+//!     Step::direction(Direction::Right).with_press(Keys::B),
+//! ]);
+//! const COMMANDS: [Command; 2] = [FIREBALL, DASH];
+//!
+//! let mut input = Input::new();
+//! let mut buffer = InputBuffer::<64>::new();
+//! loop {
+//!     gba::bios::vblank();
+//!     input.update();
+//!     buffer.push(input.keys_pressed());
+//!
+//!     for index in buffer.matched(&COMMANDS, Facing::Right) {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use super::{Direction, Keys};
+
+/// The default number of frames allowed between steps of a command.
+pub const DEFAULT_LENIENCY: u16 = 10;
+/// The default number of frames allowed for a whole command.
+pub const DEFAULT_WINDOW: u16 = 30;
+
+/// The direction a character is facing.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Facing {
+    #[default]
+    Right,
+    Left,
+}
+
+impl Facing {
+    /// Converts keys pressed to keys as if facing right.
+    const fn normalize(self, keys: Keys) -> Keys {
+        match self {
+            Self::Right => keys,
+            Self::Left => {
+                let left = (keys.0 & Keys::LEFT.0) != 0;
+                let right = (keys.0 & Keys::RIGHT.0) != 0;
+                let mut bits = keys.0 & !(Keys::LEFT.0 | Keys::RIGHT.0);
+                if left {
+                    bits |= Keys::RIGHT.0;
+                }
+                if right {
+                    bits |= Keys::LEFT.0;
+                }
+                Keys(bits)
+            }
+        }
+    }
+}
+
+/// The control-pad condition of a step.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Dpad {
+    /// The control-pad is ignored.
+    Any,
+    /// No control-pad keys are pressed.
+    Neutral,
+    /// The control-pad is exactly in the direction.
+    Exact(Direction),
+    /// The control-pad includes the direction, and has for some frames.
+    Charge(Direction, u16),
+}
+
+/// A single step of a [`Command`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Step {
+    dpad: Dpad,
+    press: Keys,
+}
+
+impl Step {
+    /// The control-pad is exactly in `direction`.
+    pub const fn direction(direction: Direction) -> Self {
+        Self {
+            dpad: Dpad::Exact(direction),
+            press: Keys(0),
+        }
+    }
+
+    /// No control-pad keys are pressed.
+    ///
+    /// Use between repeated directions to require separate taps, such as
+    /// for a forward dash.
+    pub const fn neutral() -> Self {
+        Self {
+            dpad: Dpad::Neutral,
+            press: Keys(0),
+        }
+    }
+
+    /// `keys` were just pressed, regardless of the control-pad.
+    pub const fn press(keys: Keys) -> Self {
+        Self { dpad: Dpad::Any, press: keys }
+    }
+
+    /// The control-pad has included `direction` for at least `frames` frames.
+    ///
+    /// Diagonals that include the direction also charge, so down-back
+    /// charges back. The buffer must hold at least `frames` frames.
+    pub const fn charge(direction: Direction, frames: u16) -> Self {
+        Self {
+            dpad: Dpad::Charge(direction, frames),
+            press: Keys(0),
+        }
+    }
+
+    /// Also requires `keys` to be just pressed on the same frame.
+    pub const fn with_press(self, keys: Keys) -> Self {
+        Self {
+            dpad: self.dpad,
+            press: Keys(self.press.0 | keys.0),
+        }
+    }
+}
+
+/// A sequence of steps to recognize, such as a special move.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Command {
+    steps: &'static [Step],
+    leniency: u16,
+    window: u16,
+}
+
+impl Command {
+    /// Returns a command with the default leniency and window.
+    pub const fn new(steps: &'static [Step]) -> Self {
+        Self {
+            steps,
+            leniency: DEFAULT_LENIENCY,
+            window: DEFAULT_WINDOW,
+        }
+    }
+
+    /// Sets the number of frames allowed between each step.
+    pub const fn with_leniency(self, frames: u16) -> Self {
+        Self { leniency: frames, ..self }
+    }
+
+    /// Sets the number of frames allowed from the first step to the last.
+    pub const fn with_window(self, frames: u16) -> Self {
+        Self { window: frames, ..self }
+    }
+
+    /// The steps of the command.
+    pub const fn steps(&self) -> &'static [Step] {
+        self.steps
+    }
+}
+
+/// A ring buffer of the keys pressed on each of the last `N` frames.
+#[derive(Debug, Clone)]
+pub struct InputBuffer<const N: usize> {
+    frames: [Keys; N],
+    /// The index of the current frame
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for InputBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> InputBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            frames: [Keys(0); N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds the keys pressed on a new frame.
+    ///
+    /// Should be called once per game loop with the keys of
+    /// `gba::input::Input::keys_pressed()`.
+    pub fn push(&mut self, keys: Keys) {
+        if N == 0 {
+            return;
+        }
+
+        self.head = (self.head + 1) % N;
+        self.frames[self.head] = keys;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Discards all frames.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The number of frames in the buffer.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the buffer has no frames.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the keys pressed `age` frames ago, where 0 is the current
+    /// frame. Frames older than the buffer have no keys pressed.
+    pub fn keys(&self, age: usize) -> Keys {
+        if age >= self.len {
+            return Keys(0);
+        }
+
+        self.frames[(self.head + N - age) % N]
+    }
+
+    /// Returns the keys that were just pressed `age` frames ago.
+    ///
+    /// The oldest frame has no frame before it to compare with, so no keys
+    /// were just pressed on it. This includes the first frame after
+    /// [`clear()`](Self::clear).
+    pub fn just_pressed(&self, age: usize) -> Keys {
+        if age + 1 >= self.len {
+            return Keys(0);
+        }
+
+        self.keys(age) & !self.keys(age + 1)
+    }
+
+    /// Checks if `command` was completed on the current frame.
+    pub fn matches(&self, command: &Command, facing: Facing) -> bool {
+        let Some((last, rest)) = command.steps.split_last() else {
+            return false;
+        };
+
+        if !self.step_matches(last, 0, facing) {
+            return false;
+        }
+
+        // Find the most recent frame for each earlier step, which leaves the
+        // most room for the steps before it.
+        let leniency = usize::from(command.leniency);
+        let mut age = 0;
+        for step in rest.iter().rev() {
+            let found = ((age + 1)..=(age + leniency))
+                .take_while(|&a| a < self.len)
+                .find(|&a| self.step_matches(step, a, facing));
+            match found {
+                Some(a) => age = a,
+                None => return false,
+            }
+        }
+
+        age <= usize::from(command.window)
+    }
+
+    /// Returns the index of each command that was completed on the current
+    /// frame.
+    pub fn matched<'a>(
+        &'a self,
+        commands: &'a [Command],
+        facing: Facing,
+    ) -> impl Iterator<Item = usize> + 'a {
+        commands
+            .iter()
+            .enumerate()
+            .filter(move |(_, command)| self.matches(command, facing))
+            .map(|(i, _)| i)
+    }
+
+    fn step_matches(&self, step: &Step, age: usize, facing: Facing) -> bool {
+        if !facing.normalize(self.just_pressed(age)).contains(step.press) {
+            return false;
+        }
+
+        let dpad = facing.normalize(self.keys(age)) & Keys::DPAD;
+        match step.dpad {
+            Dpad::Any => true,
+            Dpad::Neutral => dpad.is_empty(),
+            Dpad::Exact(direction) => dpad == direction.keys(),
+            Dpad::Charge(direction, frames) => {
+                let frames = usize::from(frames);
+                age + frames <= self.len
+                    && (age..(age + frames))
+                        .all(|a| facing.normalize(self.keys(a)).contains(direction.keys()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The commands of the module example.
+    const FIREBALL: Command = Command::new(&[
+        Step::direction(Direction::Down),
+        Step::direction(Direction::DownRight),
+        Step::direction(Direction::Right).with_press(Keys::A),
+    ]);
+    const DASH: Command = Command::new(&[
+        Step::charge(Direction::Left, 40),
+        Step::direction(Direction::Right).with_press(Keys::B),
+    ]);
+
+    const NONE: Keys = Keys(0);
+    const DOWN_RIGHT: Keys = Keys(Keys::DOWN.0 | Keys::RIGHT.0);
+    const DOWN_LEFT: Keys = Keys(Keys::DOWN.0 | Keys::LEFT.0);
+
+    /// Returns a buffer of the frames, oldest first, after a neutral frame.
+    fn frames(keys: &[Keys]) -> InputBuffer<64> {
+        let mut buffer = InputBuffer::new();
+        buffer.push(NONE);
+        for &keys in keys {
+            buffer.push(keys);
+        }
+        buffer
+    }
+
+    /// Pushes `keys` for `count` frames.
+    fn hold<const N: usize>(buffer: &mut InputBuffer<N>, keys: Keys, count: usize) {
+        for _ in 0..count {
+            buffer.push(keys);
+        }
+    }
+
+    #[test]
+    fn just_pressed() {
+        let buffer = frames(&[Keys::A, Keys::A | Keys::B]);
+
+        assert_eq!(buffer.just_pressed(0), Keys::B);
+        assert_eq!(buffer.just_pressed(1), Keys::A);
+        // The neutral frame is the oldest.
+        assert_eq!(buffer.just_pressed(2), NONE);
+    }
+
+    #[test]
+    fn just_pressed_after_clear() {
+        let mut buffer = frames(&[]);
+        buffer.clear();
+        buffer.push(Keys::A);
+
+        // There is no earlier frame to compare with.
+        assert_eq!(buffer.just_pressed(0), NONE);
+        const PRESS: Command = Command::new(&[Step::press(Keys::A)]);
+        assert!(!buffer.matches(&PRESS, Facing::Right));
+
+        buffer.push(NONE);
+        buffer.push(Keys::A);
+        assert_eq!(buffer.just_pressed(0), Keys::A);
+    }
+
+    #[test]
+    fn oldest_frame() {
+        let mut buffer = InputBuffer::<4>::new();
+        hold(&mut buffer, Keys::A, 6);
+
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.keys(3), Keys::A);
+        assert_eq!(buffer.just_pressed(3), NONE);
+    }
+
+    #[test]
+    fn fireball() {
+        let buffer = frames(&[Keys::DOWN, DOWN_RIGHT, Keys::RIGHT | Keys::A]);
+
+        assert!(buffer.matches(&FIREBALL, Facing::Right));
+        assert!(!buffer.matches(&FIREBALL, Facing::Left));
+        assert_eq!(buffer.matched(&[DASH, FIREBALL], Facing::Right).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn fireball_out_of_order() {
+        let buffer = frames(&[DOWN_RIGHT, Keys::DOWN, Keys::RIGHT | Keys::A]);
+
+        assert!(!buffer.matches(&FIREBALL, Facing::Right));
+    }
+
+    #[test]
+    fn mirrored() {
+        let buffer = frames(&[Keys::DOWN, DOWN_LEFT, Keys::LEFT | Keys::A]);
+
+        assert!(buffer.matches(&FIREBALL, Facing::Left));
+        assert!(!buffer.matches(&FIREBALL, Facing::Right));
+    }
+
+    #[test]
+    fn held_button() {
+        let mut buffer = frames(&[Keys::DOWN, DOWN_RIGHT, Keys::RIGHT | Keys::A]);
+        assert!(buffer.matches(&FIREBALL, Facing::Right));
+
+        // Holding the button does not complete the command again.
+        for _ in 0..3 {
+            buffer.push(Keys::RIGHT | Keys::A);
+            assert!(!buffer.matches(&FIREBALL, Facing::Right));
+        }
+    }
+
+    #[test]
+    fn leniency() {
+        // Each step may be up to 10 frames after the one before it.
+        let mut buffer = frames(&[Keys::DOWN]);
+        hold(&mut buffer, NONE, 9);
+        buffer.push(DOWN_RIGHT);
+        hold(&mut buffer, NONE, 9);
+        buffer.push(Keys::RIGHT | Keys::A);
+        assert!(buffer.matches(&FIREBALL, Facing::Right));
+
+        let mut buffer = frames(&[Keys::DOWN]);
+        hold(&mut buffer, NONE, 10);
+        buffer.push(DOWN_RIGHT);
+        buffer.push(Keys::RIGHT | Keys::A);
+        assert!(!buffer.matches(&FIREBALL, Facing::Right));
+
+        // A command can be made stricter.
+        let strict = FIREBALL.with_leniency(2);
+        let mut buffer = frames(&[Keys::DOWN, DOWN_RIGHT]);
+        hold(&mut buffer, NONE, 2);
+        buffer.push(Keys::RIGHT | Keys::A);
+        assert!(!buffer.matches(&strict, Facing::Right));
+        assert!(buffer.matches(&FIREBALL, Facing::Right));
+    }
+
+    #[test]
+    fn window() {
+        // Within the leniency of each step, but 20 frames from first to last
+        let mut buffer = frames(&[Keys::DOWN]);
+        hold(&mut buffer, NONE, 9);
+        buffer.push(DOWN_RIGHT);
+        hold(&mut buffer, NONE, 9);
+        buffer.push(Keys::RIGHT | Keys::A);
+
+        assert!(buffer.matches(&FIREBALL.with_window(20), Facing::Right));
+        assert!(!buffer.matches(&FIREBALL.with_window(19), Facing::Right));
+    }
+
+    #[test]
+    fn charge() {
+        let mut buffer = frames(&[]);
+        hold(&mut buffer, Keys::LEFT, 40);
+        buffer.push(Keys::RIGHT | Keys::B);
+        assert!(buffer.matches(&DASH, Facing::Right));
+
+        let mut buffer = frames(&[]);
+        hold(&mut buffer, Keys::LEFT, 39);
+        buffer.push(Keys::RIGHT | Keys::B);
+        assert!(!buffer.matches(&DASH, Facing::Right));
+    }
+
+    #[test]
+    fn charge_diagonal() {
+        // Down-back charges back, and the charge may end a few frames early.
+        let mut buffer = frames(&[]);
+        hold(&mut buffer, DOWN_LEFT, 40);
+        hold(&mut buffer, NONE, 3);
+        buffer.push(Keys::RIGHT | Keys::B);
+        assert!(buffer.matches(&DASH, Facing::Right));
+
+        // Facing left, back is right.
+        let mut buffer = frames(&[]);
+        hold(&mut buffer, Keys::RIGHT, 40);
+        buffer.push(Keys::LEFT | Keys::B);
+        assert!(buffer.matches(&DASH, Facing::Left));
+        assert!(!buffer.matches(&DASH, Facing::Right));
+    }
+
+    #[test]
+    fn charge_longer_than_buffer() {
+        let mut buffer = InputBuffer::<32>::new();
+        hold(&mut buffer, Keys::LEFT, 40);
+        buffer.push(Keys::RIGHT | Keys::B);
+
+        assert!(!buffer.matches(&DASH, Facing::Right));
+    }
+}
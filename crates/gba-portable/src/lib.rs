@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Parts of the `gba` crate that do not depend on the hardware.
+//!
+//! This crate builds for any target, so its logic can be tested on the host
+//! with `scripts/test-host.sh`. The `gba` crate re-exports everything here;
+//! games should not need to depend on it directly.
+
+#![cfg_attr(not(test), no_std)]
+#![deny(clippy::borrow_as_ptr)]
+#![deny(clippy::cast_lossless)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::panic)]
+#![deny(clippy::unwrap_used)]
+
+pub mod display;
+pub mod input;
+pub mod save;
+pub mod sound;
+pub mod timer;
@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Save memory.
+
+pub mod flash;
+
+/// Errors from accessing save memory.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SaveError {
+    /// The access is past the end of save memory.
+    OutOfRange,
+    /// The Flash chip was not recognized.
+    UnknownChip,
+    /// A Flash write or erase did not complete in time.
+    Timeout,
+}
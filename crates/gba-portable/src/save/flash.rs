@@ -0,0 +1,754 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Flash save memory.
+//!
+//! Flash is 64 KiB or 128 KiB of memory that is read directly, but written
+//! and erased with command sequences. Bits can only be cleared by writing, so
+//! a sector must be erased (set to 0xFF) before it is written again, except
+//! on Atmel chips, which erase each 128-byte page as it is written. 128 KiB
+//! chips are accessed as two banks of 64 KiB.
+//!
+//! The driver accesses the chip through a [`Bus`]. With the `simulator`
+//! feature, save code can be run on the host against a `Simulator`.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbacartbackupflashrom>
+
+use super::SaveError;
+
+/// The size of a bank of Flash.
+pub const BANK_SIZE: usize = 0x1_0000;
+
+/// The number of scanlines, including VBlank.
+const LINES: u32 = 228;
+/// The number of scanlines in a millisecond, rounded up.
+const LINES_PER_MS: u32 = 14;
+
+/// The address of the first write of a command.
+const COMMAND_1: usize = 0x5555;
+/// The address of the second write of a command.
+const COMMAND_2: usize = 0x2AAA;
+
+const CMD_ERASE_CHIP: u8 = 0x10;
+const CMD_ERASE_SECTOR: u8 = 0x30;
+const CMD_ERASE: u8 = 0x80;
+const CMD_ENTER_ID: u8 = 0x90;
+const CMD_WRITE: u8 = 0xA0;
+const CMD_BANK: u8 = 0xB0;
+const CMD_EXIT_ID: u8 = 0xF0;
+
+/// Access to the Flash chip.
+pub trait Bus {
+    /// Reads the byte at `offset` in the current bank.
+    fn read(&mut self, offset: usize) -> u8;
+
+    /// Writes the byte at `offset` in the current bank.
+    fn write(&mut self, offset: usize, value: u8);
+
+    /// Returns the current scanline (0-227), used to measure timeouts.
+    fn scanline(&mut self) -> u16;
+
+    /// Runs `f` without being interrupted.
+    ///
+    /// Used for Atmel page writes, which fail if there is a pause between
+    /// bytes.
+    fn critical<F: FnOnce(&mut Self)>(&mut self, f: F)
+    where
+        Self: Sized,
+    {
+        f(self);
+    }
+}
+
+/// Flash chip manufacturers.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Manufacturer {
+    Atmel = 0x1F,
+    Panasonic = 0x32,
+    Sanyo = 0x62,
+    Sst = 0xBF,
+    Macronix = 0xC2,
+}
+
+/// Supported Flash chips.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Chip {
+    /// Atmel AT29LV512, 64 KiB
+    At29lv512,
+    /// Panasonic MN63F805MNP, 64 KiB
+    Mn63f805mnp,
+    /// SST SST39VF512, 64 KiB
+    Sst39vf512,
+    /// Macronix MX29L512, 64 KiB
+    Mx29l512,
+    /// Macronix MX29L010, 128 KiB
+    Mx29l010,
+    /// Sanyo LE26FV10N1TS, 128 KiB
+    Le26fv10n1ts,
+}
+
+impl Chip {
+    /// Returns the chip with the ID read from Flash, where the low byte is
+    /// the manufacturer and the high byte is the device.
+    pub const fn from_id(id: u16) -> Option<Self> {
+        match id {
+            0x3D1F => Some(Self::At29lv512),
+            0x1B32 => Some(Self::Mn63f805mnp),
+            0xD4BF => Some(Self::Sst39vf512),
+            0x1CC2 => Some(Self::Mx29l512),
+            0x09C2 => Some(Self::Mx29l010),
+            0x1362 => Some(Self::Le26fv10n1ts),
+            _ => None,
+        }
+    }
+
+    /// The ID of the chip, where the low byte is the manufacturer and the
+    /// high byte is the device.
+    pub const fn id(self) -> u16 {
+        match self {
+            Self::At29lv512 => 0x3D1F,
+            Self::Mn63f805mnp => 0x1B32,
+            Self::Sst39vf512 => 0xD4BF,
+            Self::Mx29l512 => 0x1CC2,
+            Self::Mx29l010 => 0x09C2,
+            Self::Le26fv10n1ts => 0x1362,
+        }
+    }
+
+    pub const fn manufacturer(self) -> Manufacturer {
+        match self {
+            Self::At29lv512 => Manufacturer::Atmel,
+            Self::Mn63f805mnp => Manufacturer::Panasonic,
+            Self::Sst39vf512 => Manufacturer::Sst,
+            Self::Mx29l512 | Self::Mx29l010 => Manufacturer::Macronix,
+            Self::Le26fv10n1ts => Manufacturer::Sanyo,
+        }
+    }
+
+    /// The size of the chip, in bytes.
+    pub const fn size(self) -> usize {
+        match self {
+            Self::Mx29l010 | Self::Le26fv10n1ts => 2 * BANK_SIZE,
+            _ => BANK_SIZE,
+        }
+    }
+
+    /// The size of each sector, in bytes.
+    ///
+    /// For Atmel chips, this is the size of the page written at once.
+    pub const fn sector_size(self) -> usize {
+        match self {
+            Self::At29lv512 => 128,
+            _ => 0x1000,
+        }
+    }
+
+    /// Checks if the chip writes whole pages instead of single bytes.
+    pub const fn is_paged(self) -> bool {
+        matches!(self, Self::At29lv512)
+    }
+
+    /// The timeouts of writing, erasing a sector, and erasing the chip, in
+    /// milliseconds.
+    const fn timeouts(self) -> (u32, u32, u32) {
+        match self {
+            Self::At29lv512 => (40, 40, 40),
+            Self::Mn63f805mnp => (10, 500, 500),
+            Self::Sst39vf512 => (10, 40, 200),
+            Self::Mx29l512 | Self::Mx29l010 | Self::Le26fv10n1ts => (10, 2000, 2000),
+        }
+    }
+}
+
+/// A Flash chip driver.
+#[derive(Debug)]
+pub struct Flash<B: Bus> {
+    bus: B,
+    chip: Chip,
+    size: usize,
+    bank: usize,
+}
+
+impl<B: Bus> Flash<B> {
+    /// Identifies the chip on `bus`.
+    pub fn detect(mut bus: B) -> Result<Self, SaveError> {
+        command(&mut bus, CMD_ENTER_ID);
+        let manufacturer = bus.read(0);
+        let device = bus.read(1);
+        command(&mut bus, CMD_EXIT_ID);
+
+        let id = u16::from_le_bytes([manufacturer, device]);
+        let chip = Chip::from_id(id).ok_or(SaveError::UnknownChip)?;
+
+        let mut flash = Self {
+            bus,
+            chip,
+            size: chip.size(),
+            bank: 0,
+        };
+        if chip.size() > BANK_SIZE {
+            flash.select_bank(0);
+        }
+
+        Ok(flash)
+    }
+
+    /// Limits Flash to its first `size` bytes, such as for a game that uses
+    /// 64 KiB on a 128 KiB chip.
+    ///
+    /// Returns an error if the chip is smaller than `size`.
+    pub fn with_size(mut self, size: usize) -> Result<Self, SaveError> {
+        if size > self.chip.size() {
+            return Err(SaveError::OutOfRange);
+        }
+
+        self.size = size;
+        Ok(self)
+    }
+
+    /// The detected chip.
+    pub const fn chip(&self) -> Chip {
+        self.chip
+    }
+
+    /// The usable size of Flash, in bytes.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Releases the bus.
+    pub fn into_inner(self) -> B {
+        self.bus
+    }
+
+    /// Reads the bytes at `offset` into `buf`.
+    pub fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), SaveError> {
+        self.check(offset, buf.len())?;
+
+        for (i, byte) in buf.iter_mut().enumerate() {
+            let address = self.address(offset + i);
+            *byte = self.bus.read(address);
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the bytes at `offset` match `data`.
+    pub fn verify(&mut self, offset: usize, data: &[u8]) -> Result<bool, SaveError> {
+        self.check(offset, data.len())?;
+
+        for (i, &byte) in data.iter().enumerate() {
+            let address = self.address(offset + i);
+            if self.bus.read(address) != byte {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Erases the sector containing `offset`, setting all of its bytes to
+    /// 0xFF.
+    ///
+    /// Sectors are [`Chip::sector_size()`] bytes: 4 KiB, or a 128-byte page
+    /// on Atmel chips. To erase a range, call this at each multiple of the
+    /// sector size within it.
+    pub fn erase_sector(&mut self, offset: usize) -> Result<(), SaveError> {
+        let sector_size = self.chip.sector_size();
+        let offset = offset - (offset % sector_size);
+        self.check(offset, sector_size)?;
+
+        if self.chip.is_paged() {
+            let page = [0xFF; 128];
+            return self.write_page(offset, &page);
+        }
+
+        let address = self.address(offset);
+        command(&mut self.bus, CMD_ERASE);
+        self.bus.write(COMMAND_1, 0xAA);
+        self.bus.write(COMMAND_2, 0x55);
+        self.bus.write(address, CMD_ERASE_SECTOR);
+
+        let (_, timeout, _) = self.chip.timeouts();
+        self.poll(address, 0xFF, timeout)
+    }
+
+    /// Erases the whole chip, setting all bytes to 0xFF.
+    pub fn erase_chip(&mut self) -> Result<(), SaveError> {
+        command(&mut self.bus, CMD_ERASE);
+        command(&mut self.bus, CMD_ERASE_CHIP);
+
+        let (_, _, timeout) = self.chip.timeouts();
+        self.poll(0, 0xFF, timeout)
+    }
+
+    /// Writes `data` at `offset`.
+    ///
+    /// Except on Atmel chips, the bytes must have been erased first.
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        self.check(offset, data.len())?;
+
+        if self.chip.is_paged() {
+            return self.write_paged(offset, data);
+        }
+
+        let (timeout, ..) = self.chip.timeouts();
+        for (i, &byte) in data.iter().enumerate() {
+            let address = self.address(offset + i);
+            command(&mut self.bus, CMD_WRITE);
+            self.bus.write(address, byte);
+            self.poll(address, byte, timeout)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `data` at `offset` on a paged chip, keeping the other bytes of
+    /// each page.
+    fn write_paged(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        let page_size = self.chip.sector_size();
+        let mut page = [0; 128];
+        let mut written = 0;
+
+        while written < data.len() {
+            let position = offset + written;
+            let start = position - (position % page_size);
+            let skip = position - start;
+            let len = (page_size - skip).min(data.len() - written);
+
+            self.read(start, &mut page)?;
+            page[skip..(skip + len)].copy_from_slice(&data[written..(written + len)]);
+            self.write_page(start, &page)?;
+
+            written += len;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a whole page on a paged chip.
+    fn write_page(&mut self, offset: usize, page: &[u8; 128]) -> Result<(), SaveError> {
+        let address = self.address(offset);
+        self.bus.critical(|bus| {
+            command(bus, CMD_WRITE);
+            for (i, &byte) in page.iter().enumerate() {
+                bus.write(address + i, byte);
+            }
+        });
+
+        let (timeout, ..) = self.chip.timeouts();
+        self.poll(address + page.len() - 1, page[page.len() - 1], timeout)
+    }
+
+    /// Checks that `len` bytes at `offset` are within Flash.
+    fn check(&self, offset: usize, len: usize) -> Result<(), SaveError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size => Ok(()),
+            _ => Err(SaveError::OutOfRange),
+        }
+    }
+
+    /// Selects the bank of `offset`, returning the address within the bank.
+    fn address(&mut self, offset: usize) -> usize {
+        let bank = offset / BANK_SIZE;
+        if bank != self.bank {
+            self.select_bank(bank);
+        }
+
+        offset % BANK_SIZE
+    }
+
+    fn select_bank(&mut self, bank: usize) {
+        command(&mut self.bus, CMD_BANK);
+        self.bus.write(0, bank as u8);
+        self.bank = bank;
+    }
+
+    /// Waits until the byte at `address` reads as `value`, which signals the
+    /// end of a write or erase.
+    fn poll(&mut self, address: usize, value: u8, timeout_ms: u32) -> Result<(), SaveError> {
+        let limit = timeout_ms * LINES_PER_MS;
+        let mut elapsed = 0;
+        let mut last = u32::from(self.bus.scanline());
+
+        loop {
+            if self.bus.read(address) == value {
+                return Ok(());
+            }
+
+            let line = u32::from(self.bus.scanline());
+            elapsed += (line + LINES - last) % LINES;
+            last = line;
+
+            if elapsed > limit {
+                // Reset the chip, which Macronix chips require after a timeout.
+                command(&mut self.bus, CMD_EXIT_ID);
+                return Err(SaveError::Timeout);
+            }
+        }
+    }
+}
+
+/// Writes the unlock sequence and a command.
+fn command<B: Bus>(bus: &mut B, cmd: u8) {
+    bus.write(COMMAND_1, 0xAA);
+    bus.write(COMMAND_2, 0x55);
+    bus.write(COMMAND_1, cmd);
+}
+
+/// The state of the command sequence of a [`Simulator`].
+#[cfg(any(test, feature = "simulator"))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SimState {
+    Ready,
+    Unlock1,
+    Unlock2,
+    EraseReady,
+    EraseUnlock1,
+    EraseUnlock2,
+    /// Writing a byte, or the number of bytes written to an Atmel page.
+    Write(usize),
+    Bank,
+}
+
+/// A simulated Flash chip, for running save code on the host.
+///
+/// Writes and erases take a configurable number of reads to complete, during
+/// which the chip returns the complement of the expected data, as real chips
+/// do. Each call to [`Bus::scanline()`] advances time by one scanline.
+#[cfg(any(test, feature = "simulator"))]
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    chip: Chip,
+    memory: [u8; 2 * BANK_SIZE],
+    state: SimState,
+    id_mode: bool,
+    bank: usize,
+    /// Reads until the current operation completes
+    busy: u32,
+    /// Reads each operation takes
+    delay: u32,
+    /// Operations never complete
+    stuck: bool,
+    line: u16,
+}
+
+#[cfg(any(test, feature = "simulator"))]
+impl Simulator {
+    /// Returns an erased chip.
+    pub fn new(chip: Chip) -> Self {
+        Self {
+            chip,
+            memory: [0xFF; 2 * BANK_SIZE],
+            state: SimState::Ready,
+            id_mode: false,
+            bank: 0,
+            busy: 0,
+            delay: 0,
+            stuck: false,
+            line: 0,
+        }
+    }
+
+    /// Sets the number of reads each write or erase takes to complete.
+    pub fn with_delay(self, reads: u32) -> Self {
+        Self { delay: reads, ..self }
+    }
+
+    /// Sets if writes and erases never complete, to test timeouts.
+    pub fn with_stuck(self, stuck: bool) -> Self {
+        Self { stuck, ..self }
+    }
+
+    /// The contents of the chip.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory[..self.chip.size()]
+    }
+
+    fn index(&self, offset: usize) -> usize {
+        ((self.bank * BANK_SIZE) + (offset % BANK_SIZE)) % self.chip.size()
+    }
+
+    fn start_busy(&mut self) {
+        self.busy = self.delay;
+    }
+}
+
+#[cfg(any(test, feature = "simulator"))]
+impl Bus for Simulator {
+    fn read(&mut self, offset: usize) -> u8 {
+        if self.id_mode && offset < 2 {
+            return self.chip.id().to_le_bytes()[offset];
+        }
+
+        let value = self.memory[self.index(offset)];
+        if self.stuck || self.busy > 0 {
+            self.busy = self.busy.saturating_sub(1);
+            return !value;
+        }
+
+        value
+    }
+
+    fn write(&mut self, offset: usize, value: u8) {
+        let offset = offset % BANK_SIZE;
+
+        self.state = match (self.state, offset, value) {
+            (SimState::Write(count), ..) if self.chip.is_paged() => {
+                let index = self.index(offset);
+                self.memory[index] = value;
+                if count + 1 < self.chip.sector_size() {
+                    SimState::Write(count + 1)
+                } else {
+                    self.start_busy();
+                    SimState::Ready
+                }
+            }
+            (SimState::Write(_), ..) => {
+                let index = self.index(offset);
+                self.memory[index] &= value;
+                self.start_busy();
+                SimState::Ready
+            }
+            (SimState::Bank, 0, _) => {
+                if self.chip.size() > BANK_SIZE {
+                    self.bank = usize::from(value & 1);
+                }
+                SimState::Ready
+            }
+            (_, _, CMD_EXIT_ID) => {
+                self.id_mode = false;
+                SimState::Ready
+            }
+            (SimState::Ready, COMMAND_1, 0xAA) => SimState::Unlock1,
+            (SimState::Unlock1, COMMAND_2, 0x55) => SimState::Unlock2,
+            (SimState::Unlock2, COMMAND_1, CMD_ENTER_ID) => {
+                self.id_mode = true;
+                SimState::Ready
+            }
+            (SimState::Unlock2, COMMAND_1, CMD_ERASE) => SimState::EraseReady,
+            (SimState::Unlock2, COMMAND_1, CMD_WRITE) => SimState::Write(0),
+            (SimState::Unlock2, COMMAND_1, CMD_BANK) => SimState::Bank,
+            (SimState::EraseReady, COMMAND_1, 0xAA) => SimState::EraseUnlock1,
+            (SimState::EraseUnlock1, COMMAND_2, 0x55) => SimState::EraseUnlock2,
+            (SimState::EraseUnlock2, COMMAND_1, CMD_ERASE_CHIP) => {
+                let size = self.chip.size();
+                self.memory[..size].fill(0xFF);
+                self.start_busy();
+                SimState::Ready
+            }
+            (SimState::EraseUnlock2, _, CMD_ERASE_SECTOR) => {
+                let size = self.chip.sector_size();
+                let start = self.index(offset - (offset % size));
+                self.memory[start..(start + size)].fill(0xFF);
+                self.start_busy();
+                SimState::Ready
+            }
+            _ => SimState::Ready,
+        };
+    }
+
+    fn scanline(&mut self) -> u16 {
+        self.line = (self.line + 1) % (LINES as u16);
+        self.line
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const CHIPS: [Chip; 6] = [
+        Chip::At29lv512,
+        Chip::Mn63f805mnp,
+        Chip::Sst39vf512,
+        Chip::Mx29l512,
+        Chip::Mx29l010,
+        Chip::Le26fv10n1ts,
+    ];
+
+    /// A cartridge without Flash, where the bus reads as open.
+    struct Open;
+
+    impl Bus for Open {
+        fn read(&mut self, _offset: usize) -> u8 {
+            0xFF
+        }
+
+        fn write(&mut self, _offset: usize, _value: u8) {
+        }
+
+        fn scanline(&mut self) -> u16 {
+            0
+        }
+    }
+
+    fn simulated(chip: Chip) -> Flash<Simulator> {
+        Flash::detect(Simulator::new(chip).with_delay(3)).unwrap()
+    }
+
+    /// Bytes that differ at each offset.
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + 1) as u8).collect()
+    }
+
+    #[test]
+    fn detect() {
+        for chip in CHIPS {
+            let flash = simulated(chip);
+            assert_eq!(flash.chip(), chip);
+            assert_eq!(flash.size(), chip.size());
+            assert_eq!(Chip::from_id(chip.id()), Some(chip));
+        }
+
+        let sizes = CHIPS.map(Chip::size);
+        let (k64, k128) = (BANK_SIZE, 2 * BANK_SIZE);
+        assert_eq!(sizes, [k64, k64, k64, k64, k128, k128]);
+    }
+
+    #[test]
+    fn detect_unknown() {
+        assert_eq!(Flash::detect(Open).err(), Some(SaveError::UnknownChip));
+    }
+
+    #[test]
+    fn with_size() {
+        let flash = simulated(Chip::Mx29l010).with_size(BANK_SIZE).unwrap();
+        assert_eq!(flash.size(), BANK_SIZE);
+
+        let flash = simulated(Chip::Mx29l512).with_size(2 * BANK_SIZE);
+        assert_eq!(flash.err(), Some(SaveError::OutOfRange));
+    }
+
+    #[test]
+    fn write_and_read() {
+        for chip in CHIPS {
+            let mut flash = simulated(chip);
+            let data = pattern(300);
+            flash.write(0x2010, &data).unwrap();
+
+            let mut buf = [0; 300];
+            flash.read(0x2010, &mut buf).unwrap();
+            assert_eq!(buf[..], data[..], "{chip:?}");
+            assert!(flash.verify(0x2010, &data).unwrap());
+        }
+    }
+
+    #[test]
+    fn write_clears_bits() {
+        // Without erasing, writes can only clear bits, so the byte never
+        // reads as written.
+        let mut flash = simulated(Chip::Sst39vf512);
+        flash.write(0, &[0x0F]).unwrap();
+        assert_eq!(flash.write(0, &[0xF0]), Err(SaveError::Timeout));
+        assert_eq!(flash.into_inner().memory()[0], 0x00);
+    }
+
+    #[test]
+    fn erase_sector() {
+        for chip in CHIPS {
+            let mut flash = simulated(chip);
+            let size = chip.sector_size();
+            flash.write(0, &pattern(2 * size)).unwrap();
+
+            // Any offset in the second sector erases all of it.
+            flash.erase_sector(size + 5).unwrap();
+
+            let memory = flash.into_inner();
+            let memory = memory.memory();
+            assert_eq!(memory[..size], pattern(size)[..], "{chip:?}");
+            assert!(memory[size..(2 * size)].iter().all(|&b| b == 0xFF), "{chip:?}");
+        }
+    }
+
+    #[test]
+    fn erase_sector_units() {
+        assert_eq!(Chip::At29lv512.sector_size(), 128);
+        assert_eq!(Chip::Mx29l512.sector_size(), 0x1000);
+
+        let mut flash = simulated(Chip::Mx29l512);
+        let end = flash.size();
+        assert!(flash.erase_sector(end - 1).is_ok());
+        assert_eq!(flash.erase_sector(end), Err(SaveError::OutOfRange));
+    }
+
+    #[test]
+    fn erase_sector_second_bank() {
+        let mut flash = simulated(Chip::Le26fv10n1ts);
+        flash.write(BANK_SIZE, &[0]).unwrap();
+        flash.write(0, &[0]).unwrap();
+        flash.erase_sector(BANK_SIZE).unwrap();
+
+        let memory = flash.into_inner();
+        assert_eq!(memory.memory()[0], 0);
+        assert_eq!(memory.memory()[BANK_SIZE], 0xFF);
+    }
+
+    #[test]
+    fn erase_chip() {
+        for chip in CHIPS {
+            let mut flash = simulated(chip);
+            flash.write(0, &pattern(256)).unwrap();
+            flash.write(chip.size() - 256, &pattern(256)).unwrap();
+            flash.erase_chip().unwrap();
+
+            let memory = flash.into_inner();
+            assert!(memory.memory().iter().all(|&b| b == 0xFF), "{chip:?}");
+        }
+    }
+
+    #[test]
+    fn atmel_pages() {
+        let mut flash = simulated(Chip::At29lv512);
+        flash.write(0, &pattern(384)).unwrap();
+
+        // Rewriting across three pages keeps the rest of each page.
+        let data = [0x5A; 200];
+        flash.write(100, &data).unwrap();
+
+        let mut expected = pattern(384);
+        expected[100..300].copy_from_slice(&data);
+        assert_eq!(flash.into_inner().memory()[..384], expected[..]);
+    }
+
+    #[test]
+    fn cross_bank() {
+        for chip in [Chip::Mx29l010, Chip::Le26fv10n1ts] {
+            let mut flash = simulated(chip);
+            let data = pattern(64);
+            flash.write(BANK_SIZE - 32, &data).unwrap();
+
+            let mut buf = [0; 64];
+            flash.read(BANK_SIZE - 32, &mut buf).unwrap();
+            assert_eq!(buf[..], data[..], "{chip:?}");
+
+            // The bytes are split across the end of the first bank and the
+            // start of the second, not wrapped within one bank.
+            let memory = flash.into_inner();
+            assert_eq!(memory.memory()[(BANK_SIZE - 32)..(BANK_SIZE + 32)], data[..]);
+            assert!(memory.memory()[..32].iter().all(|&b| b == 0xFF));
+        }
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut flash = simulated(Chip::Mx29l512);
+        let mut buf = [0; 2];
+        assert_eq!(flash.read(BANK_SIZE - 1, &mut buf), Err(SaveError::OutOfRange));
+        assert_eq!(flash.write(BANK_SIZE, &[0]), Err(SaveError::OutOfRange));
+        assert_eq!(flash.write(usize::MAX, &[0]), Err(SaveError::OutOfRange));
+    }
+
+    #[test]
+    fn timeout() {
+        for chip in CHIPS {
+            let sim = Simulator::new(chip).with_stuck(true);
+            let mut flash = Flash::detect(sim).unwrap();
+
+            assert_eq!(flash.write(0, &[0]), Err(SaveError::Timeout), "{chip:?}");
+            assert_eq!(flash.erase_sector(0), Err(SaveError::Timeout), "{chip:?}");
+            assert_eq!(flash.erase_chip(), Err(SaveError::Timeout), "{chip:?}");
+        }
+    }
+}
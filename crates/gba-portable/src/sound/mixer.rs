@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Software mixer.
+//!
+//! [`Voices`] tracks the sounds being played and mixes them into stereo
+//! accumulators with an inner loop given as a [`MixFn`]. On the GBA this is
+//! the ARM code in IWRAM; elsewhere it is [`mix_stereo_reference()`], which
+//! gives the same output.
+
+/// The maximum number of voices mixed at once.
+pub const MAX_VOICES: usize = 8;
+/// The maximum volume of a voice, which plays samples unchanged.
+pub const MAX_VOLUME: u8 = 64;
+/// A pan with equal volume on both channels.
+pub const PAN_CENTER: u8 = 64;
+/// The number of fractional bits in sample positions and steps.
+pub const FRACTION_BITS: u32 = 12;
+
+/// The inner loop of the mixer, with the arguments of
+/// [`mix_stereo_reference()`].
+pub type MixFn = unsafe fn(&mut [[i32; 2]], *const i8, u32, u32, i32, i32) -> u32;
+
+/// Samples to be played by a voice.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Sound {
+    data: &'static [i8],
+    rate: u32,
+    loop_start: Option<usize>,
+}
+
+impl Sound {
+    /// Creates a sound from 8-bit signed samples recorded at `rate` Hz.
+    pub const fn new(data: &'static [i8], rate: u32) -> Self {
+        Self { data, rate, loop_start: None }
+    }
+
+    /// Loops the sound from the sample at `start` after reaching the end.
+    pub const fn with_loop(mut self, start: usize) -> Self {
+        self.loop_start = Some(start);
+        self
+    }
+}
+
+/// A voice being played by the mixer.
+///
+/// Once the voice ends, the ID no longer refers to any voice, even if its
+/// slot is reused.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct VoiceId {
+    index: u8,
+    generation: u8,
+}
+
+/// The playback state of a sound.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Voice {
+    sound: Sound,
+    /// Position in the samples, with 12 fractional bits
+    pos: u32,
+    /// Samples to advance per output sample, with 12 fractional bits
+    step: u32,
+    volume: u8,
+    pan: u8,
+}
+
+impl Voice {
+    /// The sound being played.
+    pub const fn sound(&self) -> Sound {
+        self.sound
+    }
+
+    /// The volume (0-64).
+    pub const fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Sets the volume (0-64).
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(MAX_VOLUME);
+    }
+
+    /// The pan, from 0 (left) to 128 (right).
+    pub const fn pan(&self) -> u8 {
+        self.pan
+    }
+
+    /// Sets the pan, from 0 (left) to 128 (right).
+    pub fn set_pan(&mut self, pan: u8) {
+        self.pan = pan.min(2 * PAN_CENTER);
+    }
+
+    /// The samples advanced per output sample, with 12 fractional bits.
+    pub const fn step(&self) -> u32 {
+        self.step
+    }
+
+    /// Sets the playback rate relative to the recorded rate, with 12
+    /// fractional bits (i.e., 0x1000 is the original pitch).
+    pub fn set_pitch(&mut self, pitch: u32, output_rate: u32) {
+        let step = (u64::from(self.sound.rate) * u64::from(pitch)) / u64::from(output_rate);
+        self.step = (step as u32).max(1);
+    }
+
+    /// Sets the playback rate, in Hz, ignoring the recorded rate.
+    pub fn set_frequency(&mut self, hz: u32, output_rate: u32) {
+        let step = (u64::from(hz) << FRACTION_BITS) / u64::from(output_rate);
+        self.step = (step as u32).max(1);
+    }
+
+    /// The sample being played.
+    pub const fn position(&self) -> usize {
+        (self.pos >> FRACTION_BITS) as usize
+    }
+
+    /// Moves playback to a sample. Positions past the end loop or end the
+    /// sound as if played up to there.
+    pub fn set_position(&mut self, sample: usize) {
+        self.pos = (sample as u32) << FRACTION_BITS;
+    }
+
+    fn volumes(&self) -> (i32, i32) {
+        let volume = i32::from(self.volume);
+        let center = i32::from(PAN_CENTER);
+        let pan = i32::from(self.pan);
+
+        let left = volume * (2 * center - pan).min(center) / center;
+        let right = volume * pan.min(center) / center;
+        (left, right)
+    }
+
+    /// Mixes the voice into the accumulators, returning `false` once the
+    /// sound has ended.
+    fn mix(&mut self, acc: &mut [[i32; 2]], mix: MixFn) -> bool {
+        let data = self.sound.data;
+        let end = (data.len() as u32) << FRACTION_BITS;
+        let (lvol, rvol) = self.volumes();
+
+        let mut done = 0;
+        while done < acc.len() {
+            if self.pos >= end {
+                match self.sound.loop_start {
+                    Some(start) if start < data.len() => {
+                        let start = (start as u32) << FRACTION_BITS;
+                        let length = end - start;
+                        self.pos = start + (self.pos - end) % length;
+                    }
+                    _ => return false,
+                }
+            }
+
+            // Mix up to the end of the samples.
+            let available = (end - self.pos).div_ceil(self.step) as usize;
+            let count = available.min(acc.len() - done);
+            let acc = &mut acc[done..(done + count)];
+
+            // The count stops before the end of the samples.
+            self.pos = unsafe { mix(acc, data.as_ptr(), self.pos, self.step, lvol, rvol) };
+            done += count;
+        }
+
+        true
+    }
+}
+
+/// The voices of a mixer.
+#[derive(Debug)]
+pub struct Voices {
+    rate: u32,
+    voices: [Option<Voice>; MAX_VOICES],
+    /// Incremented each time a slot is used, to detect stale voice IDs
+    generations: [u8; MAX_VOICES],
+}
+
+impl Voices {
+    /// Creates voices for output at `rate` Hz, none of which are playing.
+    pub const fn new(rate: u32) -> Self {
+        Self {
+            rate,
+            voices: [None; MAX_VOICES],
+            generations: [0; MAX_VOICES],
+        }
+    }
+
+    /// The output sample rate, in Hz.
+    pub const fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    /// Starts playing a sound at full volume, centered, and at its original
+    /// pitch.
+    ///
+    /// Returns `None` if all voices are in use.
+    pub fn play(&mut self, sound: Sound) -> Option<VoiceId> {
+        let index = self.voices.iter().position(Option::is_none)?;
+
+        let mut voice = Voice {
+            sound,
+            pos: 0,
+            step: 1 << FRACTION_BITS,
+            volume: MAX_VOLUME,
+            pan: PAN_CENTER,
+        };
+        voice.set_pitch(1 << FRACTION_BITS, self.rate);
+
+        self.voices[index] = Some(voice);
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        Some(VoiceId {
+            index: index as u8,
+            generation: self.generations[index],
+        })
+    }
+
+    /// Stops a voice.
+    pub fn stop(&mut self, id: VoiceId) {
+        if let Some(slot) = self.slot(&id) {
+            *slot = None;
+        }
+    }
+
+    /// Checks if a voice is still playing.
+    pub fn is_playing(&self, id: &VoiceId) -> bool {
+        let index = usize::from(id.index);
+        self.generations[index] == id.generation && self.voices[index].is_some()
+    }
+
+    /// Returns the state of a voice, to change its volume, pan, or pitch.
+    ///
+    /// Returns `None` if the voice has ended.
+    pub fn voice_mut(&mut self, id: &VoiceId) -> Option<&mut Voice> {
+        self.slot(id)?.as_mut()
+    }
+
+    fn slot(&mut self, id: &VoiceId) -> Option<&mut Option<Voice>> {
+        let index = usize::from(id.index);
+        if self.generations[index] == id.generation {
+            Some(&mut self.voices[index])
+        } else {
+            None
+        }
+    }
+
+    /// Mixes the voices into cleared accumulators with the inner loop `mix`,
+    /// stopping the voices that end.
+    pub fn mix(&mut self, acc: &mut [[i32; 2]], mix: MixFn) {
+        acc.fill([0; 2]);
+
+        for slot in self.voices.iter_mut() {
+            if let Some(voice) = slot {
+                if !voice.mix(acc, mix) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+}
+
+impl AsMut<Voices> for Voices {
+    fn as_mut(&mut self) -> &mut Voices {
+        self
+    }
+}
+
+/// Converts an accumulated value to an output sample.
+pub fn clip(value: i32) -> i8 {
+    (value >> 6).clamp(i32::from(i8::MIN), i32::from(i8::MAX)) as i8
+}
+
+/// The reference implementation of the ARM inner loop in `mixer.S`.
+///
+/// For each accumulator, adds `sample[pos >> 12]` scaled by the left and
+/// right volumes, then advances `pos` by `step`. Returns the new position.
+///
+/// This matches the ARM code bit for bit: samples are read without bounds
+/// checks, and the position and accumulators wrap on overflow.
+///
+/// # Safety
+///
+/// `sample` must be valid for reads at every position reached, as for the
+/// ARM code.
+pub unsafe fn mix_stereo_reference(
+    acc: &mut [[i32; 2]],
+    sample: *const i8,
+    mut pos: u32,
+    step: u32,
+    lvol: i32,
+    rvol: i32,
+) -> u32 {
+    for [left, right] in acc.iter_mut() {
+        // add r8, r2, r3, lsr #12; ldrsb r7, [r8]
+        let value = i32::from(sample.wrapping_add((pos >> FRACTION_BITS) as usize).read());
+        // add r3, r3, r4
+        pos = pos.wrapping_add(step);
+        // mla r8, r7, r5, r8; mla r12, r7, r6, r12
+        *left = value.wrapping_mul(lvol).wrapping_add(*left);
+        *right = value.wrapping_mul(rvol).wrapping_add(*right);
+    }
+
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The expected values are worked out from the instructions of
+    // `gba_mix_stereo`, with 32-bit wrapping arithmetic.
+
+    fn mix(acc: &mut [[i32; 2]], sample: &[i8], pos: u32, step: u32, vol: (i32, i32)) -> u32 {
+        unsafe { mix_stereo_reference(acc, sample.as_ptr(), pos, step, vol.0, vol.1) }
+    }
+
+    #[test]
+    fn unit_step() {
+        let mut acc = [[0; 2]; 4];
+        let pos = mix(&mut acc, &[10, -20, 30, -40], 0, 0x1000, (64, 32));
+
+        assert_eq!(pos, 0x4000);
+        assert_eq!(acc, [[640, 320], [-1280, -640], [1920, 960], [-2560, -1280]]);
+    }
+
+    #[test]
+    fn fractional_step() {
+        let mut acc = [[0; 2]; 5];
+        let pos = mix(&mut acc, &[1, 2, 3], 0x0C00, 0x0800, (1, 2));
+
+        // Positions 0x0C00, 0x1400, 0x1C00, 0x2400, 0x2C00.
+        assert_eq!(pos, 0x3400);
+        assert_eq!(acc, [[1, 2], [2, 4], [2, 4], [3, 6], [3, 6]]);
+    }
+
+    #[test]
+    fn accumulates() {
+        let mut acc = [[100, -100], [7, 8]];
+        let pos = mix(&mut acc, &[5, 6], 0, 0x1000, (3, 4));
+
+        assert_eq!(pos, 0x2000);
+        assert_eq!(acc, [[115, -80], [25, 32]]);
+    }
+
+    #[test]
+    fn sign_extends() {
+        let mut acc = [[0; 2]; 2];
+        mix(&mut acc, &[-128, 127], 0, 0x1000, (64, 64));
+
+        assert_eq!(acc, [[-8192, -8192], [8128, 8128]]);
+    }
+
+    #[test]
+    fn zero_count() {
+        let mut acc: [[i32; 2]; 0] = [];
+        let pos = mix(&mut acc, &[], 0x1234, 0x1000, (64, 64));
+
+        assert_eq!(pos, 0x1234);
+    }
+
+    #[test]
+    fn accumulators_wrap() {
+        let mut acc = [[i32::MAX - 10, i32::MIN]];
+        mix(&mut acc, &[127], 0, 0x1000, (64, -1));
+
+        assert_eq!(acc, [[i32::MIN + 8117, i32::MAX - 126]]);
+    }
+
+    #[test]
+    fn position_wraps() {
+        let mut sample = std::vec![0; 0x10_0000];
+        sample[0xF_FFFF] = 9;
+        sample[0] = 3;
+
+        let mut acc = [[0; 2]; 2];
+        let pos = mix(&mut acc, &sample, 0xFFFF_F000, 0x1000, (1, 1));
+
+        assert_eq!(pos, 0x1000);
+        assert_eq!(acc, [[9, 9], [3, 3]]);
+    }
+
+    #[test]
+    fn reads_past_end() {
+        // A sound of 2 samples followed by other data. The ARM code has no
+        // bounds checks, so positions past the end read whatever follows.
+        let memory = [1, 2, 3, 4];
+        let mut acc = [[0; 2]; 4];
+        let pos = mix(&mut acc, &memory, 0, 0x1000, (1, 1));
+
+        assert_eq!(pos, 0x4000);
+        assert_eq!(acc, [[1, 1], [2, 2], [3, 3], [4, 4]]);
+    }
+}
@@ -0,0 +1,1248 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Tracker music.
+//!
+//! Plays songs converted from MOD, S3M, and XM modules at build time by
+//! `gba::include_tracker!`. Each channel of the song plays on one of the
+//! [`Voices`] of the software mixer, so at most
+//! [`MAX_VOICES`](super::mixer::MAX_VOICES) notes sound at once, shared with
+//! any sound effects.
+//!
+//! Pitch uses Scream Tracker periods, where a period of 1712 plays C-4 at
+//! 8363 Hz. Pitch slides of XM modules with linear frequencies are
+//! approximated with periods.
+
+use self::effect::{
+    BREAK,
+    EXTENDED,
+    FINE_PORTA_DOWN,
+    FINE_PORTA_UP,
+    FINE_VOLUME_DOWN,
+    FINE_VOLUME_UP,
+    JUMP,
+    NOTE_CUT,
+    NOTE_DELAY,
+    OFFSET,
+    PAN,
+    PATTERN_DELAY,
+    PATTERN_LOOP,
+    PORTA_DOWN,
+    PORTA_UP,
+    SET_VOLUME,
+    SPEED,
+    TONE_PORTA,
+    TONE_PORTA_VOLUME,
+    TREMOLO,
+    VIBRATO,
+    VIBRATO_VOLUME,
+    VOLUME_SLIDE,
+};
+use super::mixer::{Sound, VoiceId, Voices, MAX_VOLUME};
+use crate::timer::{CPU_FREQUENCY, FRAME_CYCLES};
+
+/// The maximum number of channels in a song.
+pub const MAX_CHANNELS: usize = 16;
+/// The note value that stops the channel.
+pub const NOTE_OFF: u8 = 0xFF;
+
+/// The sample rate of a sample tuned to play C-4 at period 1712.
+pub const C4_RATE: u32 = 8363;
+/// The periods of the notes of octave 4, from C-4. The period doubles for
+/// each octave below.
+pub const PERIODS: [u32; 12] =
+    [1712, 1616, 1525, 1440, 1357, 1281, 1209, 1141, 1077, 1017, 961, 907];
+
+// Pattern entry flags.
+pub const CHANNEL_MASK: u8 = 0x1F;
+pub const HAS_NOTE: u8 = 1 << 5;
+pub const HAS_VOLUME: u8 = 1 << 6;
+pub const HAS_EFFECT: u8 = 1 << 7;
+
+/// The effects of pattern entries, numbered as in Protracker.
+pub mod effect {
+    pub const ARPEGGIO: u8 = 0x0;
+    pub const PORTA_UP: u8 = 0x1;
+    pub const PORTA_DOWN: u8 = 0x2;
+    pub const TONE_PORTA: u8 = 0x3;
+    pub const VIBRATO: u8 = 0x4;
+    pub const TONE_PORTA_VOLUME: u8 = 0x5;
+    pub const VIBRATO_VOLUME: u8 = 0x6;
+    pub const TREMOLO: u8 = 0x7;
+    pub const PAN: u8 = 0x8;
+    pub const OFFSET: u8 = 0x9;
+    pub const VOLUME_SLIDE: u8 = 0xA;
+    pub const JUMP: u8 = 0xB;
+    pub const SET_VOLUME: u8 = 0xC;
+    pub const BREAK: u8 = 0xD;
+    pub const EXTENDED: u8 = 0xE;
+    pub const SPEED: u8 = 0xF;
+
+    // Extended effects, in the high nibble of the parameter.
+    pub const FINE_PORTA_UP: u8 = 0x1;
+    pub const FINE_PORTA_DOWN: u8 = 0x2;
+    pub const PATTERN_LOOP: u8 = 0x6;
+    pub const FINE_VOLUME_UP: u8 = 0xA;
+    pub const FINE_VOLUME_DOWN: u8 = 0xB;
+    pub const NOTE_CUT: u8 = 0xC;
+    pub const NOTE_DELAY: u8 = 0xD;
+    pub const PATTERN_DELAY: u8 = 0xE;
+}
+
+/// The period clock, in Hz. A period of 1712 plays C-4 at 8363 Hz.
+const PERIOD_CLOCK: u32 = 14_317_456;
+const MIN_PERIOD: i32 = 64;
+const MAX_PERIOD: i32 = 32767;
+/// The period multiplier for each semitone of an arpeggio, with 16
+/// fractional bits.
+const ARPEGGIO: [u32; 16] = [
+    65536, 61858, 58386, 55109, 52016, 49097, 46341, 43740, 41285, 38968, 36781, 34716, 32768,
+    30929, 29193, 27554,
+];
+/// The first half of a sine wave, for vibrato and tremolo.
+const SINE: [u8; 32] = [
+    0, 24, 49, 74, 97, 120, 141, 161, 180, 197, 212, 224, 235, 244, 250, 253, 255, 253, 250, 244,
+    235, 224, 212, 197, 180, 161, 141, 120, 97, 74, 49, 24,
+];
+
+const PAN_CENTER: u8 = 128;
+/// The period units of one unit of a Protracker slide.
+const SLIDE_SCALE: i32 = 4;
+
+/// An instrument sample.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Sample {
+    data: &'static [i8],
+    rate: u32,
+    volume: u8,
+    loop_start: Option<usize>,
+}
+
+impl Sample {
+    /// Creates a sample from 8-bit signed samples, played at `rate` Hz for
+    /// C-4, with a default volume (0-64).
+    pub const fn new(data: &'static [i8], rate: u32, volume: u8) -> Self {
+        Self {
+            data,
+            rate,
+            volume,
+            loop_start: None,
+        }
+    }
+
+    /// Creates a sample from bytes that are 8-bit signed samples.
+    pub const fn from_bytes(data: &'static [u8], rate: u32, volume: u8) -> Self {
+        // SAFETY: `i8` has the same size and alignment as `u8`.
+        let data = unsafe { core::slice::from_raw_parts(data.as_ptr().cast::<i8>(), data.len()) };
+        Self::new(data, rate, volume)
+    }
+
+    /// Loops the sample from the sample at `start` to the end.
+    pub const fn with_loop(mut self, start: usize) -> Self {
+        self.loop_start = Some(start);
+        self
+    }
+
+    fn sound(&self) -> Sound {
+        let sound = Sound::new(self.data, self.rate);
+        match self.loop_start {
+            Some(start) => sound.with_loop(start),
+            None => sound,
+        }
+    }
+}
+
+/// A pattern of rows of notes and effects.
+///
+/// Each row is a list of entries followed by a 0 byte. An entry starts with
+/// a byte of the channel (bits 0-4) and the fields that follow:
+///
+/// - Bit 5: the note (1 is C-0; [`NOTE_OFF`] stops the channel) and the sample
+///   number (from 1), either of which may be 0
+/// - Bit 6: the volume (0-64)
+/// - Bit 7: the [`effect`] and its parameter
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Pattern {
+    rows: u16,
+    data: &'static [u8],
+}
+
+impl Pattern {
+    pub const fn new(rows: u16, data: &'static [u8]) -> Self {
+        Self { rows, data }
+    }
+
+    /// The number of rows.
+    pub const fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// Returns the offset of the entries of a row.
+    fn row_offset(&self, row: u16) -> usize {
+        let mut reader = RowReader { data: self.data, offset: 0 };
+        for _ in 0..row {
+            while reader.next().is_some() {}
+        }
+        reader.offset
+    }
+}
+
+/// A song converted from a tracker module.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Song {
+    channels: u8,
+    samples: &'static [Sample],
+    patterns: &'static [Pattern],
+    orders: &'static [u8],
+    restart: u8,
+    speed: u8,
+    tempo: u8,
+    panning: &'static [u8],
+}
+
+impl Song {
+    /// Creates a song that plays the patterns in the order given, at speed 6
+    /// and 125 BPM.
+    pub const fn new(
+        channels: u8,
+        samples: &'static [Sample],
+        patterns: &'static [Pattern],
+        orders: &'static [u8],
+    ) -> Self {
+        Self {
+            channels,
+            samples,
+            patterns,
+            orders,
+            restart: 0,
+            speed: 6,
+            tempo: 125,
+            panning: &[],
+        }
+    }
+
+    /// Sets the initial number of ticks per row.
+    pub const fn with_speed(mut self, speed: u8) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Sets the initial tempo, in BPM.
+    pub const fn with_tempo(mut self, tempo: u8) -> Self {
+        self.tempo = tempo;
+        self
+    }
+
+    /// Sets the order to continue from after the end of the song.
+    pub const fn with_restart(mut self, order: u8) -> Self {
+        self.restart = order;
+        self
+    }
+
+    /// Sets the initial pan of each channel, from 0 (left) to 255 (right).
+    /// Channels without a pan are centered.
+    pub const fn with_panning(mut self, panning: &'static [u8]) -> Self {
+        self.panning = panning;
+        self
+    }
+
+    /// The number of channels.
+    pub const fn channels(&self) -> usize {
+        self.channels as usize
+    }
+
+    /// The number of entries in the order list.
+    pub const fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Checks if the song has no orders.
+    pub const fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+/// An event reported by the player.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrackerEvent {
+    /// A row started playing.
+    Row { order: u8, row: u16 },
+    /// The song reached its end and restarted.
+    Looped,
+    /// The song reached its end and stopped.
+    Finished,
+}
+
+/// An entry of a pattern row.
+#[derive(Debug, Default, Clone, Copy)]
+struct Entry {
+    channel: usize,
+    note: u8,
+    sample: u8,
+    volume: Option<u8>,
+    effect: u8,
+    param: u8,
+}
+
+/// Reads the entries of a row.
+struct RowReader {
+    data: &'static [u8],
+    offset: usize,
+}
+
+impl RowReader {
+    fn byte(&mut self) -> u8 {
+        let value = self.data.get(self.offset).copied().unwrap_or(0);
+        self.offset += 1;
+        value
+    }
+}
+
+impl Iterator for RowReader {
+    type Item = Entry;
+
+    /// Returns the next entry, or `None` after the end of the row.
+    fn next(&mut self) -> Option<Entry> {
+        if self.offset >= self.data.len() {
+            return None;
+        }
+
+        let flags = self.byte();
+        if (flags & (HAS_NOTE | HAS_VOLUME | HAS_EFFECT)) == 0 {
+            return None;
+        }
+
+        let mut entry = Entry {
+            channel: usize::from(flags & CHANNEL_MASK),
+            ..Entry::default()
+        };
+        if (flags & HAS_NOTE) != 0 {
+            entry.note = self.byte();
+            entry.sample = self.byte();
+        }
+        if (flags & HAS_VOLUME) != 0 {
+            entry.volume = Some(self.byte());
+        }
+        if (flags & HAS_EFFECT) != 0 {
+            entry.effect = self.byte();
+            entry.param = self.byte();
+        }
+
+        Some(entry)
+    }
+}
+
+/// The playback state of a channel.
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    voice: Option<VoiceId>,
+    /// The current sample number, from 1
+    sample: u8,
+    period: i32,
+    /// The target of tone portamento
+    target: i32,
+    volume: u8,
+    pan: u8,
+    effect: u8,
+    param: u8,
+    porta_speed: u8,
+    vibrato: u8,
+    vibrato_pos: u8,
+    tremolo: u8,
+    tremolo_pos: u8,
+    offset: u8,
+    /// Semitones added by arpeggio on this tick
+    arpeggio: u8,
+    /// Period added by vibrato on this tick
+    period_delta: i32,
+    /// Volume added by tremolo on this tick
+    volume_delta: i32,
+    /// An entry waiting for a note delay
+    delayed: Option<Entry>,
+    loop_row: u16,
+    loop_count: u8,
+}
+
+impl Channel {
+    const fn new(pan: u8) -> Self {
+        Self {
+            voice: None,
+            sample: 0,
+            period: 0,
+            target: 0,
+            volume: 0,
+            pan,
+            effect: 0,
+            param: 0,
+            porta_speed: 0,
+            vibrato: 0,
+            vibrato_pos: 0,
+            tremolo: 0,
+            tremolo_pos: 0,
+            offset: 0,
+            arpeggio: 0,
+            period_delta: 0,
+            volume_delta: 0,
+            delayed: None,
+            loop_row: 0,
+            loop_count: 0,
+        }
+    }
+
+    fn slide_period(&mut self, amount: i32) {
+        self.period = (self.period + amount).clamp(MIN_PERIOD, MAX_PERIOD);
+    }
+
+    fn slide_volume(&mut self, param: u8) {
+        let up = param >> 4;
+        let down = param & 0xF;
+        if up != 0 {
+            self.volume = (self.volume + up).min(MAX_VOLUME);
+        } else {
+            self.volume = self.volume.saturating_sub(down);
+        }
+    }
+
+    fn tone_porta(&mut self) {
+        let speed = i32::from(self.porta_speed) * SLIDE_SCALE;
+        if self.period < self.target {
+            self.period = (self.period + speed).min(self.target);
+        } else {
+            self.period = (self.period - speed).max(self.target);
+        }
+    }
+
+    fn vibrato(&mut self) {
+        let speed = self.vibrato >> 4;
+        let depth = i32::from(self.vibrato & 0xF);
+        let delta = (i32::from(SINE[usize::from(self.vibrato_pos & 31)]) * depth) >> 5;
+        self.period_delta = if self.vibrato_pos < 32 {
+            delta
+        } else {
+            -delta
+        };
+        self.vibrato_pos = (self.vibrato_pos + speed) & 63;
+    }
+
+    fn tremolo(&mut self) {
+        let speed = self.tremolo >> 4;
+        let depth = i32::from(self.tremolo & 0xF);
+        let delta = (i32::from(SINE[usize::from(self.tremolo_pos & 31)]) * depth) >> 6;
+        self.volume_delta = if self.tremolo_pos < 32 {
+            delta
+        } else {
+            -delta
+        };
+        self.tremolo_pos = (self.tremolo_pos + speed) & 63;
+    }
+
+    /// Sets the speed and depth nibbles that are not 0.
+    fn update_nibbles(value: &mut u8, param: u8) {
+        if (param & 0xF0) != 0 {
+            *value = (*value & 0x0F) | (param & 0xF0);
+        }
+        if (param & 0x0F) != 0 {
+            *value = (*value & 0xF0) | (param & 0x0F);
+        }
+    }
+}
+
+/// Plays a song on the mixer.
+#[derive(Debug)]
+pub struct Player {
+    song: &'static Song,
+    channels: [Channel; MAX_CHANNELS],
+    order: u8,
+    row: u16,
+    /// The offset of the next row in the pattern
+    offset: usize,
+    tick: u8,
+    speed: u8,
+    tempo: u8,
+    /// Tempo accumulated towards the next tick
+    clock: u32,
+    pattern_delay: u8,
+    /// Set while a row is repeated by a pattern delay
+    repeating: bool,
+    /// The order and row to continue from after this row
+    jump: Option<(u8, u16)>,
+    playing: bool,
+    looping: bool,
+    volume: u8,
+    callback: Option<fn(TrackerEvent)>,
+}
+
+impl Player {
+    /// The tempo accumulated per tick: 2.5 seconds of CPU cycles per BPM.
+    const TICK_CLOCK: u32 = 5 * CPU_FREQUENCY;
+
+    /// Creates a stopped player for the song, which loops at full volume.
+    pub const fn new(song: &'static Song) -> Self {
+        Self {
+            song,
+            channels: [Channel::new(PAN_CENTER); MAX_CHANNELS],
+            order: 0,
+            row: 0,
+            offset: 0,
+            tick: 0,
+            speed: song.speed,
+            tempo: song.tempo,
+            clock: 0,
+            pattern_delay: 0,
+            repeating: false,
+            jump: None,
+            playing: false,
+            looping: true,
+            volume: MAX_VOLUME,
+            callback: None,
+        }
+    }
+
+    /// Sets a function to call on each row and at the end of the song.
+    ///
+    /// Called from [`update()`](Self::update).
+    pub fn set_callback(&mut self, callback: Option<fn(TrackerEvent)>) {
+        self.callback = callback;
+    }
+
+    /// Sets if the song continues from its restart order after the end.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Sets the master volume (0-64).
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(MAX_VOLUME);
+    }
+
+    /// The tempo, in BPM.
+    pub const fn tempo(&self) -> u8 {
+        self.tempo
+    }
+
+    /// Sets the tempo (32-255 BPM), until changed by the song.
+    pub fn set_tempo(&mut self, tempo: u8) {
+        self.tempo = tempo.max(32);
+    }
+
+    /// The number of ticks per row.
+    pub const fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    /// Sets the number of ticks per row, until changed by the song.
+    pub fn set_speed(&mut self, speed: u8) {
+        self.speed = speed.max(1);
+    }
+
+    /// Returns the order and row being played.
+    pub const fn position(&self) -> (u8, u16) {
+        (self.order, self.row)
+    }
+
+    /// Checks if the song is playing.
+    pub const fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Starts the song from the beginning.
+    pub fn play(&mut self) {
+        for (i, channel) in self.channels.iter_mut().enumerate() {
+            let pan = self.song.panning.get(i).copied().unwrap_or(PAN_CENTER);
+            *channel = Channel::new(pan);
+        }
+
+        self.speed = self.song.speed.max(1);
+        self.tempo = self.song.tempo.max(32);
+        self.tick = 0;
+        self.clock = Self::TICK_CLOCK;
+        self.pattern_delay = 0;
+        self.repeating = false;
+        self.jump = None;
+        self.playing = true;
+        self.seek(0, 0);
+    }
+
+    /// Continues the song from the start of an order.
+    pub fn set_position(&mut self, order: u8) {
+        self.jump = None;
+        self.tick = 0;
+        self.repeating = false;
+        self.seek(order, 0);
+    }
+
+    /// Stops the song and its voices.
+    pub fn stop(&mut self, mixer: &mut impl AsMut<Voices>) {
+        self.stop_voices(mixer.as_mut());
+    }
+
+    fn stop_voices(&mut self, voices: &mut Voices) {
+        self.playing = false;
+        for channel in self.channels.iter_mut() {
+            if let Some(voice) = channel.voice.take() {
+                voices.stop(voice);
+            }
+        }
+    }
+
+    /// Advances the song by one frame.
+    ///
+    /// Must be called once per frame, such as after the mixer has mixed the
+    /// frame.
+    pub fn update(&mut self, mixer: &mut impl AsMut<Voices>) {
+        if !self.playing {
+            return;
+        }
+
+        let voices = mixer.as_mut();
+        self.clock += u32::from(self.tempo) * 2 * FRAME_CYCLES;
+        while self.playing && self.clock >= Self::TICK_CLOCK {
+            self.clock -= Self::TICK_CLOCK;
+            self.step(voices);
+        }
+    }
+
+    /// Plays one tick.
+    fn step(&mut self, voices: &mut Voices) {
+        if self.tick == 0 && !self.repeating {
+            self.play_row(voices);
+        } else {
+            self.play_effects(voices);
+        }
+        self.update_voices(voices);
+
+        self.tick += 1;
+        if self.tick >= self.speed {
+            self.tick = 0;
+            if self.pattern_delay > 0 {
+                self.pattern_delay -= 1;
+                self.repeating = true;
+            } else {
+                self.repeating = false;
+                self.next_row(voices);
+            }
+        }
+    }
+
+    fn emit(&self, event: TrackerEvent) {
+        if let Some(callback) = self.callback {
+            callback(event);
+        }
+    }
+
+    fn pattern(&self) -> Option<&'static Pattern> {
+        let index = *self.song.orders.get(usize::from(self.order))?;
+        self.song.patterns.get(usize::from(index))
+    }
+
+    /// Moves to a row, wrapping to the restart order after the end.
+    fn seek(&mut self, order: u8, row: u16) -> bool {
+        let mut order = order;
+        if usize::from(order) >= self.song.orders.len() {
+            if !self.looping || self.song.orders.is_empty() {
+                self.playing = false;
+                self.emit(TrackerEvent::Finished);
+                return false;
+            }
+
+            order = if usize::from(self.song.restart) < self.song.orders.len() {
+                self.song.restart
+            } else {
+                0
+            };
+            self.emit(TrackerEvent::Looped);
+        }
+
+        self.order = order;
+        self.row = row;
+        match self.pattern() {
+            Some(pattern) if row < pattern.rows => self.offset = pattern.row_offset(row),
+            _ => {
+                self.row = 0;
+                self.offset = 0;
+            }
+        }
+
+        true
+    }
+
+    fn next_row(&mut self, voices: &mut Voices) {
+        let moved = match self.jump.take() {
+            Some((order, row)) => self.seek(order, row),
+            None => {
+                let rows = self.pattern().map_or(0, |pattern| pattern.rows);
+                if self.row + 1 < rows {
+                    self.row += 1;
+                    true
+                } else {
+                    self.seek(self.order.wrapping_add(1), 0)
+                }
+            }
+        };
+
+        if !moved {
+            self.stop_voices(voices);
+        }
+    }
+
+    fn play_row(&mut self, voices: &mut Voices) {
+        self.emit(TrackerEvent::Row {
+            order: self.order,
+            row: self.row,
+        });
+
+        for channel in self.channels.iter_mut() {
+            channel.effect = 0;
+            channel.param = 0;
+            channel.arpeggio = 0;
+            channel.period_delta = 0;
+            channel.volume_delta = 0;
+            channel.delayed = None;
+        }
+
+        let Some(pattern) = self.pattern() else {
+            return;
+        };
+
+        let mut reader = RowReader {
+            data: pattern.data,
+            offset: self.offset,
+        };
+        for entry in reader.by_ref() {
+            if entry.channel >= self.song.channels().min(MAX_CHANNELS) {
+                continue;
+            }
+
+            let channel = &mut self.channels[entry.channel];
+            channel.effect = entry.effect;
+            channel.param = entry.param;
+
+            if entry.effect == EXTENDED
+                && (entry.param >> 4) == NOTE_DELAY
+                && (entry.param & 0xF) != 0
+            {
+                channel.delayed = Some(entry);
+            } else {
+                self.trigger(&entry, voices);
+            }
+            self.row_effect(&entry);
+        }
+        self.offset = reader.offset;
+    }
+
+    /// Plays the note, sample, and volume of an entry.
+    fn trigger(&mut self, entry: &Entry, voices: &mut Voices) {
+        let samples = self.song.samples;
+        let channel = &mut self.channels[entry.channel];
+
+        if let Some(sample) = samples.get(usize::from(entry.sample).wrapping_sub(1)) {
+            channel.sample = entry.sample;
+            channel.volume = sample.volume.min(MAX_VOLUME);
+        }
+
+        match entry.note {
+            0 => {}
+            NOTE_OFF => {
+                if let Some(voice) = channel.voice.take() {
+                    voices.stop(voice);
+                }
+            }
+            note => {
+                let Some(sample) = samples.get(usize::from(channel.sample).wrapping_sub(1)) else {
+                    return;
+                };
+                let period = period(note - 1, sample.rate);
+
+                let porta = matches!(entry.effect, TONE_PORTA | TONE_PORTA_VOLUME);
+                let playing = channel.voice.is_some_and(|voice| voices.is_playing(&voice));
+                if porta && playing {
+                    channel.target = period;
+                } else {
+                    channel.period = period;
+                    channel.target = period;
+                    channel.vibrato_pos = 0;
+                    channel.tremolo_pos = 0;
+
+                    if let Some(voice) = channel.voice.take() {
+                        voices.stop(voice);
+                    }
+                    channel.voice = voices.play(sample.sound());
+
+                    if entry.effect == OFFSET {
+                        if entry.param != 0 {
+                            channel.offset = entry.param;
+                        }
+                        let position = usize::from(channel.offset) * 256;
+                        if let Some(voice) = channel.voice.and_then(|id| voices.voice_mut(&id)) {
+                            voice.set_position(position);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(volume) = entry.volume {
+            channel.volume = volume.min(MAX_VOLUME);
+        }
+    }
+
+    /// Applies the effects that happen at the start of a row.
+    fn row_effect(&mut self, entry: &Entry) {
+        let param = entry.param;
+        let x = param & 0xF;
+        let channel = &mut self.channels[entry.channel];
+
+        match entry.effect {
+            TONE_PORTA => {
+                if param != 0 {
+                    channel.porta_speed = param;
+                }
+            }
+            VIBRATO => Channel::update_nibbles(&mut channel.vibrato, param),
+            TREMOLO => Channel::update_nibbles(&mut channel.tremolo, param),
+            PAN => channel.pan = param,
+            SET_VOLUME => channel.volume = param.min(MAX_VOLUME),
+            JUMP => {
+                let row = self.jump.map_or(0, |(_, row)| row);
+                self.jump = Some((param, row));
+            }
+            BREAK => {
+                let row = u16::from(param >> 4) * 10 + u16::from(x);
+                let order = self.jump.map_or(self.order.wrapping_add(1), |(order, _)| order);
+                self.jump = Some((order, row));
+            }
+            SPEED => match param {
+                0 => {}
+                1..=0x1F => self.speed = param,
+                _ => self.tempo = param,
+            },
+            EXTENDED => match param >> 4 {
+                FINE_PORTA_UP => channel.slide_period(-i32::from(x) * SLIDE_SCALE),
+                FINE_PORTA_DOWN => channel.slide_period(i32::from(x) * SLIDE_SCALE),
+                PATTERN_LOOP => {
+                    if x == 0 {
+                        channel.loop_row = self.row;
+                    } else if channel.loop_count == 0 {
+                        channel.loop_count = x;
+                        self.jump = Some((self.order, channel.loop_row));
+                    } else {
+                        channel.loop_count -= 1;
+                        if channel.loop_count > 0 {
+                            self.jump = Some((self.order, channel.loop_row));
+                        }
+                    }
+                }
+                FINE_VOLUME_UP => channel.volume = (channel.volume + x).min(MAX_VOLUME),
+                FINE_VOLUME_DOWN => channel.volume = channel.volume.saturating_sub(x),
+                NOTE_CUT if x == 0 => channel.volume = 0,
+                PATTERN_DELAY if !self.repeating => self.pattern_delay = x,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Applies the effects that happen on the ticks after the start of a
+    /// row.
+    fn play_effects(&mut self, voices: &mut Voices) {
+        let tick = self.tick;
+
+        for index in 0..self.song.channels().min(MAX_CHANNELS) {
+            let channel = &mut self.channels[index];
+            let param = channel.param;
+            let x = param & 0xF;
+
+            channel.arpeggio = 0;
+            channel.period_delta = 0;
+            channel.volume_delta = 0;
+
+            match channel.effect {
+                effect::ARPEGGIO if param != 0 => {
+                    channel.arpeggio = match tick % 3 {
+                        0 => 0,
+                        1 => param >> 4,
+                        _ => x,
+                    };
+                }
+                PORTA_UP => channel.slide_period(-i32::from(param) * SLIDE_SCALE),
+                PORTA_DOWN => channel.slide_period(i32::from(param) * SLIDE_SCALE),
+                TONE_PORTA => channel.tone_porta(),
+                VIBRATO => channel.vibrato(),
+                TONE_PORTA_VOLUME => {
+                    channel.tone_porta();
+                    channel.slide_volume(param);
+                }
+                VIBRATO_VOLUME => {
+                    channel.vibrato();
+                    channel.slide_volume(param);
+                }
+                TREMOLO => channel.tremolo(),
+                VOLUME_SLIDE => channel.slide_volume(param),
+                EXTENDED => match param >> 4 {
+                    NOTE_CUT if tick == x => channel.volume = 0,
+                    NOTE_DELAY if tick == x => {
+                        if let Some(entry) = channel.delayed.take() {
+                            self.trigger(&entry, voices);
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// Applies the pitch, volume, and pan of each channel to its voice.
+    fn update_voices(&mut self, voices: &mut Voices) {
+        let rate = voices.rate();
+
+        for channel in self.channels[..self.song.channels().min(MAX_CHANNELS)].iter_mut() {
+            let Some(id) = channel.voice else {
+                continue;
+            };
+            let Some(voice) = voices.voice_mut(&id) else {
+                channel.voice = None;
+                continue;
+            };
+
+            let base = channel.period.clamp(MIN_PERIOD, MAX_PERIOD) as u32;
+            let arpeggio = ARPEGGIO[usize::from(channel.arpeggio & 0xF)];
+            let period = ((base * arpeggio) >> 16) as i32 + channel.period_delta;
+            let period = period.clamp(MIN_PERIOD, MAX_PERIOD) as u32;
+            voice.set_frequency(PERIOD_CLOCK / period, rate);
+
+            let volume =
+                (i32::from(channel.volume) + channel.volume_delta).clamp(0, i32::from(MAX_VOLUME));
+            voice.set_volume((volume * i32::from(self.volume) / i32::from(MAX_VOLUME)) as u8);
+            voice.set_pan(((u16::from(channel.pan) + 1) / 2) as u8);
+        }
+    }
+}
+
+/// Returns the period of a note (0 is C-0) for a sample played at `rate` Hz
+/// for C-4.
+fn period(note: u8, rate: u32) -> i32 {
+    let octave = u32::from(note / 12);
+    let base = (PERIODS[usize::from(note % 12)] << 4) >> octave.min(15);
+    let period = (C4_RATE * base) / rate.max(1);
+    (period as i32).clamp(MIN_PERIOD, MAX_PERIOD)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::sound::mixer::{Voice, FRACTION_BITS};
+
+    // The expected periods follow the Protracker effect definitions, with
+    // the period of C-4 at 1712.
+
+    /// An output rate at which the step of a voice is its frequency in Hz.
+    const RATE: u32 = 1 << FRACTION_BITS;
+
+    static DATA: [i8; 16] = [0; 16];
+    static SAMPLES: [Sample; 2] = [
+        Sample::new(&DATA, C4_RATE, 64).with_loop(0),
+        Sample::new(&DATA, 2 * C4_RATE, 32).with_loop(0),
+    ];
+
+    std::thread_local! {
+        static EVENTS: RefCell<Vec<TrackerEvent>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn record(event: TrackerEvent) {
+        EVENTS.with(|events| events.borrow_mut().push(event));
+    }
+
+    fn take_events() -> Vec<TrackerEvent> {
+        EVENTS.with(|events| events.take())
+    }
+
+    /// The frequency a period plays at.
+    fn hz(period: u32) -> u32 {
+        PERIOD_CLOCK / period
+    }
+
+    fn start(song: &'static Song) -> (Player, Voices) {
+        let mut player = Player::new(song);
+        player.play();
+        (player, Voices::new(RATE))
+    }
+
+    fn voice(player: &Player, voices: &mut Voices, channel: usize) -> Option<Voice> {
+        let id = player.channels[channel].voice?;
+        voices.voice_mut(&id).copied()
+    }
+
+    /// Plays `ticks` ticks, returning the voice of the channel after each.
+    fn run(player: &mut Player, voices: &mut Voices, channel: usize, ticks: usize) -> Vec<Voice> {
+        (0..ticks)
+            .map(|_| {
+                player.step(voices);
+                voice(player, voices, channel).unwrap()
+            })
+            .collect()
+    }
+
+    fn steps(voices: &[Voice]) -> Vec<u32> {
+        voices.iter().map(Voice::step).collect()
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(period(48, C4_RATE), 1712);
+        assert_eq!(period(49, C4_RATE), 1616);
+        assert_eq!(period(60, C4_RATE), 856);
+        assert_eq!(period(36, C4_RATE), 3424);
+        assert_eq!(period(0, C4_RATE), 27392);
+        // A sample at twice the rate plays an octave higher.
+        assert_eq!(period(48, 2 * C4_RATE), 856);
+        assert_eq!(period(119, C4_RATE), MIN_PERIOD);
+        assert_eq!(period(48, 1), MAX_PERIOD);
+    }
+
+    #[test]
+    fn note() {
+        // C-4, sample 1, volume 48
+        static PATTERNS: [Pattern; 1] = [Pattern::new(1, &[0x60, 49, 1, 48, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.step(&mut voices);
+
+        let voice = voice(&player, &mut voices, 0).unwrap();
+        assert_eq!(voice.sound(), SAMPLES[0].sound());
+        assert_eq!(voice.step(), hz(1712));
+        assert_eq!(voice.volume(), 48);
+        assert_eq!(voice.pan(), 64);
+    }
+
+    #[test]
+    fn master_volume() {
+        static PATTERNS: [Pattern; 1] = [Pattern::new(1, &[0x60, 49, 1, 48, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.set_volume(32);
+        player.step(&mut voices);
+
+        assert_eq!(voice(&player, &mut voices, 0).unwrap().volume(), 24);
+    }
+
+    #[test]
+    fn stop() {
+        static PATTERNS: [Pattern; 1] = [Pattern::new(1, &[0x20, 49, 1, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.step(&mut voices);
+        let id = player.channels[0].voice.unwrap();
+        player.stop(&mut voices);
+
+        assert!(!player.is_playing());
+        assert!(!voices.is_playing(&id));
+    }
+
+    #[test]
+    fn volume_slide() {
+        // C-4 with A0F, then an empty row
+        static PATTERNS: [Pattern; 1] = [Pattern::new(2, &[0xA0, 49, 1, 0xA, 0x0F, 0, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]).with_speed(3);
+
+        let (mut player, mut voices) = start(&SONG);
+        let volumes: Vec<u8> =
+            run(&mut player, &mut voices, 0, 6).iter().map(Voice::volume).collect();
+
+        assert_eq!(volumes, [64, 49, 34, 34, 34, 34]);
+    }
+
+    #[test]
+    fn arpeggio() {
+        // C-4 with 047: C-4, E-4, G-4 on each tick in turn
+        static PATTERNS: [Pattern; 1] = [Pattern::new(1, &[0xA0, 49, 1, 0x0, 0x47, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        let steps = steps(&run(&mut player, &mut voices, 0, 6));
+
+        // 1712 * 2^(-4/12) and 1712 * 2^(-7/12), rounded down
+        let (c, e, g) = (hz(1712), hz(1358), hz(1142));
+        assert_eq!(steps, [c, e, g, c, e, g]);
+    }
+
+    #[test]
+    fn porta() {
+        // C-4 with 102, then 204
+        static PATTERNS: [Pattern; 1] =
+            [Pattern::new(2, &[0xA0, 49, 1, 0x1, 0x02, 0, 0x80, 0x2, 0x04, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]).with_speed(4);
+
+        let (mut player, mut voices) = start(&SONG);
+        let steps = steps(&run(&mut player, &mut voices, 0, 8));
+
+        // Slides are in Amiga periods, 4 times coarser, and skip tick 0.
+        let periods = [1712, 1704, 1696, 1688, 1688, 1704, 1720, 1736];
+        assert_eq!(steps, periods.map(hz));
+    }
+
+    #[test]
+    fn tone_porta() {
+        // C-4, then C-5 with 340
+        static PATTERNS: [Pattern; 1] =
+            [Pattern::new(2, &[0x20, 49, 1, 0, 0xA0, 61, 0, 0x3, 0x40, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.step(&mut voices);
+        let id = player.channels[0].voice;
+        run(&mut player, &mut voices, 0, 5);
+
+        let steps = steps(&run(&mut player, &mut voices, 0, 6));
+        let periods = [1712, 1456, 1200, 944, 856, 856];
+        assert_eq!(steps, periods.map(hz));
+        // The note slides without restarting.
+        assert_eq!(player.channels[0].voice, id);
+    }
+
+    #[test]
+    fn vibrato() {
+        // C-4 with 484, then an empty row
+        static PATTERNS: [Pattern; 1] = [Pattern::new(2, &[0xA0, 49, 1, 0x4, 0x84, 0, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        let steps = steps(&run(&mut player, &mut voices, 0, 7));
+
+        // Sine positions 0, 8, 16, 24, and 32 at depth 4
+        let periods = [1712, 1712, 1734, 1743, 1734, 1712, 1712];
+        assert_eq!(steps, periods.map(hz));
+    }
+
+    #[test]
+    fn note_cut_and_delay() {
+        // C-4 with EC2, and C-4 on sample 2 with ED3
+        static PATTERNS: [Pattern; 1] =
+            [Pattern::new(1, &[0xA0, 49, 1, 0xE, 0xC2, 0xA1, 49, 2, 0xE, 0xD3, 0])];
+        static SONG: Song = Song::new(2, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        let mut volumes = Vec::new();
+        let mut delayed = Vec::new();
+        for _ in 0..6 {
+            player.step(&mut voices);
+            volumes.push(voice(&player, &mut voices, 0).unwrap().volume());
+            delayed.push(voice(&player, &mut voices, 1).map(|v| v.sound()));
+        }
+
+        assert_eq!(volumes, [64, 64, 0, 0, 0, 0]);
+        let sound = Some(SAMPLES[1].sound());
+        assert_eq!(delayed, [None, None, None, sound, sound, sound]);
+    }
+
+    #[test]
+    fn speed_and_tempo() {
+        // F03 and F96
+        static PATTERNS: [Pattern; 1] = [Pattern::new(1, &[0x80, 0xF, 0x03, 0x81, 0xF, 0x96, 0])];
+        static SONG: Song = Song::new(2, &SAMPLES, &PATTERNS, &[0]);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.step(&mut voices);
+
+        assert_eq!((player.speed(), player.tempo()), (3, 150));
+    }
+
+    #[test]
+    fn order_flow() {
+        // A break to row 2 on row 1
+        static P0: [u8; 7] = [0, 0x80, 0xD, 0x02, 0, 0, 0];
+        // A jump to order 2 on row 2
+        static P1: [u8; 7] = [0, 0, 0x80, 0xB, 0x02, 0, 0];
+        // A pattern loop of rows 0 and 1, played twice
+        static P2: [u8; 8] = [0x80, 0xE, 0x60, 0, 0x80, 0xE, 0x61, 0];
+        static PATTERNS: [Pattern; 3] =
+            [Pattern::new(4, &P0), Pattern::new(4, &P1), Pattern::new(2, &P2)];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0, 1, 2]).with_speed(1);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.set_callback(Some(record));
+        take_events();
+        for _ in 0..8 {
+            player.step(&mut voices);
+        }
+
+        let row = |order, row| TrackerEvent::Row { order, row };
+        assert_eq!(take_events(), [
+            row(0, 0),
+            row(0, 1),
+            row(1, 2),
+            row(2, 0),
+            row(2, 1),
+            row(2, 0),
+            row(2, 1),
+            TrackerEvent::Looped,
+            row(0, 0),
+        ]);
+    }
+
+    #[test]
+    fn finish() {
+        static PATTERNS: [Pattern; 1] = [Pattern::new(2, &[0x20, 49, 1, 0, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]).with_speed(1);
+
+        let (mut player, mut voices) = start(&SONG);
+        player.set_looping(false);
+        player.set_callback(Some(record));
+        take_events();
+        player.step(&mut voices);
+        let id = player.channels[0].voice.unwrap();
+        player.step(&mut voices);
+
+        let row = |order, row| TrackerEvent::Row { order, row };
+        assert_eq!(take_events(), [row(0, 0), row(0, 1), TrackerEvent::Finished]);
+        assert!(!player.is_playing());
+        assert!(!voices.is_playing(&id));
+    }
+
+    #[test]
+    fn pattern_delay() {
+        // EE2 on row 0
+        static PATTERNS: [Pattern; 1] = [Pattern::new(2, &[0x80, 0xE, 0xE2, 0, 0])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]).with_speed(2);
+
+        let (mut player, mut voices) = start(&SONG);
+        let mut ticks = 0;
+        while player.position() == (0, 0) {
+            player.step(&mut voices);
+            ticks += 1;
+        }
+
+        // The row plays 3 times, at 2 ticks each.
+        assert_eq!(ticks, 6);
+    }
+
+    #[test]
+    fn tempo() {
+        static PATTERNS: [Pattern; 1] = [Pattern::new(64, &[0; 64])];
+        static SONG: Song = Song::new(1, &SAMPLES, &PATTERNS, &[0]).with_speed(1);
+
+        let count = |tempo| {
+            let (mut player, mut voices) = start(&SONG);
+            player.set_tempo(tempo);
+            player.set_callback(Some(record));
+            take_events();
+            for _ in 0..60 {
+                player.update(&mut voices);
+            }
+            take_events().len()
+        };
+
+        // 125 BPM is 50 ticks per second, and 60 frames are 1.0031 seconds.
+        // The first tick plays on the first frame.
+        assert_eq!(count(125), 51);
+        // 150 BPM is 60 ticks per second, slightly faster than the frames.
+        assert_eq!(count(150), 61);
+    }
+}
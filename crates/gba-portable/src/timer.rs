@@ -0,0 +1,9 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Timing of the hardware.
+
+/// The frequency of the CPU clock, in Hz.
+pub const CPU_FREQUENCY: u32 = 1 << 24;
+/// The number of CPU cycles in a frame (228 lines of 1232 cycles).
+pub const FRAME_CYCLES: u32 = 280_896;
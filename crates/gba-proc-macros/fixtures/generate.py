@@ -0,0 +1,179 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: CC0-1.0
+# SPDX-FileCopyrightText: NONE
+
+# Generates the minimal MOD, S3M, and XM modules used by the tests of the
+# tracker converter. Each module exercises the header, sample, and pattern
+# fields that the converter reads; see the tests for the expected values.
+
+import struct
+from pathlib import Path
+
+OUT = Path(__file__).parent
+
+
+def pad(data, size):
+    assert len(data) <= size
+    return data + bytes(size - len(data))
+
+
+def mod_cell(period, sample, effect, param):
+    return bytes([
+        (sample & 0xF0) | (period >> 8),
+        period & 0xFF,
+        ((sample & 0x0F) << 4) | effect,
+        param,
+    ])
+
+
+def mod_sample(length, finetune, volume, loop_start, loop_length):
+    return pad(b"sample", 22) + struct.pack(
+        ">HBBHH", length // 2, finetune, volume, loop_start // 2, loop_length // 2
+    )
+
+
+def generate_mod():
+    data = pad(b"fixture", 20)
+
+    samples = [
+        mod_sample(8, 0x0, 48, 0, 2),
+        mod_sample(16, 0xF, 64, 4, 8),
+    ]
+    for i in range(31):
+        data += samples[i] if i < len(samples) else mod_sample(0, 0, 0, 0, 0)
+
+    data += bytes([3, 1]) + pad(bytes([0, 1, 0]), 128) + b"M.K."
+
+    empty = mod_cell(0, 0, 0, 0)
+    pattern = [[empty] * 4 for _ in range(64)]
+    # C-4, sample 1, set volume 32
+    pattern[0][0] = mod_cell(428, 1, 0xC, 0x20)
+    # Coarse pan to 4
+    pattern[0][1] = mod_cell(0, 0, 0xE, 0x84)
+    # B-3, sample 2, speed 3
+    pattern[0][2] = mod_cell(453, 2, 0xF, 0x03)
+    # Volume slide down 15
+    pattern[1][0] = mod_cell(0, 0, 0xA, 0x0F)
+    data += b"".join(b"".join(row) for row in pattern)
+
+    pattern = [[empty] * 4 for _ in range(64)]
+    # C-3, sample 1
+    pattern[0][1] = mod_cell(856, 1, 0, 0)
+    data += b"".join(b"".join(row) for row in pattern)
+
+    data += bytes([0, 1, 2, 3, 4, 5, 6, 7])
+    data += bytes(range(0x10, 0x20))
+
+    (OUT / "fixture.mod").write_bytes(data)
+
+
+def generate_s3m():
+    orders = bytes([1, 0, 254, 255])
+    instruments = [0x90, 0xE0]
+    pattern_at = 0x130
+
+    rows = []
+    # C#4, sample 1, volume 40, speed 3
+    row = bytes([0xE0, 0x41, 1, 40, 1, 0x03])
+    # Fine volume slide up 2
+    row += bytes([0x81, 4, 0x2F])
+    # An AdLib channel, which is dropped
+    row += bytes([0x22, 0x40, 1])
+    # Volume 10, pan 0x40
+    row += bytes([0xC3, 10, 24, 0x40])
+    rows.append(row + b"\0")
+    # Note off, pattern loop 2
+    rows.append(bytes([0x20, 254, 0, 0x81, 19, 0xB2, 0]))
+    # Tempo too low, break to row 0
+    rows.append(bytes([0x80, 20, 0x10, 0x81, 3, 0x00, 0]))
+    rows += [b"\0"] * 61
+    packed = b"".join(rows)
+    pattern = struct.pack("<H", len(packed) + 2) + packed
+
+    sample_at = (pattern_at + len(pattern) + 15) // 16 * 16
+    sample = bytes([0x80, 0x90, 0xA0, 0x70, 0x60, 0xFF, 0x00, 0x80])
+
+    header = pad(b"fixture", 28) + bytes([0x1A, 16, 0, 0])
+    header += struct.pack("<HHHHHH", len(orders), 2, 2, 0, 0x1320, 2)
+    header += b"SCRM" + bytes([64, 4, 150, 0x80 | 48, 0, 252])
+    header = pad(header, 0x40)
+    settings = [0, 8, 16, 1] + [255] * 28
+    header += bytes(settings) + orders
+    header += struct.pack("<HH", instruments[0] // 16, instruments[1] // 16)
+    header += struct.pack("<HH", pattern_at // 16, 0)
+    header += bytes([0x22, 0x00, 0x00, 0x2F] + [0] * 28)
+    data = pad(header, instruments[0])
+
+    instrument = bytes([1]) + pad(b"sample", 12)
+    instrument += bytes([(sample_at // 16) >> 16]) + struct.pack("<H", (sample_at // 16) & 0xFFFF)
+    instrument += struct.pack("<III", len(sample), 2, 6)
+    instrument += bytes([70, 0, 0, 0x01]) + struct.pack("<I", 16726)
+    instrument = pad(instrument, 0x30) + pad(b"instrument", 28) + b"SCRS"
+    data += instrument
+    data = pad(data, instruments[1])
+
+    data += pad(bytes([0]) + pad(b"empty", 12), 0x4C) + b"SCRS"
+    data = pad(data, pattern_at) + pattern
+    data = pad(data, sample_at) + sample
+
+    (OUT / "fixture.s3m").write_bytes(data)
+
+
+def xm_sample(data, loop_start, loop_length, volume, finetune, kind, relative):
+    header = struct.pack("<IIIBbBBbB", len(data), loop_start, loop_length, volume, finetune, kind, 0x80, relative, 0)
+    return pad(header + pad(b"sample", 22), 40)
+
+
+def xm_instrument(samples, keymap):
+    header = struct.pack("<I", 263) + pad(b"instrument", 22) + bytes([0])
+    header += struct.pack("<HI", len(samples), 40) + bytes(keymap)
+    header = pad(header, 263)
+    return header + b"".join(s[0] for s in samples) + b"".join(s[1] for s in samples)
+
+
+def generate_xm():
+    header = b"Extended Module: " + pad(b"fixture", 20) + b"\x1A" + pad(b"generate.py", 20)
+    header += struct.pack("<H", 0x0104)
+    header += struct.pack("<IHHHHHHHH", 276, 3, 2, 2, 2, 2, 1, 5, 140)
+    header += pad(bytes([1, 0, 7]), 256)
+
+    # C-4, instrument 1, volume 0x20, key off at tick 3
+    cells = bytes([49, 1, 0x30, 0x14, 3])
+    # Instrument 2 without a note, volume slide
+    cells += bytes([0x80 | 0x02 | 0x08 | 0x10, 2, 0xA, 0x12])
+    # Note off, pan 4
+    cells += bytes([0x80 | 0x01 | 0x04, 97, 0xC4])
+    cells += bytes([0x80])
+    data = header + struct.pack("<IBHH", 9, 0, 2, len(cells)) + cells
+
+    empty = bytes([0x80])
+    # C-5, instrument 2, volume 0x40, vibrato
+    cells = bytes([61, 2, 0x50, 0x4, 0x84]) + empty
+    # Arpeggio, and C-4 on instrument 1
+    cells += bytes([0x80 | 0x08 | 0x10, 0x0, 0x47]) + bytes([49, 1, 0, 0x0, 0x00])
+    # Tone portamento to C-6
+    cells += bytes([73, 0, 0, 0x3, 0x40]) + empty
+    # Portamento up, and volume 0x10 with fine volume slide down
+    cells += bytes([0x80 | 0x08 | 0x10, 0x1, 0x08]) + bytes([0x80 | 0x04 | 0x08 | 0x10, 0x20, 0xE, 0xB2])
+    data += struct.pack("<IBHH", 9, 0, 4, len(cells)) + cells
+
+    deltas = struct.pack("<4b", 10, 5, -20, 0)
+    data += xm_instrument([(xm_sample(deltas, 0, 0, 50, 0, 0, 12), deltas)], [0] * 96)
+
+    narrow = struct.pack("<2b", 1, 1)
+    wide = struct.pack("<4h", 0x0100, 0x0100, -0x0200, 0x0300)
+    keymap = [0] * 48 + [1] * 48
+    data += xm_instrument(
+        [
+            (xm_sample(narrow, 0, 0, 80, -128, 0x00, 0), narrow),
+            (xm_sample(wide, 2, 4, 32, 0, 0x11, -12), wide),
+        ],
+        keymap,
+    )
+
+    (OUT / "fixture.xm").write_bytes(data)
+
+
+generate_mod()
+generate_s3m()
+generate_xm()
@@ -0,0 +1,750 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: CC0-1.0
+# SPDX-FileCopyrightText: NONE
+
+# Renders the fixture modules to PCM for the playback tests of the tracker
+# converter, and writes the number of frames and a hash of each render to
+# `renders.txt`.
+#
+# This is a player written separately from the Rust code, from the module
+# formats and the documented behavior of `gba::sound::tracker`: Scream
+# Tracker periods, Protracker effects, ticks clocked from VBlank, and the
+# fixed-point software mixer. The output is mixed at 10512 Hz, 176 samples
+# per frame, and played until the end of the song without looping.
+
+import math
+import struct
+from pathlib import Path
+
+OUT = Path(__file__).parent
+
+CPU_FREQUENCY = 1 << 24
+FRAME_CYCLES = 280896
+RATE = 10512
+FRAME_SAMPLES = 176
+MAX_FRAMES = 3000
+
+NOTE_OFF = 0xFF
+C4_RATE = 8363
+PERIOD_CLOCK = 14317456
+MIN_PERIOD = 64
+MAX_PERIOD = 32767
+PERIODS = [1712, 1616, 1525, 1440, 1357, 1281, 1209, 1141, 1077, 1017, 961, 907]
+# The vibrato table of Protracker
+SINE = [
+    0, 24, 49, 74, 97, 120, 141, 161, 180, 197, 212, 224, 235, 244, 250, 253,
+    255, 253, 250, 244, 235, 224, 212, 197, 180, 161, 141, 120, 97, 74, 49, 24,
+]
+MAX_VOICES = 8
+
+
+# Module formats
+
+
+class Sample:
+    def __init__(self, data=(), rate=0, volume=0):
+        self.data = list(data)
+        self.rate = rate
+        self.volume = min(volume, 64)
+        self.loop_start = None
+
+    def set_loop(self, start, end):
+        end = min(end, len(self.data))
+        if start < end:
+            del self.data[end:]
+            self.loop_start = start
+
+
+class Module:
+    def __init__(self, channels, samples, patterns, orders, restart, speed, tempo, panning):
+        self.channels = channels
+        self.samples = samples
+        # Each pattern is a list of rows of (note, sample, volume, effect,
+        # param) for each channel, where note 1 is C-0.
+        self.patterns = patterns
+        self.orders = orders
+        self.restart = restart
+        self.speed = speed
+        self.tempo = tempo
+        self.panning = panning
+
+
+def tuned_rate(fine):
+    """The rate of C-4 for a tuning in 1/128 semitones, rounded half up."""
+    return math.floor(C4_RATE * 2 ** (fine / 1536) + 0.5)
+
+
+def octave_period(note):
+    """The period of a note, from C-0, at 8363 Hz."""
+    return (PERIODS[note % 12] << 4) >> (note // 12)
+
+
+def parse_mod(data):
+    channels = {b"M.K.": 4}[data[1080:1084]]
+    length = min(data[950], 128)
+    restart = data[951] if data[951] < length else 0
+    count = max(data[952:1080]) + 1
+
+    offset = 1084
+    patterns = []
+    for _ in range(count):
+        rows = []
+        for _ in range(64):
+            row = []
+            for _ in range(channels):
+                b = data[offset:offset + 4]
+                offset += 4
+                period = ((b[0] & 0x0F) << 8) | b[1]
+                note = 0
+                if period != 0:
+                    distance = [abs(octave_period(n) - period * 4) for n in range(120)]
+                    note = distance.index(min(distance)) + 1
+                effect, param = b[2] & 0x0F, b[3]
+                if effect == 0xE and (param >> 4) == 0x8:
+                    effect, param = 0x8, (param & 0x0F) * 17
+                row.append((note, (b[0] & 0xF0) | (b[2] >> 4), None, effect, param))
+            rows.append(row)
+        patterns.append(rows)
+
+    samples = []
+    for i in range(31):
+        words, finetune, volume, loop_start, loop_length = struct.unpack_from(">HBBHH", data, 42 + i * 30)
+        finetune = (finetune & 0x0F) - 16 if finetune & 0x08 else finetune & 0x0F
+        sample = Sample(
+            (b - 256 if b >= 128 else b for b in data[offset:offset + words * 2]),
+            tuned_rate(finetune * 16),
+            volume,
+        )
+        if loop_length > 1:
+            sample.set_loop(loop_start * 2, (loop_start + loop_length) * 2)
+        offset += words * 2
+        samples.append(sample)
+
+    panning = [[0x40, 0xC0, 0xC0, 0x40][c % 4] for c in range(channels)]
+    return Module(channels, samples, patterns, list(data[952:952 + length]), restart, 6, 125, panning)
+
+
+def s3m_effect(command, info):
+    x, y = info >> 4, info & 0x0F
+    command = chr(command + ord("A") - 1) if 1 <= command <= 26 else "@"
+
+    if command == "A" and info != 0:
+        return (0xF, min(info, 0x1F))
+    if command == "B":
+        return (0xB, info)
+    if command == "C":
+        return (0xD, info)
+    if command == "D":
+        if y == 0xF and x != 0:
+            return (0xE, 0xA0 | x)
+        if x == 0xF and y != 0:
+            return (0xE, 0xB0 | y)
+        return (0xA, info)
+    if command in "EF":
+        fine = 0x10 if command == "F" else 0x20
+        if x == 0xF:
+            return (0xE, fine | y)
+        if x == 0xE:
+            return (0xE, fine | max(y // 4, 1))
+        return (0x1 if command == "F" else 0x2, info)
+    simple = {"G": 0x3, "H": 0x4, "J": 0x0, "K": 0x6, "L": 0x5, "O": 0x9, "R": 0x7}
+    if command in simple:
+        return (simple[command], info)
+    if command == "S":
+        if x == 0x8:
+            return (0x8, y * 17)
+        if x in (0xB, 0xC, 0xD, 0xE):
+            return (0xE, ({0xB: 0x6}.get(x, x) << 4) | y)
+        return (0, 0)
+    if command == "T" and info >= 0x20:
+        return (0xF, info)
+    if command == "X":
+        return (0x8, min(info * 2, 255))
+    return (0, 0)
+
+
+def parse_s3m(data):
+    orders, instruments, patterns = struct.unpack_from("<HHH", data, 0x20)
+    unsigned = struct.unpack_from("<H", data, 0x2A)[0] == 2
+    speed, tempo, master = data[0x31], data[0x32], data[0x33]
+    default_panning = data[0x35] == 252
+    settings = data[0x40:0x60]
+
+    channel_map = {}
+    for i, setting in enumerate(settings):
+        if setting < 16:
+            channel_map[i] = len(channel_map)
+    channels = len(channel_map)
+
+    order_list = []
+    for order in data[0x60:0x60 + orders]:
+        if order == 255:
+            break
+        if order < patterns:
+            order_list.append(order)
+
+    pointers = 0x60 + orders
+    pan_offset = pointers + (instruments + patterns) * 2
+    panning = [0x80] * channels
+    if master & 0x80:
+        for i, channel in channel_map.items():
+            pan = 0x3 if settings[i] < 8 else 0xC
+            if default_panning and data[pan_offset + i] & 0x20:
+                pan = data[pan_offset + i] & 0x0F
+            panning[channel] = pan * 17
+
+    samples = []
+    for i in range(instruments):
+        header = struct.unpack_from("<H", data, pointers + i * 2)[0] * 16
+        if data[header] != 1:
+            samples.append(Sample())
+            continue
+
+        memseg = (data[header + 0x0D] << 16) | struct.unpack_from("<H", data, header + 0x0E)[0]
+        length, loop_start, loop_end = struct.unpack_from("<III", data, header + 0x10)
+        volume, flags = data[header + 0x1C], data[header + 0x1F]
+        rate = struct.unpack_from("<I", data, header + 0x20)[0]
+
+        width = 2 if flags & 0x04 else 1
+        raw = data[memseg * 16:memseg * 16 + length * width][width - 1::width]
+        values = ((b ^ 0x80) if unsigned else b for b in raw)
+        sample = Sample((b - 256 if b >= 128 else b for b in values), rate, volume)
+        if flags & 0x01:
+            sample.set_loop(loop_start, loop_end)
+        samples.append(sample)
+
+    pattern_list = []
+    for i in range(patterns):
+        rows = [[(0, 0, None, 0, 0)] * channels for _ in range(64)]
+        offset = struct.unpack_from("<H", data, pointers + (instruments + i) * 2)[0] * 16
+        if offset != 0:
+            offset += 2
+            for row in rows:
+                while data[offset] != 0:
+                    what = data[offset]
+                    offset += 1
+                    note = sample = effect = param = 0
+                    volume = None
+                    if what & 0x20:
+                        value = data[offset]
+                        if value == 254:
+                            note = NOTE_OFF
+                        elif value != 255 and (value & 0x0F) < 12:
+                            note = (value >> 4) * 12 + (value & 0x0F) + 1
+                        sample = data[offset + 1]
+                        offset += 2
+                    if what & 0x40:
+                        volume = data[offset] if data[offset] <= 64 else None
+                        offset += 1
+                    if what & 0x80:
+                        effect, param = s3m_effect(data[offset], data[offset + 1])
+                        offset += 2
+                    if (what & 0x1F) in channel_map:
+                        row[channel_map[what & 0x1F]] = (note, sample, volume, effect, param)
+                offset += 1
+        pattern_list.append(rows)
+
+    return Module(
+        channels, samples, pattern_list, order_list, 0, speed or 6, max(tempo, 32), panning
+    )
+
+
+def parse_xm(data):
+    header_size, length, restart, channels, patterns, instruments = struct.unpack_from(
+        "<IHHHHH", data, 60
+    )
+    speed, tempo = struct.unpack_from("<HH", data, 76)
+    length = min(length, 256)
+
+    offset = 60 + header_size
+    raw_patterns = []
+    for _ in range(patterns):
+        size, _, rows, packed = struct.unpack_from("<IBHH", data, offset)
+        offset += size
+        cells = []
+        pos = offset
+        for _ in range(rows * channels if packed else 0):
+            first = data[pos]
+            pos += 1
+            if not first & 0x80:
+                cells.append([first] + list(data[pos:pos + 4]))
+                pos += 4
+                continue
+            cell = []
+            for bit in range(5):
+                if first & (1 << bit):
+                    cell.append(data[pos])
+                    pos += 1
+                else:
+                    cell.append(0)
+            cells.append(cell)
+        offset += packed
+        raw_patterns.append((rows, cells or [[0] * 5] * (rows * channels)))
+
+    samples = []
+    instrument_list = []
+    for _ in range(instruments):
+        size = struct.unpack_from("<I", data, offset)[0]
+        count = struct.unpack_from("<H", data, offset + 27)[0]
+        if count == 0:
+            instrument_list.append((len(samples), 0, [0] * 96))
+            offset += size
+            continue
+
+        sample_header_size = struct.unpack_from("<I", data, offset + 29)[0]
+        instrument_list.append((len(samples), count, list(data[offset + 33:offset + 129])))
+        offset += size
+
+        headers = offset
+        offset += count * sample_header_size
+        for s in range(count):
+            size, loop_start, loop_length, volume, finetune, kind, _, relative = struct.unpack_from(
+                "<IIIBbBBb", data, headers + s * sample_header_size
+            )
+            raw = data[offset:offset + size]
+            offset += size
+
+            values = []
+            value = 0
+            if kind & 0x10:
+                for (delta,) in struct.iter_unpack("<h", raw):
+                    value = (value + delta + 0x8000) % 0x10000 - 0x8000
+                    values.append(value >> 8)
+            else:
+                for delta in raw:
+                    value = (value + delta + 0x80) % 0x100 - 0x80
+                    values.append(value)
+
+            sample = Sample(values, tuned_rate(relative * 128 + finetune), volume)
+            if kind & 0x03 and loop_length > 0:
+                scale = 2 if kind & 0x10 else 1
+                sample.set_loop(loop_start // scale, (loop_start + loop_length) // scale)
+            samples.append(sample)
+
+    pattern_list = []
+    for rows, cells in raw_patterns:
+        last_note = [48] * channels
+        pattern = [[None] * channels for _ in range(rows)]
+        for i, (note, instrument, volume_column, effect, param) in enumerate(cells):
+            channel = i % channels
+            if 1 <= note <= 96:
+                last_note[channel] = note - 1
+            elif note == 97:
+                note = NOTE_OFF
+            else:
+                note = 0
+
+            sample = 0
+            if 1 <= instrument <= len(instrument_list):
+                base, count, keymap = instrument_list[instrument - 1]
+                if keymap[last_note[channel]] < count:
+                    sample = min(base + keymap[last_note[channel]] + 1, 255)
+
+            if effect == 0x14:
+                effect, param = 0xE, 0xC0 | min(param, 0x0F)
+            elif effect > 0xF:
+                effect, param = 0, 0
+
+            volume = None
+            if 0x10 <= volume_column <= 0x50:
+                volume = volume_column - 0x10
+            elif volume_column >> 4 == 0xC and effect == 0 and param == 0:
+                effect, param = 0x8, (volume_column & 0x0F) * 17
+
+            pattern[i // channels][channel] = (note, sample, volume, effect, param)
+        pattern_list.append(pattern)
+
+    orders = [o for o in data[80:80 + length] if o < patterns]
+    restart = restart if restart < length else 0
+    speed = min(speed, 0x1F) if speed else 6
+    return Module(channels, samples, pattern_list, orders, restart, speed, min(max(tempo, 32), 255), [])
+
+
+# Mixer
+
+
+class Voice:
+    def __init__(self, sample):
+        self.data = sample.data
+        self.loop_start = sample.loop_start
+        self.pos = 0
+        self.step = max(sample.rate * 4096 // RATE, 1)
+        self.volume = 64
+        self.pan = 64
+        self.playing = True
+
+    def mix(self, acc):
+        """Mixes into the accumulators, returning False once ended."""
+        end = len(self.data) << 12
+        left = self.volume * min(128 - self.pan, 64) // 64
+        right = self.volume * min(self.pan, 64) // 64
+
+        done = 0
+        while done < len(acc):
+            if self.pos >= end:
+                if self.loop_start is None or self.loop_start >= len(self.data):
+                    return False
+                start = self.loop_start << 12
+                self.pos = start + (self.pos - end) % (end - start)
+
+            count = min(-(-(end - self.pos) // self.step), len(acc) - done)
+            for i in range(done, done + count):
+                value = self.data[self.pos >> 12]
+                acc[i][0] += value * left
+                acc[i][1] += value * right
+                self.pos += self.step
+            done += count
+
+        return True
+
+
+class Voices:
+    def __init__(self):
+        self.voices = []
+
+    def play(self, sample):
+        if len(self.voices) >= MAX_VOICES:
+            return None
+        voice = Voice(sample)
+        self.voices.append(voice)
+        return voice
+
+    def stop(self, voice):
+        if voice.playing:
+            voice.playing = False
+            self.voices.remove(voice)
+
+    def mix(self):
+        acc = [[0, 0] for _ in range(FRAME_SAMPLES)]
+        for voice in list(self.voices):
+            if not voice.mix(acc):
+                self.stop(voice)
+        return acc
+
+
+# Player
+
+
+class Channel:
+    def __init__(self, pan):
+        self.voice = None
+        self.sample = 0
+        self.period = 0
+        self.target = 0
+        self.volume = 0
+        self.pan = pan
+        self.effect = 0
+        self.param = 0
+        self.porta_speed = 0
+        self.vibrato = 0
+        self.vibrato_pos = 0
+        self.tremolo = 0
+        self.tremolo_pos = 0
+        self.offset = 0
+        self.arpeggio = 0
+        self.period_delta = 0
+        self.volume_delta = 0
+        self.delayed = None
+        self.loop_row = 0
+        self.loop_count = 0
+
+    def slide_period(self, amount):
+        self.period = min(max(self.period + amount, MIN_PERIOD), MAX_PERIOD)
+
+    def slide_volume(self, param):
+        if param >> 4:
+            self.volume = min(self.volume + (param >> 4), 64)
+        else:
+            self.volume = max(self.volume - (param & 0x0F), 0)
+
+    def tone_porta(self):
+        speed = self.porta_speed * 4
+        if self.period < self.target:
+            self.period = min(self.period + speed, self.target)
+        else:
+            self.period = max(self.period - speed, self.target)
+
+    def oscillate(self, setting, pos, shift):
+        """Returns the delta and next position of vibrato or tremolo."""
+        delta = (SINE[pos & 31] * (setting & 0x0F)) >> shift
+        return (delta if pos < 32 else -delta), (pos + (setting >> 4)) & 63
+
+
+def nibbles(value, param):
+    if param & 0xF0:
+        value = (value & 0x0F) | (param & 0xF0)
+    if param & 0x0F:
+        value = (value & 0xF0) | (param & 0x0F)
+    return value
+
+
+def note_period(note, rate):
+    """The period of a note, from C-0, for a sample of C-4 at `rate` Hz."""
+    base = (PERIODS[note % 12] << 4) >> min(note // 12, 15)
+    return min(max(C4_RATE * base // max(rate, 1), MIN_PERIOD), MAX_PERIOD)
+
+
+class Player:
+    TICK_CLOCK = 5 * CPU_FREQUENCY
+
+    def __init__(self, module):
+        self.module = module
+        self.voices = Voices()
+        self.channels = [
+            Channel(module.panning[i] if i < len(module.panning) else 128)
+            for i in range(module.channels)
+        ]
+        self.speed = max(module.speed, 1)
+        self.tempo = max(module.tempo, 32)
+        self.tick = 0
+        self.clock = self.TICK_CLOCK
+        self.pattern_delay = 0
+        self.repeating = False
+        self.jump = None
+        self.playing = True
+        self.seek(0, 0)
+
+    def pattern(self):
+        if self.order < len(self.module.orders):
+            index = self.module.orders[self.order]
+            if index < len(self.module.patterns):
+                return self.module.patterns[index]
+        return None
+
+    def seek(self, order, row):
+        if order >= len(self.module.orders):
+            self.playing = False
+            return False
+        self.order = order
+        pattern = self.pattern()
+        self.row = row if pattern is not None and row < len(pattern) else 0
+        return True
+
+    def update(self):
+        if not self.playing:
+            return
+        self.clock += self.tempo * 2 * FRAME_CYCLES
+        while self.playing and self.clock >= self.TICK_CLOCK:
+            self.clock -= self.TICK_CLOCK
+            self.step()
+
+    def step(self):
+        if self.tick == 0 and not self.repeating:
+            self.play_row()
+        else:
+            self.play_effects()
+        self.update_voices()
+
+        self.tick += 1
+        if self.tick >= self.speed:
+            self.tick = 0
+            if self.pattern_delay > 0:
+                self.pattern_delay -= 1
+                self.repeating = True
+            else:
+                self.repeating = False
+                self.next_row()
+
+    def next_row(self):
+        if self.jump is not None:
+            moved = self.seek(*self.jump)
+            self.jump = None
+        else:
+            pattern = self.pattern()
+            if pattern is not None and self.row + 1 < len(pattern):
+                self.row += 1
+                moved = True
+            else:
+                moved = self.seek((self.order + 1) & 0xFF, 0)
+
+        if not moved:
+            for channel in self.channels:
+                if channel.voice is not None:
+                    self.voices.stop(channel.voice)
+                    channel.voice = None
+
+    def play_row(self):
+        for channel in self.channels:
+            channel.effect = channel.param = 0
+            channel.arpeggio = channel.period_delta = channel.volume_delta = 0
+            channel.delayed = None
+
+        pattern = self.pattern()
+        if pattern is None:
+            return
+
+        for index, cell in enumerate(pattern[self.row]):
+            channel = self.channels[index]
+            channel.effect, channel.param = cell[3], cell[4]
+            if cell[3] == 0xE and cell[4] >> 4 == 0xD and cell[4] & 0x0F:
+                channel.delayed = cell
+            else:
+                self.trigger(channel, cell)
+            self.row_effect(channel, cell)
+
+    def trigger(self, channel, cell):
+        note, sample, volume, effect, param = cell
+        samples = self.module.samples
+
+        if 1 <= sample <= len(samples):
+            channel.sample = sample
+            channel.volume = samples[sample - 1].volume
+
+        if note == NOTE_OFF:
+            if channel.voice is not None:
+                self.voices.stop(channel.voice)
+                channel.voice = None
+        elif note != 0:
+            if not 1 <= channel.sample <= len(samples):
+                return
+            sample = samples[channel.sample - 1]
+            period = note_period(note - 1, sample.rate)
+
+            playing = channel.voice is not None and channel.voice.playing
+            if effect in (0x3, 0x5) and playing:
+                channel.target = period
+            else:
+                channel.period = channel.target = period
+                channel.vibrato_pos = channel.tremolo_pos = 0
+                if channel.voice is not None:
+                    self.voices.stop(channel.voice)
+                channel.voice = self.voices.play(sample)
+
+                if effect == 0x9:
+                    if param != 0:
+                        channel.offset = param
+                    if channel.voice is not None:
+                        channel.voice.pos = (channel.offset * 256) << 12
+
+        if volume is not None:
+            channel.volume = min(volume, 64)
+
+    def row_effect(self, channel, cell):
+        effect, param = cell[3], cell[4]
+        x = param & 0x0F
+
+        if effect == 0x3 and param != 0:
+            channel.porta_speed = param
+        elif effect == 0x4:
+            channel.vibrato = nibbles(channel.vibrato, param)
+        elif effect == 0x7:
+            channel.tremolo = nibbles(channel.tremolo, param)
+        elif effect == 0x8:
+            channel.pan = param
+        elif effect == 0xC:
+            channel.volume = min(param, 64)
+        elif effect == 0xB:
+            self.jump = (param, self.jump[1] if self.jump else 0)
+        elif effect == 0xD:
+            order = self.jump[0] if self.jump else (self.order + 1) & 0xFF
+            self.jump = (order, (param >> 4) * 10 + x)
+        elif effect == 0xF and param != 0:
+            if param < 0x20:
+                self.speed = param
+            else:
+                self.tempo = param
+        elif effect == 0xE:
+            command = param >> 4
+            if command == 0x1:
+                channel.slide_period(-x * 4)
+            elif command == 0x2:
+                channel.slide_period(x * 4)
+            elif command == 0x6:
+                if x == 0:
+                    channel.loop_row = self.row
+                elif channel.loop_count == 0:
+                    channel.loop_count = x
+                    self.jump = (self.order, channel.loop_row)
+                else:
+                    channel.loop_count -= 1
+                    if channel.loop_count > 0:
+                        self.jump = (self.order, channel.loop_row)
+            elif command == 0xA:
+                channel.volume = min(channel.volume + x, 64)
+            elif command == 0xB:
+                channel.volume = max(channel.volume - x, 0)
+            elif command == 0xC and x == 0:
+                channel.volume = 0
+            elif command == 0xE and not self.repeating:
+                self.pattern_delay = x
+
+    def play_effects(self):
+        for channel in self.channels:
+            effect, param = channel.effect, channel.param
+            x = param & 0x0F
+            channel.arpeggio = channel.period_delta = channel.volume_delta = 0
+
+            if effect == 0x0 and param != 0:
+                channel.arpeggio = [0, param >> 4, x][self.tick % 3]
+            elif effect == 0x1:
+                channel.slide_period(-param * 4)
+            elif effect == 0x2:
+                channel.slide_period(param * 4)
+            elif effect in (0x3, 0x5):
+                channel.tone_porta()
+            elif effect in (0x4, 0x6):
+                channel.period_delta, channel.vibrato_pos = channel.oscillate(
+                    channel.vibrato, channel.vibrato_pos, 5
+                )
+            elif effect == 0x7:
+                channel.volume_delta, channel.tremolo_pos = channel.oscillate(
+                    channel.tremolo, channel.tremolo_pos, 6
+                )
+
+            if effect in (0x5, 0x6, 0xA):
+                channel.slide_volume(param)
+            elif effect == 0xE and param >> 4 == 0xC and self.tick == x:
+                channel.volume = 0
+            elif effect == 0xE and param >> 4 == 0xD and self.tick == x:
+                if channel.delayed is not None:
+                    self.trigger(channel, channel.delayed)
+                    channel.delayed = None
+
+    def update_voices(self):
+        for channel in self.channels:
+            voice = channel.voice
+            if voice is None:
+                continue
+            if not voice.playing:
+                channel.voice = None
+                continue
+
+            base = min(max(channel.period, MIN_PERIOD), MAX_PERIOD)
+            ratio = math.floor(65536 * 2 ** (-(channel.arpeggio & 0x0F) / 12) + 0.5)
+            period = ((base * ratio) >> 16) + channel.period_delta
+            period = min(max(period, MIN_PERIOD), MAX_PERIOD)
+            voice.step = max(((PERIOD_CLOCK // period) << 12) // RATE, 1)
+
+            voice.volume = min(max(channel.volume + channel.volume_delta, 0), 64)
+            voice.pan = min((channel.pan + 1) // 2, 128)
+
+
+# Output
+
+
+def render(module):
+    """Returns the frames played and the 8-bit stereo output."""
+    player = Player(module)
+    output = bytearray()
+    frames = 0
+    while player.playing and frames < MAX_FRAMES:
+        for left, right in player.voices.mix():
+            output += struct.pack("<bb", *(min(max(v >> 6, -128), 127) for v in (left, right)))
+        player.update()
+        frames += 1
+    return frames, output
+
+
+def fnv1a(data):
+    value = 0xCBF29CE484222325
+    for byte in data:
+        value = ((value ^ byte) * 0x100000001B3) % (1 << 64)
+    return value
+
+
+lines = []
+for name, parse in [("fixture.mod", parse_mod), ("fixture.s3m", parse_s3m), ("fixture.xm", parse_xm)]:
+    frames, output = render(parse((OUT / name).read_bytes()))
+    lines.append(f"{name} {frames} {fnv1a(output):016x}\n")
+
+(OUT / "renders.txt").write_text("".join(lines))
@@ -3,7 +3,12 @@
 
 //! Rust procedural macros for GBA development.
 
+mod tracker;
+
+use std::path::Path;
+
 use proc_macro::TokenStream;
+use proc_macro2::Literal;
 use syn::spanned::Spanned;
 
 /// Attribute to declare the main function of the GBA program.
@@ -83,3 +88,78 @@ pub fn entry(args: TokenStream, stream: TokenStream) -> TokenStream {
         #block
     })
 }
+
+/// Converts a tracker module to a `gba::sound::tracker::Song` at build time.
+///
+/// MOD, S3M, and XM modules are supported, with up to 16 channels. The path
+/// is relative to the directory of the crate's `Cargo.toml`.
+///
+/// ```rust
+/// use gba::sound::tracker::Song;
+///
+/// static THEME: Song = gba::include_tracker!("music/theme.mod");
+/// ```
+#[proc_macro]
+pub fn include_tracker(input: TokenStream) -> TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = Path::new(&dir).join(path.value());
+    let data = match std::fs::read(&file) {
+        Ok(data) => data,
+        Err(err) => {
+            let message = format!("Failed to read {}: {}", file.display(), err);
+            return syn::Error::new(path.span(), message).to_compile_error().into();
+        }
+    };
+
+    let module = match tracker::parse(&data) {
+        Ok(module) => module,
+        Err(err) => {
+            let message = format!("Failed to convert {}: {}", file.display(), err);
+            return syn::Error::new(path.span(), message).to_compile_error().into();
+        }
+    };
+
+    let samples = module.samples.iter().map(|sample| {
+        let bytes: Vec<u8> = sample.data.iter().map(|&s| s as u8).collect();
+        let data = Literal::byte_string(&bytes);
+        let rate = sample.rate;
+        let volume = sample.volume;
+        let with_loop = sample.loop_start.map(|start| quote::quote!(.with_loop(#start)));
+        quote::quote! {
+            ::gba::sound::tracker::Sample::from_bytes(#data, #rate, #volume)#with_loop
+        }
+    });
+
+    let patterns = module.patterns.iter().map(|pattern| {
+        let rows = pattern.rows.len() as u16;
+        let data = Literal::byte_string(&pattern.encode());
+        quote::quote! {
+            ::gba::sound::tracker::Pattern::new(#rows, #data)
+        }
+    });
+
+    let file = file.to_string_lossy();
+    let channels = module.channels as u8;
+    let orders = Literal::byte_string(&module.orders);
+    let restart = module.restart;
+    let speed = module.speed;
+    let tempo = module.tempo;
+    let panning = Literal::byte_string(&module.panning);
+
+    TokenStream::from(quote::quote! {
+        {
+            // Rebuild when the module changes.
+            const _: &[u8] = include_bytes!(#file);
+            const SAMPLES: &[::gba::sound::tracker::Sample] = &[#(#samples),*];
+            const PATTERNS: &[::gba::sound::tracker::Pattern] = &[#(#patterns),*];
+
+            ::gba::sound::tracker::Song::new(#channels, SAMPLES, PATTERNS, #orders)
+                .with_restart(#restart)
+                .with_speed(#speed)
+                .with_tempo(#tempo)
+                .with_panning(#panning)
+        }
+    })
+}
@@ -0,0 +1,1057 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Conversion of MOD, S3M, and XM modules to the song format of
+//! `gba::sound::tracker`.
+//!
+//! Samples are converted to 8-bit signed, with loops truncated to end at the
+//! loop end. Ping-pong loops play forwards, and XM envelopes, sample panning,
+//! and effects without a Protracker equivalent are dropped.
+
+use gba_portable::sound::tracker::effect::{
+    ARPEGGIO,
+    BREAK,
+    EXTENDED,
+    FINE_PORTA_DOWN,
+    FINE_PORTA_UP,
+    FINE_VOLUME_DOWN,
+    FINE_VOLUME_UP,
+    JUMP,
+    NOTE_CUT,
+    NOTE_DELAY,
+    OFFSET,
+    PAN,
+    PATTERN_DELAY,
+    PATTERN_LOOP,
+    PORTA_DOWN,
+    PORTA_UP,
+    SPEED,
+    TONE_PORTA,
+    TONE_PORTA_VOLUME,
+    TREMOLO,
+    VIBRATO,
+    VIBRATO_VOLUME,
+    VOLUME_SLIDE,
+};
+use gba_portable::sound::tracker::{
+    C4_RATE,
+    CHANNEL_MASK,
+    HAS_EFFECT,
+    HAS_NOTE,
+    HAS_VOLUME,
+    MAX_CHANNELS,
+    NOTE_OFF,
+    PERIODS,
+};
+
+/// The number of notes, from C-0.
+const NOTES: usize = 120;
+/// The pan of the left and right channels of an Amiga.
+const AMIGA_PANNING: [u8; 4] = [0x40, 0xC0, 0xC0, 0x40];
+
+/// An instrument sample.
+#[derive(Debug, Default)]
+pub struct Sample {
+    pub data: Vec<i8>,
+    /// The sample rate that plays C-4, in Hz
+    pub rate: u32,
+    pub volume: u8,
+    pub loop_start: Option<usize>,
+}
+
+impl Sample {
+    /// Truncates the sample to the end of its loop.
+    fn set_loop(&mut self, start: usize, end: usize) {
+        let end = end.min(self.data.len());
+        if start < end {
+            self.data.truncate(end);
+            self.loop_start = Some(start);
+        }
+    }
+}
+
+/// A note, sample, volume, and effect of a channel in a row.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct Cell {
+    /// The note, where 1 is C-0
+    pub note: u8,
+    /// The sample number, from 1
+    pub sample: u8,
+    pub volume: Option<u8>,
+    pub effect: u8,
+    pub param: u8,
+}
+
+/// The cells of each row and channel.
+#[derive(Debug)]
+pub struct Pattern {
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Pattern {
+    fn new(rows: usize, channels: usize) -> Self {
+        Self {
+            rows: vec![vec![Cell::default(); channels]; rows],
+        }
+    }
+
+    /// Encodes the rows as entries for the channels with content, each row
+    /// ending with a 0 byte.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        for row in &self.rows {
+            for (channel, cell) in row.iter().enumerate() {
+                let has_note = cell.note != 0 || cell.sample != 0;
+                let has_volume = cell.volume.is_some();
+                let has_effect = cell.effect != 0 || cell.param != 0;
+
+                if !(has_note || has_volume || has_effect) {
+                    continue;
+                }
+
+                let mut flags = channel as u8 & CHANNEL_MASK;
+                if has_note {
+                    flags |= HAS_NOTE;
+                }
+                if has_volume {
+                    flags |= HAS_VOLUME;
+                }
+                if has_effect {
+                    flags |= HAS_EFFECT;
+                }
+
+                data.push(flags);
+                if has_note {
+                    data.extend([cell.note, cell.sample]);
+                }
+                if let Some(volume) = cell.volume {
+                    data.push(volume);
+                }
+                if has_effect {
+                    data.extend([cell.effect, cell.param]);
+                }
+            }
+            data.push(0);
+        }
+
+        data
+    }
+}
+
+/// A converted module.
+#[derive(Debug)]
+pub struct Module {
+    pub channels: usize,
+    pub samples: Vec<Sample>,
+    pub patterns: Vec<Pattern>,
+    pub orders: Vec<u8>,
+    pub restart: u8,
+    pub speed: u8,
+    pub tempo: u8,
+    pub panning: Vec<u8>,
+}
+
+/// Converts a MOD, S3M, or XM module.
+pub fn parse(data: &[u8]) -> Result<Module, String> {
+    let module = if data.starts_with(b"Extended Module: ") {
+        parse_xm(data)?
+    } else if data.get(0x2C..0x30) == Some(b"SCRM") {
+        parse_s3m(data)?
+    } else if let Some(channels) = mod_channels(data) {
+        parse_mod(data, channels)?
+    } else {
+        return Err("Unrecognized module format".into());
+    };
+
+    if module.channels > MAX_CHANNELS {
+        return Err(format!(
+            "Module has {} channels; at most {} are supported",
+            module.channels, MAX_CHANNELS
+        ));
+    }
+    if module.samples.len() > usize::from(u8::MAX) {
+        return Err(format!(
+            "Module has {} samples; at most 255 are supported",
+            module.samples.len()
+        ));
+    }
+    if module.patterns.len() > 256 {
+        return Err(format!(
+            "Module has {} patterns; at most 256 are supported",
+            module.patterns.len()
+        ));
+    }
+
+    Ok(module)
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], String> {
+    data.get(offset..offset.saturating_add(len)).ok_or_else(|| "Unexpected end of file".into())
+}
+
+fn byte(data: &[u8], offset: usize) -> Result<u8, String> {
+    Ok(slice(data, offset, 1)?[0])
+}
+
+fn u16_be(data: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = slice(data, offset, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn u16_le(data: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = slice(data, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_le(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = slice(data, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Returns the sample rate of C-4 for a tuning in 1/128 semitones.
+fn tuned_rate(fine: i32) -> u32 {
+    (f64::from(C4_RATE) * 2f64.powf(f64::from(fine) / 1536.0)).round() as u32
+}
+
+/// Returns the note (from 1) closest to an Amiga period.
+fn amiga_note(period: u16) -> u8 {
+    let period = u32::from(period) * 4;
+    let note = (0..NOTES)
+        .min_by_key(|&n| ((PERIODS[n % 12] << 4) >> (n / 12)).abs_diff(period))
+        .unwrap_or(0);
+    note as u8 + 1
+}
+
+/// Returns the number of channels of a MOD from its signature.
+fn mod_channels(data: &[u8]) -> Option<usize> {
+    let signature = data.get(1080..1084)?;
+    let digit = |c: u8| c.is_ascii_digit().then(|| usize::from(c - b'0'));
+
+    match signature {
+        b"M.K." | b"M!K!" | b"M&K!" | b"FLT4" => Some(4),
+        b"OCTA" | b"CD81" => Some(8),
+        [n, b'C', b'H', b'N'] => digit(*n),
+        [a, b, b'C', b'H'] => Some(digit(*a)? * 10 + digit(*b)?),
+        _ => None,
+    }
+}
+
+fn parse_mod(data: &[u8], channels: usize) -> Result<Module, String> {
+    const SAMPLES: usize = 31;
+    const ROWS: usize = 64;
+
+    let length = usize::from(byte(data, 950)?).min(128);
+    let restart = byte(data, 951)?;
+    let orders = slice(data, 952, 128)?;
+    let count = orders.iter().copied().max().map_or(0, |max| usize::from(max) + 1);
+
+    let mut offset = 1084;
+    let mut patterns = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut pattern = Pattern::new(ROWS, channels);
+        for row in pattern.rows.iter_mut() {
+            for cell in row.iter_mut() {
+                let bytes = slice(data, offset, 4)?;
+                offset += 4;
+
+                let period = (u16::from(bytes[0] & 0x0F) << 8) | u16::from(bytes[1]);
+                cell.note = if period == 0 {
+                    0
+                } else {
+                    amiga_note(period)
+                };
+                cell.sample = (bytes[0] & 0xF0) | (bytes[2] >> 4);
+                (cell.effect, cell.param) = match (bytes[2] & 0x0F, bytes[3]) {
+                    // Coarse panning
+                    (EXTENDED, param) if (param >> 4) == 0x8 => (PAN, (param & 0xF) * 17),
+                    effect => effect,
+                };
+            }
+        }
+        patterns.push(pattern);
+    }
+
+    let mut samples = Vec::with_capacity(SAMPLES);
+    for i in 0..SAMPLES {
+        let header = 20 + i * 30;
+        let length = usize::from(u16_be(data, header + 22)?) * 2;
+        let finetune = i32::from(((byte(data, header + 24)? & 0x0F) as i8) << 4 >> 4);
+        let volume = byte(data, header + 25)?.min(64);
+        let loop_start = usize::from(u16_be(data, header + 26)?) * 2;
+        let loop_length = usize::from(u16_be(data, header + 28)?) * 2;
+
+        // Tolerate truncated sample data.
+        let end = (offset + length).min(data.len());
+        let mut sample = Sample {
+            data: data.get(offset..end).unwrap_or(&[]).iter().map(|&s| s as i8).collect(),
+            rate: tuned_rate(finetune * 16),
+            volume,
+            loop_start: None,
+        };
+        if loop_length > 2 {
+            sample.set_loop(loop_start, loop_start + loop_length);
+        }
+        offset += length;
+
+        samples.push(sample);
+    }
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders: orders[..length].to_vec(),
+        restart: if usize::from(restart) < length {
+            restart
+        } else {
+            0
+        },
+        speed: 6,
+        tempo: 125,
+        panning: (0..channels).map(|c| AMIGA_PANNING[c % 4]).collect(),
+    })
+}
+
+/// Converts an S3M effect to its Protracker equivalent.
+fn s3m_effect(command: u8, info: u8) -> (u8, u8) {
+    let x = info >> 4;
+    let y = info & 0x0F;
+
+    match command.wrapping_add(b'A' - 1) {
+        b'A' if info != 0 => (SPEED, info.min(0x1F)),
+        b'B' => (JUMP, info),
+        b'C' => (BREAK, info),
+        b'D' if y == 0x0F && x != 0 => (EXTENDED, (FINE_VOLUME_UP << 4) | x),
+        b'D' if x == 0x0F && y != 0 => (EXTENDED, (FINE_VOLUME_DOWN << 4) | y),
+        b'D' => (VOLUME_SLIDE, info),
+        b'E' if x == 0x0F => (EXTENDED, (FINE_PORTA_DOWN << 4) | y),
+        b'E' if x == 0x0E => (EXTENDED, (FINE_PORTA_DOWN << 4) | (y / 4).max(1)),
+        b'E' => (PORTA_DOWN, info),
+        b'F' if x == 0x0F => (EXTENDED, (FINE_PORTA_UP << 4) | y),
+        b'F' if x == 0x0E => (EXTENDED, (FINE_PORTA_UP << 4) | (y / 4).max(1)),
+        b'F' => (PORTA_UP, info),
+        b'G' => (TONE_PORTA, info),
+        b'H' => (VIBRATO, info),
+        b'J' => (ARPEGGIO, info),
+        b'K' => (VIBRATO_VOLUME, info),
+        b'L' => (TONE_PORTA_VOLUME, info),
+        b'O' => (OFFSET, info),
+        b'R' => (TREMOLO, info),
+        b'S' => match x {
+            0x8 => (PAN, y * 17),
+            0xB => (EXTENDED, (PATTERN_LOOP << 4) | y),
+            0xC => (EXTENDED, (NOTE_CUT << 4) | y),
+            0xD => (EXTENDED, (NOTE_DELAY << 4) | y),
+            0xE => (EXTENDED, (PATTERN_DELAY << 4) | y),
+            _ => (0, 0),
+        },
+        b'T' if info >= 0x20 => (SPEED, info),
+        b'X' => (PAN, info.saturating_mul(2)),
+        _ => (0, 0),
+    }
+}
+
+fn parse_s3m(data: &[u8]) -> Result<Module, String> {
+    const ROWS: usize = 64;
+
+    let order_count = usize::from(u16_le(data, 0x20)?);
+    let instrument_count = usize::from(u16_le(data, 0x22)?);
+    let pattern_count = usize::from(u16_le(data, 0x24)?);
+    let unsigned = u16_le(data, 0x2A)? == 2;
+    let speed = byte(data, 0x31)?;
+    let tempo = byte(data, 0x32)?;
+    let stereo = (byte(data, 0x33)? & 0x80) != 0;
+    let default_panning = byte(data, 0x35)? == 252;
+    let settings = slice(data, 0x40, 32)?;
+
+    // Only enabled PCM channels are kept.
+    let mut channel_map = [None; 32];
+    let mut channels = 0;
+    for (map, &setting) in channel_map.iter_mut().zip(settings) {
+        if setting < 16 {
+            *map = Some(channels);
+            channels += 1;
+        }
+    }
+
+    let orders = slice(data, 0x60, order_count)?;
+    let instruments = 0x60 + order_count;
+    let pattern_offsets = instruments + instrument_count * 2;
+    let pan_offset = pattern_offsets + pattern_count * 2;
+
+    let mut panning = vec![0x80; channels];
+    for (i, &setting) in settings.iter().enumerate() {
+        let Some(channel) = channel_map[i] else {
+            continue;
+        };
+        if !stereo {
+            continue;
+        }
+
+        let mut pan = if setting < 8 {
+            0x3
+        } else {
+            0xC
+        };
+        if default_panning {
+            let value = byte(data, pan_offset + i)?;
+            if (value & 0x20) != 0 {
+                pan = value & 0x0F;
+            }
+        }
+        panning[channel] = pan * 17;
+    }
+
+    let mut samples = Vec::with_capacity(instrument_count);
+    for i in 0..instrument_count {
+        let header = usize::from(u16_le(data, instruments + i * 2)?) * 16;
+        if byte(data, header)? != 1 {
+            samples.push(Sample::default());
+            continue;
+        }
+
+        let memseg = (usize::from(byte(data, header + 0x0D)?) << 16)
+            | usize::from(u16_le(data, header + 0x0E)?);
+        let length = u32_le(data, header + 0x10)? as usize;
+        let loop_start = u32_le(data, header + 0x14)? as usize;
+        let loop_end = u32_le(data, header + 0x18)? as usize;
+        let volume = byte(data, header + 0x1C)?.min(64);
+        let flags = byte(data, header + 0x1F)?;
+        let rate = u32_le(data, header + 0x20)?;
+
+        // Only the left channel of stereo samples is kept.
+        let wide = (flags & 0x04) != 0;
+        let width = if wide {
+            2
+        } else {
+            1
+        };
+        let bytes = slice(data, memseg * 16, length * width)?;
+        let sample_data = bytes
+            .chunks_exact(width)
+            .map(|s| {
+                let value = s[width - 1];
+                (if unsigned {
+                    value ^ 0x80
+                } else {
+                    value
+                }) as i8
+            })
+            .collect();
+
+        let mut sample = Sample {
+            data: sample_data,
+            rate,
+            volume,
+            loop_start: None,
+        };
+        if (flags & 0x01) != 0 {
+            sample.set_loop(loop_start, loop_end);
+        }
+        samples.push(sample);
+    }
+
+    let mut patterns = Vec::with_capacity(pattern_count);
+    for i in 0..pattern_count {
+        let mut pattern = Pattern::new(ROWS, channels);
+        let offset = usize::from(u16_le(data, pattern_offsets + i * 2)?) * 16;
+        if offset == 0 {
+            patterns.push(pattern);
+            continue;
+        }
+
+        let mut offset = offset + 2;
+        for row in pattern.rows.iter_mut() {
+            loop {
+                let what = byte(data, offset)?;
+                offset += 1;
+                if what == 0 {
+                    break;
+                }
+
+                let mut cell = Cell::default();
+                if (what & 0x20) != 0 {
+                    let note = byte(data, offset)?;
+                    cell.note = match note {
+                        255 => 0,
+                        254 => NOTE_OFF,
+                        _ if (note & 0x0F) < 12 => (note >> 4) * 12 + (note & 0x0F) + 1,
+                        _ => 0,
+                    };
+                    cell.sample = byte(data, offset + 1)?;
+                    offset += 2;
+                }
+                if (what & 0x40) != 0 {
+                    let volume = byte(data, offset)?;
+                    cell.volume = (volume <= 64).then_some(volume);
+                    offset += 1;
+                }
+                if (what & 0x80) != 0 {
+                    (cell.effect, cell.param) =
+                        s3m_effect(byte(data, offset)?, byte(data, offset + 1)?);
+                    offset += 2;
+                }
+
+                if let Some(channel) = channel_map[usize::from(what & 0x1F)] {
+                    row[channel] = cell;
+                }
+            }
+        }
+        patterns.push(pattern);
+    }
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders: orders
+            .iter()
+            .copied()
+            .take_while(|&order| order != 255)
+            .filter(|&order| usize::from(order) < pattern_count)
+            .collect(),
+        restart: 0,
+        speed: if speed == 0 {
+            6
+        } else {
+            speed
+        },
+        tempo: tempo.max(32),
+        panning,
+    })
+}
+
+/// The samples of an XM instrument.
+struct XmInstrument {
+    /// The index of the first sample in the module
+    base: usize,
+    count: usize,
+    keymap: Vec<u8>,
+}
+
+fn parse_xm(data: &[u8]) -> Result<Module, String> {
+    let header_size = u32_le(data, 60)? as usize;
+    let length = usize::from(u16_le(data, 64)?).min(256);
+    let restart = u16_le(data, 66)?;
+    let channels = usize::from(u16_le(data, 68)?);
+    let pattern_count = usize::from(u16_le(data, 70)?);
+    let instrument_count = usize::from(u16_le(data, 72)?);
+    let speed = u16_le(data, 76)?;
+    let tempo = u16_le(data, 78)?;
+    let orders = slice(data, 80, length)?;
+
+    // The cells are converted once the instrument keymaps are known.
+    let mut offset = 60 + header_size;
+    let mut raw_patterns = Vec::with_capacity(pattern_count);
+    for _ in 0..pattern_count {
+        let length = u32_le(data, offset)? as usize;
+        let rows = usize::from(u16_le(data, offset + 5)?);
+        let size = usize::from(u16_le(data, offset + 7)?);
+        let packed = slice(data, offset + length, size)?;
+        offset += length + size;
+
+        let mut cells = vec![[0u8; 5]; rows * channels];
+        if size != 0 {
+            let mut pos = 0;
+            for cell in cells.iter_mut() {
+                let first = byte(packed, pos)?;
+                pos += 1;
+                if (first & 0x80) == 0 {
+                    cell[0] = first;
+                    cell[1..].copy_from_slice(slice(packed, pos, 4)?);
+                    pos += 4;
+                } else {
+                    for (bit, field) in cell.iter_mut().enumerate() {
+                        if (first & (1 << bit)) != 0 {
+                            *field = byte(packed, pos)?;
+                            pos += 1;
+                        }
+                    }
+                }
+            }
+        }
+        raw_patterns.push((rows, cells));
+    }
+
+    let mut samples = Vec::new();
+    let mut instruments = Vec::with_capacity(instrument_count);
+    for _ in 0..instrument_count {
+        let size = u32_le(data, offset)? as usize;
+        let count = usize::from(u16_le(data, offset + 27)?);
+        let mut instrument = XmInstrument {
+            base: samples.len(),
+            count,
+            keymap: vec![0; 96],
+        };
+        if count == 0 {
+            offset += size;
+            instruments.push(instrument);
+            continue;
+        }
+
+        let sample_header_size = u32_le(data, offset + 29)? as usize;
+        instrument.keymap = slice(data, offset + 33, 96)?.to_vec();
+        offset += size;
+
+        let headers = offset;
+        offset += count * sample_header_size;
+        for s in 0..count {
+            let header = headers + s * sample_header_size;
+            let length = u32_le(data, header)? as usize;
+            let loop_start = u32_le(data, header + 4)? as usize;
+            let loop_length = u32_le(data, header + 8)? as usize;
+            let volume = byte(data, header + 12)?.min(64);
+            let finetune = i32::from(byte(data, header + 13)? as i8);
+            let kind = byte(data, header + 14)?;
+            let relative = i32::from(byte(data, header + 16)? as i8);
+
+            // Samples are delta encoded.
+            let bytes = slice(data, offset, length)?;
+            offset += length;
+            let wide = (kind & 0x10) != 0;
+            let sample_data = if wide {
+                let mut value = 0i16;
+                bytes
+                    .chunks_exact(2)
+                    .map(|delta| {
+                        value = value.wrapping_add(i16::from_le_bytes([delta[0], delta[1]]));
+                        (value >> 8) as i8
+                    })
+                    .collect()
+            } else {
+                let mut value = 0i8;
+                bytes
+                    .iter()
+                    .map(|&delta| {
+                        value = value.wrapping_add(delta as i8);
+                        value
+                    })
+                    .collect()
+            };
+
+            let mut sample = Sample {
+                data: sample_data,
+                rate: tuned_rate(relative * 128 + finetune),
+                volume,
+                loop_start: None,
+            };
+            if (kind & 0x03) != 0 && loop_length > 0 {
+                let scale = if wide {
+                    2
+                } else {
+                    1
+                };
+                sample.set_loop(loop_start / scale, (loop_start + loop_length) / scale);
+            }
+            samples.push(sample);
+        }
+
+        instruments.push(instrument);
+    }
+
+    let mut patterns = Vec::with_capacity(pattern_count);
+    for (rows, cells) in raw_patterns {
+        let mut pattern = Pattern::new(rows, channels);
+        let mut last_note = vec![48; channels];
+
+        for (i, raw) in cells.iter().enumerate() {
+            let (row, channel) = (i / channels, i % channels);
+            let [note, instrument, volume, effect, param] = *raw;
+            let cell = &mut pattern.rows[row][channel];
+
+            cell.note = match note {
+                1..=96 => {
+                    last_note[channel] = usize::from(note - 1);
+                    note
+                }
+                97 => NOTE_OFF,
+                _ => 0,
+            };
+
+            if let Some(instrument) = instruments.get(usize::from(instrument).wrapping_sub(1)) {
+                let local = usize::from(instrument.keymap[last_note[channel]]);
+                if local < instrument.count {
+                    cell.sample = (instrument.base + local + 1).min(255) as u8;
+                }
+            }
+
+            (cell.effect, cell.param) = match effect {
+                0x0..=0xF => (effect, param),
+                // Key off, which cuts the note without envelopes
+                0x14 => (EXTENDED, (NOTE_CUT << 4) | param.min(0x0F)),
+                _ => (0, 0),
+            };
+
+            match volume {
+                0x10..=0x50 => cell.volume = Some(volume - 0x10),
+                0xC0..=0xCF if cell.effect == 0 && cell.param == 0 => {
+                    (cell.effect, cell.param) = (PAN, (volume & 0x0F) * 17);
+                }
+                _ => {}
+            }
+        }
+
+        patterns.push(pattern);
+    }
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders: orders
+            .iter()
+            .copied()
+            .filter(|&order| usize::from(order) < pattern_count)
+            .collect(),
+        restart: if usize::from(restart) < length {
+            restart as u8
+        } else {
+            0
+        },
+        speed: if speed == 0 {
+            6
+        } else {
+            speed.min(0x1F) as u8
+        },
+        tempo: tempo.clamp(32, 255) as u8,
+        panning: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use gba_portable::sound::mixer::{self, Voices};
+    use gba_portable::sound::tracker;
+
+    use super::*;
+
+    // The fixtures are generated by `fixtures/generate.py`.
+    const MOD: &[u8] = include_bytes!("../fixtures/fixture.mod");
+    const S3M: &[u8] = include_bytes!("../fixtures/fixture.s3m");
+    const XM: &[u8] = include_bytes!("../fixtures/fixture.xm");
+
+    // The frames played and output hash of each fixture, from the separate
+    // player in `fixtures/render.py`.
+    const RENDERS: &str = include_str!("../fixtures/renders.txt");
+
+    /// The output rate of the renders, which mixes 176 samples per frame.
+    const RATE: u32 = 10512;
+    const FRAME_SAMPLES: usize = 176;
+    const MAX_FRAMES: usize = 3000;
+
+    fn cell(note: u8, sample: u8, volume: Option<u8>, effect: u8, param: u8) -> Cell {
+        Cell {
+            note,
+            sample,
+            volume,
+            effect,
+            param,
+        }
+    }
+
+    fn check_sample(
+        sample: &Sample,
+        data: &[i8],
+        rate: u32,
+        volume: u8,
+        loop_start: Option<usize>,
+    ) {
+        assert_eq!(sample.data, data);
+        assert_eq!(sample.rate, rate);
+        assert_eq!(sample.volume, volume);
+        assert_eq!(sample.loop_start, loop_start);
+    }
+
+    /// Builds the song that `include_tracker!` generates for a module.
+    fn song(module: &Module) -> &'static tracker::Song {
+        let samples: Vec<_> = module
+            .samples
+            .iter()
+            .map(|sample| {
+                let data = Vec::leak(sample.data.clone());
+                let converted = tracker::Sample::new(data, sample.rate, sample.volume);
+                match sample.loop_start {
+                    Some(start) => converted.with_loop(start),
+                    None => converted,
+                }
+            })
+            .collect();
+        let patterns: Vec<_> = module
+            .patterns
+            .iter()
+            .map(|pattern| {
+                tracker::Pattern::new(pattern.rows.len() as u16, Vec::leak(pattern.encode()))
+            })
+            .collect();
+
+        let song = tracker::Song::new(
+            module.channels as u8,
+            Vec::leak(samples),
+            Vec::leak(patterns),
+            Vec::leak(module.orders.clone()),
+        );
+        Box::leak(Box::new(
+            song.with_restart(module.restart)
+                .with_speed(module.speed)
+                .with_tempo(module.tempo)
+                .with_panning(Vec::leak(module.panning.clone())),
+        ))
+    }
+
+    /// Plays a module to its end, mixing each frame before updating the
+    /// player as on the GBA. Returns the frames played and the FNV-1a hash
+    /// of the 8-bit stereo output.
+    fn render(data: &[u8]) -> (usize, u64) {
+        let mut player = tracker::Player::new(song(&parse(data).unwrap()));
+        player.set_looping(false);
+        player.play();
+
+        let mut voices = Voices::new(RATE);
+        let mut acc = [[0; 2]; FRAME_SAMPLES];
+        let mut hash = 0xCBF2_9CE4_8422_2325;
+        let mut frames = 0;
+        while player.is_playing() && frames < MAX_FRAMES {
+            voices.mix(&mut acc, mixer::mix_stereo_reference);
+            for &value in acc.iter().flatten() {
+                let byte = mixer::clip(value) as u8;
+                hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3);
+            }
+            player.update(&mut voices);
+            frames += 1;
+        }
+
+        (frames, hash)
+    }
+
+    fn reference(name: &str) -> (usize, u64) {
+        let line = RENDERS.lines().find(|line| line.starts_with(name)).unwrap();
+        let fields: Vec<&str> = line.split(' ').collect();
+        (fields[1].parse().unwrap(), u64::from_str_radix(fields[2], 16).unwrap())
+    }
+
+    #[test]
+    fn mod_header() {
+        let module = parse(MOD).unwrap();
+
+        assert_eq!(module.channels, 4);
+        assert_eq!(module.orders, [0, 1, 0]);
+        assert_eq!(module.restart, 1);
+        assert_eq!((module.speed, module.tempo), (6, 125));
+        assert_eq!(module.panning, [0x40, 0xC0, 0xC0, 0x40]);
+        assert_eq!(module.patterns.len(), 2);
+    }
+
+    #[test]
+    fn mod_samples() {
+        let module = parse(MOD).unwrap();
+
+        assert_eq!(module.samples.len(), 31);
+        check_sample(&module.samples[0], &[0, 1, 2, 3, 4, 5, 6, 7], 8363, 48, None);
+        // Finetune -1, looped from 4 to 12
+        let data: Vec<i8> = (0x10..0x1C).collect();
+        check_sample(&module.samples[1], &data, 8303, 64, Some(4));
+        assert!(module.samples[2..].iter().all(|s| s.data.is_empty()));
+    }
+
+    #[test]
+    fn mod_patterns() {
+        let module = parse(MOD).unwrap();
+
+        let rows = &module.patterns[0].rows;
+        assert_eq!(rows.len(), 64);
+        assert_eq!(rows[0][0], cell(49, 1, None, 0xC, 0x20));
+        assert_eq!(rows[0][1], cell(0, 0, None, PAN, 68));
+        assert_eq!(rows[0][2], cell(48, 2, None, SPEED, 3));
+        assert_eq!(rows[0][3], Cell::default());
+        assert_eq!(rows[1][0], cell(0, 0, None, VOLUME_SLIDE, 0x0F));
+
+        assert_eq!(module.patterns[1].rows[0][1], cell(37, 1, None, 0, 0));
+    }
+
+    #[test]
+    fn mod_signatures() {
+        let mut data = vec![0; 1084];
+        let mut channels = |signature: &[u8]| {
+            data[1080..].copy_from_slice(signature);
+            mod_channels(&data)
+        };
+
+        assert_eq!(channels(b"M.K."), Some(4));
+        assert_eq!(channels(b"FLT4"), Some(4));
+        assert_eq!(channels(b"OCTA"), Some(8));
+        assert_eq!(channels(b"6CHN"), Some(6));
+        assert_eq!(channels(b"12CH"), Some(12));
+        assert_eq!(channels(b"XXCH"), None);
+        assert_eq!(channels(b"\0\0\0\0"), None);
+    }
+
+    #[test]
+    fn amiga_notes() {
+        assert_eq!(amiga_note(428), 49);
+        assert_eq!(amiga_note(856), 37);
+        // Between A#3 and B-3, closer to B-3
+        assert_eq!(amiga_note(453), 48);
+    }
+
+    #[test]
+    fn s3m_header() {
+        let module = parse(S3M).unwrap();
+
+        // The AdLib and disabled channels are dropped.
+        assert_eq!(module.channels, 3);
+        // The marker and end of the orders are dropped.
+        assert_eq!(module.orders, [1, 0]);
+        assert_eq!(module.restart, 0);
+        assert_eq!((module.speed, module.tempo), (4, 150));
+        assert_eq!(module.panning, [0x22, 0xCC, 0xFF]);
+    }
+
+    #[test]
+    fn s3m_samples() {
+        let module = parse(S3M).unwrap();
+
+        assert_eq!(module.samples.len(), 2);
+        // Unsigned, looped from 2 to 6, volume clamped to 64
+        check_sample(&module.samples[0], &[0, 16, 32, -16, -32, 127], 16726, 64, Some(2));
+        check_sample(&module.samples[1], &[], 0, 0, None);
+    }
+
+    #[test]
+    fn s3m_patterns() {
+        let module = parse(S3M).unwrap();
+
+        let rows = &module.patterns[0].rows;
+        assert_eq!(rows[0][0], cell(50, 1, Some(40), SPEED, 3));
+        assert_eq!(rows[0][1], cell(0, 0, None, EXTENDED, 0xA2));
+        assert_eq!(rows[0][2], cell(0, 0, Some(10), PAN, 0x80));
+        assert_eq!(rows[1][0], cell(NOTE_OFF, 0, None, 0, 0));
+        assert_eq!(rows[1][1], cell(0, 0, None, EXTENDED, 0x62));
+        assert_eq!(rows[2][0], Cell::default());
+        assert_eq!(rows[2][1], cell(0, 0, None, BREAK, 0));
+
+        // A pattern without data is empty.
+        let rows = &module.patterns[1].rows;
+        assert_eq!(rows.len(), 64);
+        assert!(rows.iter().flatten().all(|&c| c == Cell::default()));
+    }
+
+    #[test]
+    fn s3m_effects() {
+        assert_eq!(s3m_effect(1, 0), (0, 0));
+        assert_eq!(s3m_effect(1, 0x40), (SPEED, 0x1F));
+        assert_eq!(s3m_effect(4, 0xF3), (EXTENDED, 0xB3));
+        assert_eq!(s3m_effect(5, 0xE8), (EXTENDED, 0x22));
+        assert_eq!(s3m_effect(6, 0xF4), (EXTENDED, 0x14));
+        assert_eq!(s3m_effect(6, 0x12), (PORTA_UP, 0x12));
+        assert_eq!(s3m_effect(10, 0x37), (ARPEGGIO, 0x37));
+        assert_eq!(s3m_effect(19, 0x8A), (PAN, 0xAA));
+        assert_eq!(s3m_effect(20, 0x80), (SPEED, 0x80));
+        assert_eq!(s3m_effect(24, 0x90), (PAN, 0xFF));
+    }
+
+    #[test]
+    fn xm_header() {
+        let module = parse(XM).unwrap();
+
+        assert_eq!(module.channels, 2);
+        // Orders of missing patterns are dropped.
+        assert_eq!(module.orders, [1, 0]);
+        assert_eq!(module.restart, 2);
+        assert_eq!((module.speed, module.tempo), (5, 140));
+        assert!(module.panning.is_empty());
+    }
+
+    #[test]
+    fn xm_samples() {
+        let module = parse(XM).unwrap();
+
+        assert_eq!(module.samples.len(), 3);
+        // Delta encoded, relative note +12
+        check_sample(&module.samples[0], &[10, 15, -5, -5], 16726, 50, None);
+        // Finetune -128, volume clamped to 64
+        check_sample(&module.samples[1], &[1, 2], 7894, 64, None);
+        // 16-bit, relative note -12, looped from 1 to 3
+        check_sample(&module.samples[2], &[1, 2, 0], 4182, 32, Some(1));
+    }
+
+    #[test]
+    fn xm_patterns() {
+        let module = parse(XM).unwrap();
+
+        let rows = &module.patterns[0].rows;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][0], cell(49, 1, Some(0x20), EXTENDED, 0xC3));
+        // The keymap of instrument 2 maps the default note to its second
+        // sample.
+        assert_eq!(rows[0][1], cell(0, 3, None, VOLUME_SLIDE, 0x12));
+        assert_eq!(rows[1][0], cell(NOTE_OFF, 0, None, PAN, 68));
+        assert_eq!(rows[1][1], Cell::default());
+
+        assert_eq!(module.patterns[1].rows.len(), 4);
+    }
+
+    #[test]
+    fn encode() {
+        let module = parse(MOD).unwrap();
+
+        let data = module.patterns[0].encode();
+        assert_eq!(data[..18], [
+            0xA0,
+            49,
+            1,
+            0xC,
+            0x20,
+            0x81,
+            PAN,
+            68,
+            0xA2,
+            48,
+            2,
+            SPEED,
+            3,
+            0,
+            0x80,
+            VOLUME_SLIDE,
+            0x0F,
+            0,
+        ]);
+        assert_eq!(data.len(), 18 + 62);
+
+        let mut expected = vec![0x21, 37, 1, 0];
+        expected.extend([0; 63]);
+        assert_eq!(module.patterns[1].encode(), expected);
+
+        let module = parse(S3M).unwrap();
+        assert_eq!(module.patterns[0].encode()[..12], [
+            0xE0, 50, 1, 40, SPEED, 3, 0x81, EXTENDED, 0xA2, 0xC2, 10, PAN
+        ]);
+    }
+
+    #[test]
+    fn render_mod() {
+        assert_eq!(render(MOD), reference("fixture.mod"));
+    }
+
+    #[test]
+    fn render_s3m() {
+        assert_eq!(render(S3M), reference("fixture.s3m"));
+    }
+
+    #[test]
+    fn render_xm() {
+        assert_eq!(render(XM), reference("fixture.xm"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse(b"not a module").unwrap_err(), "Unrecognized module format");
+        assert_eq!(parse(&S3M[..0x100]).unwrap_err(), "Unexpected end of file");
+        assert_eq!(parse(&XM[..100]).unwrap_err(), "Unexpected end of file");
+
+        let mut data = MOD.to_vec();
+        data[1080..1084].copy_from_slice(b"20CH");
+        data.resize(1084 + 64 * 20 * 4 * 2, 0);
+        assert_eq!(parse(&data).unwrap_err(), "Module has 20 channels; at most 16 are supported");
+    }
+}
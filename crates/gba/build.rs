@@ -25,4 +25,12 @@ fn main() {
         .flag("-mcpu=arm7tdmi")
         .file("src/interrupt.S")
         .compile("interrupt");
+
+    cc::Build::new()
+        .compiler("arm-none-eabi-gcc")
+        .no_default_flags(true)
+        .warnings_into_errors(true)
+        .flag("-mcpu=arm7tdmi")
+        .file("src/sound/mixer.S")
+        .compile("mixer");
 }
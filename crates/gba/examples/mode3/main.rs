@@ -7,26 +7,19 @@
 #[rustfmt::skip]
 mod cyberpunk;
 
+use gba::display::Mode3;
 use gba::{bios, entry, interrupt};
 
-const DISPCNT: *mut u16 = 0x0400_0000 as *mut u16;
-const VRAM: *mut u16 = 0x0600_0000 as *mut u16;
-
-const MODE3: u16 = 0x3;
-const ENABLE_BG2: u16 = 1 << 10;
-
 #[entry]
 fn main() {
     interrupt::init(interrupt::master_isr);
     interrupt::enable(interrupt::Irq::VBlank);
 
-    unsafe {
-        DISPCNT.write_volatile(MODE3 | ENABLE_BG2);
-    }
+    let display = Mode3::new();
 
     for (i, &color) in cyberpunk::DATA.iter().enumerate() {
         unsafe {
-            VRAM.add(i).write_volatile(color);
+            display.write(i, color);
         }
     }
 
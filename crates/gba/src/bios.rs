@@ -3,6 +3,8 @@
 
 use core::arch::asm;
 
+use crate::display::{AffineTransform, BgAffineSource};
+
 /// Reset the device.
 #[inline]
 pub unsafe fn reset() {
@@ -55,3 +57,53 @@ pub fn vblank() {
         );
     }
 }
+
+/// Copy or fill memory in units of 16 or 32 bits.
+///
+/// * `control`: Bits 0-20 are the number of units, bit 24 selects fill, and bit
+///   26 selects 32-bit units.
+#[inline]
+pub unsafe fn cpu_set(source: *const u32, dest: *mut u32, control: usize) {
+    asm!("svc 0x0B",
+        inout("r0") source => _,
+        inout("r1") dest => _,
+        inout("r2") control => _,
+        // Clobbers
+        out("r3") _
+    );
+}
+
+/// Copy or fill memory in blocks of 8 words.
+///
+/// * `control`: Bits 0-20 are the number of words, rounded up to a multiple of
+///   8, and bit 24 selects fill.
+#[inline]
+pub unsafe fn cpu_fast_set(source: *const u32, dest: *mut u32, control: usize) {
+    asm!("svc 0x0C",
+        inout("r0") source => _,
+        inout("r1") dest => _,
+        inout("r2") control => _,
+        // Clobbers
+        out("r3") _
+    );
+}
+
+/// Calculate affine background transforms from scale and rotation
+/// parameters.
+///
+/// Writes `count` transforms to `dest`, which may be the affine registers of a
+/// background.
+#[inline]
+pub unsafe fn bg_affine_set(
+    source: *const BgAffineSource,
+    dest: *mut AffineTransform,
+    count: usize,
+) {
+    asm!("svc 0x0E",
+        inout("r0") source => _,
+        inout("r1") dest => _,
+        inout("r2") count => _,
+        // Clobbers
+        out("r3") _
+    );
+}
@@ -37,6 +37,63 @@ impl Color {
     pub const fn blue(&self) -> Self {
         Self((self.0 >> 10) & 0x1F)
     }
+
+    /// Blends two colors as the hardware does for alpha blending.
+    ///
+    /// Each component is `self * eva / 16 + other * evb / 16`, saturating
+    /// at 31. Coefficients greater than 16 are treated as 16.
+    pub const fn blend(self, other: Self, eva: u16, evb: u16) -> Self {
+        let eva = if eva > 16 { 16 } else { eva };
+        let evb = if evb > 16 { 16 } else { evb };
+
+        let mut result = 0;
+        let mut shift = 0;
+        while shift < 15 {
+            let a = (self.0 >> shift) & 0x1F;
+            let b = (other.0 >> shift) & 0x1F;
+            let c = (a * eva + b * evb) / 16;
+            result |= (if c > 0x1F { 0x1F } else { c }) << shift;
+            shift += 5;
+        }
+
+        Self(result)
+    }
+
+    /// Brightens a color as the hardware does for the brighten effect, where
+    /// `evy` is 0-16.
+    ///
+    /// Each component is `c + ((31 - c) * evy) / 16`, rounded down.
+    pub const fn brighten(self, evy: u16) -> Self {
+        let evy = if evy > 16 { 16 } else { evy };
+
+        let mut result = 0;
+        let mut shift = 0;
+        while shift < 15 {
+            let c = (self.0 >> shift) & 0x1F;
+            result |= (c + (((0x1F - c) * evy) >> 4)) << shift;
+            shift += 5;
+        }
+
+        Self(result)
+    }
+
+    /// Darkens a color as the hardware does for the darken effect, where
+    /// `evy` is 0-16.
+    ///
+    /// Each component is `c - (c * evy) / 16`, rounded down.
+    pub const fn darken(self, evy: u16) -> Self {
+        let evy = if evy > 16 { 16 } else { evy };
+
+        let mut result = 0;
+        let mut shift = 0;
+        while shift < 15 {
+            let c = (self.0 >> shift) & 0x1F;
+            result |= (c - ((c * evy) >> 4)) << shift;
+            shift += 5;
+        }
+
+        Self(result)
+    }
 }
 
 impl From<Color> for u16 {
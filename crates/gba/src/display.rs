@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Display control and video modes.
+//!
+//! The tiled modes (0, 1, 2) are configured with [`DisplayControl`] and the
+//! [`Background`] layers. The bitmap modes ([`Mode3`], [`Mode4`], [`Mode5`])
+//! draw directly to VRAM using background 2.
+//!
+//! ```rust
+//! use gba::display::{Background, BackgroundControl, DisplayControl, Layer, Mode};
+//!
+//! DisplayControl::new().with_mode(Mode::Mode0).enable(Layer::Bg0).set();
+//!
+//! Background::Bg0.set_control(BackgroundControl::new().with_char_base(0).with_screen_base(31));
+//! Background::Bg0.set_scroll(0, 0);
+//! ```
+
+mod affine;
+mod background;
+mod effect;
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
+mod mode3;
+mod mode4;
+mod mode5;
+mod tile;
+mod vram;
+mod window;
+
+use gba_portable::display::bitmap::Buffer;
+
+pub use self::affine::{AffineBackground, AffineSize, AffineTransform, BgAffineSource};
+pub use self::background::{Background, BackgroundControl, ColorMode, TextSize};
+pub use self::effect::{BlendControl, BlendEffect, BlendTargets, Fade, Mosaic, BLEND_MAX};
+#[cfg(feature = "embedded-graphics")]
+pub use self::graphics::PaletteIndex;
+pub use self::mode3::Mode3;
+pub use self::mode4::Mode4;
+pub use self::mode5::Mode5;
+pub use self::tile::{Tile4, Tile8, TileMapEntry};
+pub use self::vram::{CharBlock, ScreenBlock, Vram, VramError};
+pub use self::window::{Window, WindowControl, WindowLayers};
+use crate::register::{ReadWrite, Register};
+
+/// The width of the LCD, in pixels.
+pub const WIDTH: usize = 240;
+/// The height of the LCD, in pixels.
+pub const HEIGHT: usize = 160;
+
+/// The start of video RAM.
+const VRAM: *mut u16 = 0x0600_0000 as *mut u16;
+
+/// A frame buffer of the bitmap modes in VRAM.
+#[derive(Debug)]
+struct FrameBuffer(*mut u16);
+
+impl Buffer for FrameBuffer {
+    fn read(&self, index: usize) -> u16 {
+        unsafe { self.0.add(index).read_volatile() }
+    }
+
+    fn write(&mut self, index: usize, value: u16) {
+        unsafe {
+            self.0.add(index).write_volatile(value);
+        }
+    }
+}
+
+/// The memory-mapped display control register.
+const DISPCNT: Register<DisplayControl, ReadWrite, 0x0400_0000> = unsafe { Register::new() };
+
+/// The video mode, which determines how the background layers are drawn.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u16)]
+pub enum Mode {
+    /// BG0-BG3 are text backgrounds.
+    Mode0 = 0,
+    /// BG0 and BG1 are text backgrounds; BG2 is affine.
+    Mode1 = 1,
+    /// BG2 and BG3 are affine backgrounds.
+    Mode2 = 2,
+    /// BG2 is a single 240x160 15-bit bitmap.
+    Mode3 = 3,
+    /// BG2 is two 240x160 8-bit paletted bitmaps.
+    Mode4 = 4,
+    /// BG2 is two 160x128 15-bit bitmaps.
+    Mode5 = 5,
+}
+
+impl Mode {
+    /// Checks if the mode is a bitmap mode.
+    ///
+    /// Bitmap modes use the first half of object VRAM for the frame buffers.
+    pub const fn is_bitmap(self) -> bool {
+        matches!(self, Self::Mode3 | Self::Mode4 | Self::Mode5)
+    }
+}
+
+/// A layer that can be enabled for display.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u16)]
+pub enum Layer {
+    Bg0 = 1 << 8,
+    Bg1 = 1 << 9,
+    Bg2 = 1 << 10,
+    Bg3 = 1 << 11,
+    Obj = 1 << 12,
+}
+
+impl From<Background> for Layer {
+    fn from(bg: Background) -> Self {
+        match bg {
+            Background::Bg0 => Self::Bg0,
+            Background::Bg1 => Self::Bg1,
+            Background::Bg2 => Self::Bg2,
+            Background::Bg3 => Self::Bg3,
+        }
+    }
+}
+
+/// How the tiles of an object are arranged in object VRAM.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ObjMapping {
+    /// Object VRAM is a 32x32 grid of tiles; each row of an object starts 32
+    /// tiles after the previous row.
+    #[default]
+    TwoDimensional,
+    /// The tiles of an object are stored one after the other.
+    OneDimensional,
+}
+
+/// The display control settings.
+///
+/// Settings are built up and then applied with [`set()`](Self::set).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct DisplayControl(u16);
+
+impl DisplayControl {
+    const MODE_MASK: u16 = 0b111;
+    /// Selects the second frame buffer in modes 4 and 5.
+    const SELECT_FRAME: u16 = 1 << 4;
+    /// Allows access to OAM during HBlank.
+    const HBLANK_OAM: u16 = 1 << 5;
+    /// Selects 1D object tile mapping.
+    const OBJ_1D: u16 = 1 << 6;
+    /// Blanks the screen, allowing fast access to VRAM, OAM, and palette RAM.
+    const FORCED_BLANK: u16 = 1 << 7;
+
+    /// Returns settings with mode 0 and all layers disabled.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current display settings.
+    pub fn get() -> Self {
+        DISPCNT.read()
+    }
+
+    /// Applies the display settings.
+    pub fn set(self) {
+        DISPCNT.write(self);
+    }
+
+    /// Returns the selected video mode.
+    pub const fn mode(self) -> Mode {
+        match self.0 & Self::MODE_MASK {
+            0 => Mode::Mode0,
+            1 => Mode::Mode1,
+            2 => Mode::Mode2,
+            3 => Mode::Mode3,
+            4 => Mode::Mode4,
+            // Modes 6 and 7 are invalid.
+            _ => Mode::Mode5,
+        }
+    }
+
+    /// Selects the video mode.
+    pub const fn with_mode(self, mode: Mode) -> Self {
+        Self((self.0 & !Self::MODE_MASK) | mode as u16)
+    }
+
+    /// Checks if the layer is enabled.
+    pub const fn is_enabled(self, layer: Layer) -> bool {
+        (self.0 & layer as u16) != 0
+    }
+
+    /// Enables the layer for display.
+    pub const fn enable(self, layer: Layer) -> Self {
+        Self(self.0 | layer as u16)
+    }
+
+    /// Disables the layer.
+    pub const fn disable(self, layer: Layer) -> Self {
+        Self(self.0 & !(layer as u16))
+    }
+
+    /// Checks if the window is enabled.
+    pub const fn is_window_enabled(self, window: Window) -> bool {
+        (self.0 & window as u16) != 0
+    }
+
+    /// Enables the window.
+    pub const fn enable_window(self, window: Window) -> Self {
+        Self(self.0 | window as u16)
+    }
+
+    /// Disables the window.
+    pub const fn disable_window(self, window: Window) -> Self {
+        Self(self.0 & !(window as u16))
+    }
+
+    /// Selects the other frame buffer in modes 4 and 5.
+    pub const fn flip_frame(self) -> Self {
+        Self(self.0 ^ Self::SELECT_FRAME)
+    }
+
+    /// Allows OAM to be accessed during HBlank, at the cost of fewer objects
+    /// being drawn per line.
+    pub const fn with_hblank_oam(self, enabled: bool) -> Self {
+        self.with_flag(Self::HBLANK_OAM, enabled)
+    }
+
+    /// The layout of object tiles in VRAM.
+    pub const fn obj_mapping(self) -> ObjMapping {
+        if (self.0 & Self::OBJ_1D) != 0 {
+            ObjMapping::OneDimensional
+        } else {
+            ObjMapping::TwoDimensional
+        }
+    }
+
+    /// Sets the layout of object tiles in VRAM.
+    pub const fn with_obj_mapping(self, mapping: ObjMapping) -> Self {
+        self.with_flag(Self::OBJ_1D, matches!(mapping, ObjMapping::OneDimensional))
+    }
+
+    /// Blanks the screen to white.
+    pub const fn with_forced_blank(self, enabled: bool) -> Self {
+        self.with_flag(Self::FORCED_BLANK, enabled)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for DisplayControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DisplayControl> for u16 {
+    fn from(value: DisplayControl) -> Self {
+        value.0
+    }
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Affine (rotation/scaling) background layers.
+//!
+//! In modes 1 and 2, BG2 (and BG3 in mode 2) are drawn by transforming each
+//! screen pixel into the background's texture space:
+//!
+//! ```text
+//! texture_x = x + pa * screen_x + pb * screen_y
+//! texture_y = y + pc * screen_x + pd * screen_y
+//! ```
+//!
+//! The matrix (`pa`-`pd`) is signed 8.8 fixed point. The reference point
+//! (`x`, `y`) is signed 20.8 fixed point, stored in 28 bits.
+//!
+//! The hardware copies the reference point to an internal register at the
+//! start of each frame, and increments it by (`pb`, `pd`) after each line.
+//! Writing the reference point during HBlank replaces the internal value for
+//! the following lines, which allows per-scanline (mode 7) effects. The
+//! [`raster`](crate::raster) effects for the `RasterTarget::Affine*` targets
+//! write the matrix and reference point from a table on every line.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#lcdiobgrotationscaling>
+
+use super::background::{Background, BackgroundControl};
+use crate::bios;
+
+/// The memory-mapped address of BG2PA. The BG3 parameters follow.
+const BGAFFINE: u32 = 0x0400_0020;
+
+/// The size of an affine background, in pixels.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum AffineSize {
+    /// 16x16 tiles
+    #[default]
+    S128x128 = 0,
+    /// 32x32 tiles
+    S256x256 = 1,
+    /// 64x64 tiles
+    S512x512 = 2,
+    /// 128x128 tiles
+    S1024x1024 = 3,
+}
+
+impl AffineSize {
+    /// Returns the number of screenblocks the background map uses.
+    ///
+    /// Affine maps use 1 byte per entry.
+    pub const fn screenblocks(self) -> usize {
+        match self {
+            Self::S128x128 | Self::S256x256 => 1,
+            Self::S512x512 => 2,
+            Self::S1024x1024 => 8,
+        }
+    }
+}
+
+/// The transformation applied to an affine background.
+///
+/// The layout matches the hardware registers and the output of the BIOS
+/// `BgAffineSet` function.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct AffineTransform {
+    /// Texture x increment per screen pixel (8.8)
+    pub pa: i16,
+    /// Texture x increment per screen line (8.8)
+    pub pb: i16,
+    /// Texture y increment per screen pixel (8.8)
+    pub pc: i16,
+    /// Texture y increment per screen line (8.8)
+    pub pd: i16,
+    /// Texture x of the top left of the screen (20.8)
+    pub x: i32,
+    /// Texture y of the top left of the screen (20.8)
+    pub y: i32,
+}
+
+impl AffineTransform {
+    /// No rotation or scaling.
+    pub const IDENTITY: Self = Self {
+        pa: 1 << 8,
+        pb: 0,
+        pc: 0,
+        pd: 1 << 8,
+        x: 0,
+        y: 0,
+    };
+}
+
+impl Default for AffineTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Parameters for calculating an [`AffineTransform`] with the BIOS.
+///
+/// The texture point (`texture_x`, `texture_y`) is drawn at the screen point
+/// (`screen_x`, `screen_y`), scaled and then rotated counter-clockwise around
+/// it.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct BgAffineSource {
+    /// Texture x of the center of rotation (20.8)
+    pub texture_x: i32,
+    /// Texture y of the center of rotation (20.8)
+    pub texture_y: i32,
+    /// Screen x of the center of rotation
+    pub screen_x: i16,
+    /// Screen y of the center of rotation
+    pub screen_y: i16,
+    /// Horizontal scale (8.8), where larger values shrink the background
+    pub scale_x: i16,
+    /// Vertical scale (8.8), where larger values shrink the background
+    pub scale_y: i16,
+    /// Rotation angle, where 0x10000 is a full turn. Only the upper 8 bits
+    /// are used.
+    pub angle: u16,
+}
+
+/// A background layer that can be rotated and scaled.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AffineBackground {
+    /// Affine in modes 1 and 2
+    Bg2 = 0,
+    /// Affine in mode 2
+    Bg3 = 1,
+}
+
+impl AffineBackground {
+    /// Returns the control settings of the background.
+    pub fn control(self) -> BackgroundControl {
+        Background::from(self).control()
+    }
+
+    /// Applies the control settings to the background.
+    pub fn set_control(self, control: BackgroundControl) {
+        Background::from(self).set_control(control);
+    }
+
+    /// Sets the matrix and reference point of the background.
+    pub fn set_transform(self, transform: &AffineTransform) {
+        self.set_matrix(transform.pa, transform.pb, transform.pc, transform.pd);
+        self.set_reference_point(transform.x, transform.y);
+    }
+
+    /// Sets the 8.8 fixed-point matrix of the background.
+    pub fn set_matrix(self, pa: i16, pb: i16, pc: i16, pd: i16) {
+        let regs = self.registers() as *mut i16;
+        unsafe {
+            regs.write_volatile(pa);
+            regs.add(1).write_volatile(pb);
+            regs.add(2).write_volatile(pc);
+            regs.add(3).write_volatile(pd);
+        }
+    }
+
+    /// Sets the 20.8 fixed-point reference point of the background.
+    ///
+    /// When written during HBlank, the new point is used from the next line.
+    /// To change it on every line, use a raster effect for
+    /// [`RasterTarget::AffineX`](crate::raster::RasterTarget::AffineX) and
+    /// [`RasterTarget::AffineY`](crate::raster::RasterTarget::AffineY).
+    pub fn set_reference_point(self, x: i32, y: i32) {
+        let regs = unsafe { (self.registers() as *mut i32).add(2) };
+        unsafe {
+            regs.write_volatile(x & 0x0FFF_FFFF);
+            regs.add(1).write_volatile(y & 0x0FFF_FFFF);
+        }
+    }
+
+    /// Calculates the transform with the BIOS and applies it to the
+    /// background.
+    pub fn set_rotation_scale(self, source: &BgAffineSource) {
+        unsafe {
+            bios::bg_affine_set(source, self.registers(), 1);
+        }
+    }
+
+    fn registers(self) -> *mut AffineTransform {
+        (BGAFFINE + 0x10 * self as u32) as *mut AffineTransform
+    }
+}
+
+impl From<AffineBackground> for Background {
+    fn from(bg: AffineBackground) -> Self {
+        match bg {
+            AffineBackground::Bg2 => Self::Bg2,
+            AffineBackground::Bg3 => Self::Bg3,
+        }
+    }
+}
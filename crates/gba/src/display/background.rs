@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Tiled background layers.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#lcdiobgcontrol>
+
+use super::affine::AffineSize;
+
+/// The memory-mapped address of BG0CNT. BG1CNT-BG3CNT follow.
+const BGCNT: u32 = 0x0400_0008;
+/// The memory-mapped address of BG0HOFS. The offsets for BG1-BG3 follow.
+const BGOFS: u32 = 0x0400_0010;
+
+/// A background layer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Background {
+    Bg0 = 0,
+    Bg1 = 1,
+    Bg2 = 2,
+    Bg3 = 3,
+}
+
+impl Background {
+    /// Returns the control settings of the background.
+    pub fn control(self) -> BackgroundControl {
+        unsafe { BackgroundControl(self.control_register().read_volatile()) }
+    }
+
+    /// Applies the control settings to the background.
+    pub fn set_control(self, control: BackgroundControl) {
+        unsafe {
+            self.control_register().write_volatile(control.0);
+        }
+    }
+
+    /// Sets the scroll offset of a text background.
+    ///
+    /// Only the lower 9 bits of each offset are used. The offset registers are
+    /// write-only.
+    pub fn set_scroll(self, x: u16, y: u16) {
+        let hofs = (BGOFS + 4 * self as u32) as *mut u16;
+        unsafe {
+            hofs.write_volatile(x & 0x1FF);
+            hofs.add(1).write_volatile(y & 0x1FF);
+        }
+    }
+
+    fn control_register(self) -> *mut u16 {
+        (BGCNT + 2 * self as u32) as *mut u16
+    }
+}
+
+/// The number of bits used to represent each pixel of a tile.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ColorMode {
+    /// 16 colors from one of 16 palette banks.
+    #[default]
+    Bpp4 = 0,
+    /// 256 colors from a single palette.
+    Bpp8 = 1,
+}
+
+/// The size of a text background, in pixels.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum TextSize {
+    /// 32x32 tiles; uses 1 screenblock.
+    #[default]
+    S256x256 = 0,
+    /// 64x32 tiles; uses 2 screenblocks.
+    S512x256 = 1,
+    /// 32x64 tiles; uses 2 screenblocks.
+    S256x512 = 2,
+    /// 64x64 tiles; uses 4 screenblocks.
+    S512x512 = 3,
+}
+
+impl TextSize {
+    /// Returns the number of screenblocks the background map uses.
+    pub const fn screenblocks(self) -> usize {
+        match self {
+            Self::S256x256 => 1,
+            Self::S512x256 | Self::S256x512 => 2,
+            Self::S512x512 => 4,
+        }
+    }
+}
+
+/// The control settings of a background layer.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct BackgroundControl(u16);
+
+impl BackgroundControl {
+    const PRIORITY_MASK: u16 = 0b11;
+    const CHAR_BASE_SHIFT: u16 = 2;
+    const CHAR_BASE_MASK: u16 = 0b11 << Self::CHAR_BASE_SHIFT;
+    const MOSAIC: u16 = 1 << 6;
+    const COLOR_MODE_SHIFT: u16 = 7;
+    const SCREEN_BASE_SHIFT: u16 = 8;
+    const SCREEN_BASE_MASK: u16 = 0b1_1111 << Self::SCREEN_BASE_SHIFT;
+    const WRAPAROUND: u16 = 1 << 13;
+    const SIZE_SHIFT: u16 = 14;
+    const SIZE_MASK: u16 = 0b11 << Self::SIZE_SHIFT;
+
+    /// Returns settings using charblock 0, screenblock 0, and the highest
+    /// priority.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// The drawing priority, where 0 is drawn in front.
+    pub const fn priority(self) -> u16 {
+        self.0 & Self::PRIORITY_MASK
+    }
+
+    /// Sets the drawing priority (0-3).
+    pub const fn with_priority(self, priority: u16) -> Self {
+        Self((self.0 & !Self::PRIORITY_MASK) | (priority & Self::PRIORITY_MASK))
+    }
+
+    /// The charblock (0-3) the tile data is read from.
+    pub const fn char_base(self) -> u16 {
+        (self.0 & Self::CHAR_BASE_MASK) >> Self::CHAR_BASE_SHIFT
+    }
+
+    /// Sets the charblock (0-3) the tile data is read from.
+    pub const fn with_char_base(self, block: u16) -> Self {
+        let block = (block << Self::CHAR_BASE_SHIFT) & Self::CHAR_BASE_MASK;
+        Self((self.0 & !Self::CHAR_BASE_MASK) | block)
+    }
+
+    /// Checks if the mosaic effect is applied.
+    pub const fn mosaic(self) -> bool {
+        (self.0 & Self::MOSAIC) != 0
+    }
+
+    /// Sets if the mosaic effect is applied.
+    pub const fn with_mosaic(self, enabled: bool) -> Self {
+        self.with_flag(Self::MOSAIC, enabled)
+    }
+
+    /// The number of bits per pixel of the tile data.
+    pub const fn color_mode(self) -> ColorMode {
+        if (self.0 >> Self::COLOR_MODE_SHIFT) & 1 == 1 {
+            ColorMode::Bpp8
+        } else {
+            ColorMode::Bpp4
+        }
+    }
+
+    /// Sets the number of bits per pixel of the tile data.
+    pub const fn with_color_mode(self, mode: ColorMode) -> Self {
+        self.with_flag(1 << Self::COLOR_MODE_SHIFT, matches!(mode, ColorMode::Bpp8))
+    }
+
+    /// The screenblock (0-31) the map is read from.
+    pub const fn screen_base(self) -> u16 {
+        (self.0 & Self::SCREEN_BASE_MASK) >> Self::SCREEN_BASE_SHIFT
+    }
+
+    /// Sets the screenblock (0-31) the map is read from.
+    pub const fn with_screen_base(self, block: u16) -> Self {
+        let block = (block << Self::SCREEN_BASE_SHIFT) & Self::SCREEN_BASE_MASK;
+        Self((self.0 & !Self::SCREEN_BASE_MASK) | block)
+    }
+
+    /// Checks if an affine background wraps around at its edges.
+    pub const fn wraparound(self) -> bool {
+        (self.0 & Self::WRAPAROUND) != 0
+    }
+
+    /// Sets if an affine background wraps around at its edges.
+    ///
+    /// Text backgrounds always wrap around.
+    pub const fn with_wraparound(self, enabled: bool) -> Self {
+        self.with_flag(Self::WRAPAROUND, enabled)
+    }
+
+    /// The size of a text background.
+    pub const fn size(self) -> TextSize {
+        match (self.0 & Self::SIZE_MASK) >> Self::SIZE_SHIFT {
+            0 => TextSize::S256x256,
+            1 => TextSize::S512x256,
+            2 => TextSize::S256x512,
+            _ => TextSize::S512x512,
+        }
+    }
+
+    /// Sets the size of a text background.
+    pub const fn with_size(self, size: TextSize) -> Self {
+        let size = (size as u16) << Self::SIZE_SHIFT;
+        Self((self.0 & !Self::SIZE_MASK) | size)
+    }
+
+    /// The size of an affine background.
+    pub const fn affine_size(self) -> AffineSize {
+        match (self.0 & Self::SIZE_MASK) >> Self::SIZE_SHIFT {
+            0 => AffineSize::S128x128,
+            1 => AffineSize::S256x256,
+            2 => AffineSize::S512x512,
+            _ => AffineSize::S1024x1024,
+        }
+    }
+
+    /// Sets the size of an affine background.
+    pub const fn with_affine_size(self, size: AffineSize) -> Self {
+        let size = (size as u16) << Self::SIZE_SHIFT;
+        Self((self.0 & !Self::SIZE_MASK) | size)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for BackgroundControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BackgroundControl> for u16 {
+    fn from(value: BackgroundControl) -> Self {
+        value.0
+    }
+}
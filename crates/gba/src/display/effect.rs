@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Color special effects and mosaic.
+//!
+//! Blending mixes the pixels of the first target layers with those of the
+//! second target layers beneath them. Brightness effects fade the first
+//! target layers toward white or black, without modifying palette RAM.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#lcdiocolorspecialeffects>
+//!
+//! ```rust
+//! use gba::display::{BlendControl, BlendEffect, BlendTargets, Fade, Layer};
+//!
+//! // Draw BG1 at 50% over BG0 and the backdrop.
+//! BlendControl::new()
+//!     .with_effect(BlendEffect::Alpha)
+//!     .with_first(BlendTargets::new().with_layer(Layer::Bg1, true))
+//!     .with_second(BlendTargets::new().with_layer(Layer::Bg0, true).with_backdrop(true))
+//!     .set();
+//! BlendControl::set_alpha(8, 8);
+//!
+//! // Fade the whole screen to black over 30 frames.
+//! let mut fade = Fade::to_black(BlendTargets::all(), 30);
+//! fade.start();
+//! while !fade.update() {
+//!     gba::bios::vblank();
+//! }
+//! ```
+
+use super::Layer;
+use crate::color::Color;
+use crate::register::{ReadWrite, Register, WriteOnly};
+
+const MOSAIC: Register<Mosaic, WriteOnly, 0x0400_004C> = unsafe { Register::new() };
+const BLDCNT: Register<BlendControl, ReadWrite, 0x0400_0050> = unsafe { Register::new() };
+const BLDALPHA: Register<u16, WriteOnly, 0x0400_0052> = unsafe { Register::new() };
+const BLDY: Register<u16, WriteOnly, 0x0400_0054> = unsafe { Register::new() };
+
+/// The maximum value of a blend coefficient, representing 16/16.
+pub const BLEND_MAX: u16 = 16;
+
+/// The color special effect.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum BlendEffect {
+    /// No effect, except for semi-transparent objects.
+    #[default]
+    None = 0,
+    /// Blend the first targets with the second targets.
+    Alpha = 1,
+    /// Fade the first targets toward white.
+    Brighten = 2,
+    /// Fade the first targets toward black.
+    Darken = 3,
+}
+
+/// A set of layers used as blend targets.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct BlendTargets(u8);
+
+impl BlendTargets {
+    const LAYER_SHIFT: u16 = 8;
+    const MASK: u8 = 0b11_1111;
+    const BACKDROP: u8 = 1 << 5;
+
+    /// Returns an empty set of targets.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns all layers and the backdrop.
+    pub const fn all() -> Self {
+        Self(Self::MASK)
+    }
+
+    /// Checks if the layer is a target.
+    pub const fn contains(self, layer: Layer) -> bool {
+        (self.0 & Self::layer_bit(layer)) != 0
+    }
+
+    /// Sets if the layer is a target.
+    pub const fn with_layer(self, layer: Layer, enabled: bool) -> Self {
+        self.with_flag(Self::layer_bit(layer), enabled)
+    }
+
+    /// Checks if the backdrop is a target.
+    pub const fn backdrop(self) -> bool {
+        (self.0 & Self::BACKDROP) != 0
+    }
+
+    /// Sets if the backdrop is a target.
+    pub const fn with_backdrop(self, enabled: bool) -> Self {
+        self.with_flag(Self::BACKDROP, enabled)
+    }
+
+    const fn layer_bit(layer: Layer) -> u8 {
+        // The layer bits match the order in DISPCNT.
+        ((layer as u16) >> Self::LAYER_SHIFT) as u8
+    }
+
+    const fn with_flag(self, flag: u8, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+/// The blend control settings, from BLDCNT.
+///
+/// Settings are built up and then applied with [`set()`](Self::set).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct BlendControl(u16);
+
+impl BlendControl {
+    const FIRST_MASK: u16 = 0b11_1111;
+    const EFFECT_SHIFT: u16 = 6;
+    const EFFECT_MASK: u16 = 0b11 << Self::EFFECT_SHIFT;
+    const SECOND_SHIFT: u16 = 8;
+    const SECOND_MASK: u16 = 0b11_1111 << Self::SECOND_SHIFT;
+
+    /// Returns settings with no effect and no targets.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current blend settings.
+    pub fn get() -> Self {
+        BLDCNT.read()
+    }
+
+    /// Applies the blend settings.
+    pub fn set(self) {
+        BLDCNT.write(self);
+    }
+
+    /// Sets the coefficients for alpha blending, in 1/16ths.
+    ///
+    /// The result is `first * eva / 16 + second * evb / 16`. Values greater
+    /// than 16 are treated as 16. The register is write-only.
+    pub fn set_alpha(eva: u16, evb: u16) {
+        BLDALPHA.write(clamp(eva) | (clamp(evb) << 8));
+    }
+
+    /// Sets the coefficient for brightness effects, in 1/16ths.
+    ///
+    /// Values greater than 16 are treated as 16. The register is write-only.
+    pub fn set_brightness(evy: u16) {
+        BLDY.write(clamp(evy));
+    }
+
+    /// The color special effect.
+    pub const fn effect(self) -> BlendEffect {
+        match (self.0 & Self::EFFECT_MASK) >> Self::EFFECT_SHIFT {
+            1 => BlendEffect::Alpha,
+            2 => BlendEffect::Brighten,
+            3 => BlendEffect::Darken,
+            _ => BlendEffect::None,
+        }
+    }
+
+    /// Sets the color special effect.
+    pub const fn with_effect(self, effect: BlendEffect) -> Self {
+        let effect = (effect as u16) << Self::EFFECT_SHIFT;
+        Self((self.0 & !Self::EFFECT_MASK) | effect)
+    }
+
+    /// The layers the effect is applied to.
+    pub const fn first(self) -> BlendTargets {
+        BlendTargets((self.0 & Self::FIRST_MASK) as u8)
+    }
+
+    /// Sets the layers the effect is applied to.
+    pub const fn with_first(self, targets: BlendTargets) -> Self {
+        Self((self.0 & !Self::FIRST_MASK) | targets.0 as u16)
+    }
+
+    /// The layers the first targets are blended with.
+    pub const fn second(self) -> BlendTargets {
+        BlendTargets(((self.0 & Self::SECOND_MASK) >> Self::SECOND_SHIFT) as u8)
+    }
+
+    /// Sets the layers the first targets are blended with.
+    pub const fn with_second(self, targets: BlendTargets) -> Self {
+        let targets = (targets.0 as u16) << Self::SECOND_SHIFT;
+        Self((self.0 & !Self::SECOND_MASK) | targets)
+    }
+}
+
+impl From<u16> for BlendControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BlendControl> for u16 {
+    fn from(value: BlendControl) -> Self {
+        value.0
+    }
+}
+
+/// A hardware fade of the first target layers, driven one frame at a time
+/// through BLDY.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Fade {
+    targets: BlendTargets,
+    effect: BlendEffect,
+    from: u16,
+    to: u16,
+    duration: u16,
+    elapsed: u16,
+}
+
+impl Fade {
+    /// Fades from the normal colors to black.
+    pub const fn to_black(targets: BlendTargets, frames: u16) -> Self {
+        Self::new(targets, BlendEffect::Darken, 0, BLEND_MAX, frames)
+    }
+
+    /// Fades from black to the normal colors.
+    pub const fn from_black(targets: BlendTargets, frames: u16) -> Self {
+        Self::new(targets, BlendEffect::Darken, BLEND_MAX, 0, frames)
+    }
+
+    /// Fades from the normal colors to white.
+    pub const fn to_white(targets: BlendTargets, frames: u16) -> Self {
+        Self::new(targets, BlendEffect::Brighten, 0, BLEND_MAX, frames)
+    }
+
+    /// Fades from white to the normal colors.
+    pub const fn from_white(targets: BlendTargets, frames: u16) -> Self {
+        Self::new(targets, BlendEffect::Brighten, BLEND_MAX, 0, frames)
+    }
+
+    const fn new(
+        targets: BlendTargets,
+        effect: BlendEffect,
+        from: u16,
+        to: u16,
+        duration: u16,
+    ) -> Self {
+        Self {
+            targets,
+            effect,
+            from,
+            to,
+            duration,
+            elapsed: 0,
+        }
+    }
+
+    /// Configures BLDCNT for the fade and applies the starting level.
+    ///
+    /// The second targets are left unchanged.
+    pub fn start(&mut self) {
+        self.elapsed = 0;
+        BlendControl::get().with_effect(self.effect).with_first(self.targets).set();
+        BlendControl::set_brightness(self.level());
+    }
+
+    /// Advances the fade by one frame and applies the new level.
+    ///
+    /// Returns `true` once the fade has completed.
+    pub fn update(&mut self) -> bool {
+        if self.elapsed < self.duration {
+            self.elapsed += 1;
+        }
+
+        BlendControl::set_brightness(self.level());
+        self.is_finished()
+    }
+
+    /// Checks if the fade has completed.
+    pub const fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// The current brightness coefficient (0-16).
+    pub const fn level(&self) -> u16 {
+        if self.duration == 0 {
+            return self.to;
+        }
+
+        let (from, to) = (self.from as u32, self.to as u32);
+        let (elapsed, duration) = (self.elapsed as u32, self.duration as u32);
+        if to >= from {
+            (from + (to - from) * elapsed / duration) as u16
+        } else {
+            (from - (from - to) * elapsed / duration) as u16
+        }
+    }
+
+    /// Returns the color as it currently appears on a target layer.
+    ///
+    /// This matches the hardware, which can be used to fade colors that are
+    /// not covered by the effect, such as by writing them to palette RAM.
+    pub const fn apply(&self, color: Color) -> Color {
+        match self.effect {
+            BlendEffect::Brighten => color.brighten(self.level()),
+            _ => color.darken(self.level()),
+        }
+    }
+}
+
+/// The mosaic sizes, from MOSAIC.
+///
+/// Each size is the number of pixels (1-16) that are drawn the same color.
+/// Layers and objects must also have mosaic enabled. The register is
+/// write-only.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct Mosaic(u16);
+
+impl Mosaic {
+    const BG_SHIFT: u16 = 0;
+    const OBJ_SHIFT: u16 = 8;
+
+    /// Returns settings with no mosaic (1x1 pixels).
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Applies the mosaic settings.
+    pub fn set(self) {
+        MOSAIC.write(self);
+    }
+
+    /// Sets the size of the mosaic for backgrounds.
+    pub const fn with_bg_size(self, width: u16, height: u16) -> Self {
+        self.with_size(Self::BG_SHIFT, width, height)
+    }
+
+    /// Sets the size of the mosaic for objects.
+    pub const fn with_obj_size(self, width: u16, height: u16) -> Self {
+        self.with_size(Self::OBJ_SHIFT, width, height)
+    }
+
+    const fn with_size(self, shift: u16, width: u16, height: u16) -> Self {
+        // Each size is stored as 0-15 for 1-16 pixels.
+        let width = clamp(width).saturating_sub(1);
+        let height = clamp(height).saturating_sub(1);
+        let mask = 0xFF << shift;
+        Self((self.0 & !mask) | ((width | height << 4) << shift))
+    }
+}
+
+impl From<u16> for Mosaic {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Mosaic> for u16 {
+    fn from(value: Mosaic) -> Self {
+        value.0
+    }
+}
+
+const fn clamp(coefficient: u16) -> u16 {
+    if coefficient > BLEND_MAX {
+        BLEND_MAX
+    } else {
+        coefficient
+    }
+}
@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! [embedded-graphics] support for the bitmap modes.
+//!
+//! Modes 3 and 5 draw [`Rgb555`] colors. Mode 4 draws [`PaletteIndex`]
+//! colors, which refer to entries in the background palette. Clipping and
+//! the pairing of mode 4 pixels into halfwords are done by
+//! [`gba_portable::display::bitmap`].
+//!
+//! [embedded-graphics]: https://docs.rs/embedded-graphics
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{Dimensions, OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::raw::RawU8;
+use embedded_graphics_core::pixelcolor::{PixelColor, Rgb555, RgbColor};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+use gba_portable::display::bitmap::Area;
+
+use super::{Mode3, Mode4, Mode5};
+use crate::color::Color;
+
+/// A color represented as an index into the background palette.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct PaletteIndex(pub u8);
+
+impl PixelColor for PaletteIndex {
+    type Raw = RawU8;
+}
+
+impl From<u8> for PaletteIndex {
+    fn from(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Rgb555> for Color {
+    fn from(color: Rgb555) -> Self {
+        Self::new(u16::from(color.r()), u16::from(color.g()), u16::from(color.b()))
+    }
+}
+
+impl From<Color> for Rgb555 {
+    fn from(color: Color) -> Self {
+        let value = u16::from(color);
+        let red = (value & 0x1F) as u8;
+        let green = ((value >> 5) & 0x1F) as u8;
+        let blue = ((value >> 10) & 0x1F) as u8;
+        Self::new(red, green, blue)
+    }
+}
+
+/// Converts a rectangle to the area of a
+/// [`bitmap`](gba_portable::display::bitmap).
+fn area(rect: &Rectangle) -> Area {
+    Area::new(rect.top_left.x, rect.top_left.y, rect.size.width, rect.size.height)
+}
+
+macro_rules! impl_rgb555 {
+    ($mode:ty) => {
+        impl OriginDimensions for $mode {
+            fn size(&self) -> Size {
+                Size::new(self.width() as u32, self.height() as u32)
+            }
+        }
+
+        impl DrawTarget for $mode {
+            type Color = Rgb555;
+            type Error = Infallible;
+
+            fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Pixel<Self::Color>>,
+            {
+                let mut bitmap = self.bitmap();
+                for Pixel(point, color) in pixels {
+                    bitmap.set_pixel(point.x, point.y, u16::from(Color::from(color)));
+                }
+
+                Ok(())
+            }
+
+            fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Self::Color>,
+            {
+                let colors = colors.into_iter().map(|color| u16::from(Color::from(color)));
+                self.bitmap().fill_contiguous(self::area(area), colors);
+                Ok(())
+            }
+
+            fn fill_solid(
+                &mut self,
+                area: &Rectangle,
+                color: Self::Color,
+            ) -> Result<(), Self::Error> {
+                self.bitmap().fill_solid(self::area(area), u16::from(Color::from(color)));
+                Ok(())
+            }
+
+            fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+                self.fill_solid(&self.bounding_box(), color)
+            }
+        }
+    };
+}
+
+impl_rgb555!(Mode3);
+impl_rgb555!(Mode5);
+
+impl OriginDimensions for Mode4 {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
+
+impl DrawTarget for Mode4 {
+    type Color = PaletteIndex;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mut bitmap = self.bitmap();
+        for Pixel(point, color) in pixels {
+            bitmap.set_pixel(point.x, point.y, color.0);
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let indexes = colors.into_iter().map(|color| color.0);
+        self.bitmap().fill_contiguous(self::area(area), indexes);
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.bitmap().fill_solid(self::area(area), color.0);
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill_solid(&self.bounding_box(), color)
+    }
+}
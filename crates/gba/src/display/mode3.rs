@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+#[cfg(feature = "embedded-graphics")]
+use gba_portable::display::bitmap::Bitmap16;
+
+use super::{DisplayControl, Layer, Mode, HEIGHT, VRAM, WIDTH};
+use crate::color::Color;
+
+/// A single 240x160 frame buffer of 15-bit colors.
+pub struct Mode3 {
+    vram: *mut u16,
+}
+
+impl Mode3 {
+    const HEIGHT: usize = HEIGHT;
+    const WIDTH: usize = WIDTH;
+
+    pub fn new() -> Self {
+        DisplayControl::new().with_mode(Mode::Mode3).enable(Layer::Bg2).set();
+        Self { vram: VRAM }
+    }
+
+    pub const fn height(&self) -> usize {
+        Self::HEIGHT
+    }
+
+    pub const fn width(&self) -> usize {
+        Self::WIDTH
+    }
+
+    /// The current frame buffer, for drawing with clipping.
+    #[cfg(feature = "embedded-graphics")]
+    pub(super) fn bitmap(&self) -> Bitmap16<super::FrameBuffer> {
+        Bitmap16::new(super::FrameBuffer(self.vram), self.width(), self.height())
+    }
+
+    pub unsafe fn write(&self, offset: usize, value: u16) {
+        self.vram.add(offset).write_volatile(value);
+    }
+
+    /// Set the pixel at (x, y) to the given color.
+    pub fn draw(&self, x: usize, y: usize, color: Color) {
+        if x >= self.width() || y >= self.height() {
+            // No-op if not visible
+            return;
+        }
+
+        unsafe {
+            self.write(x + y * self.width(), u16::from(color));
+        }
+    }
+
+    /// Set every pixel of the frame to the given color.
+    pub fn fill(&self, color: Color) {
+        let value = u16::from(color);
+        for offset in 0..(self.width() * self.height()) {
+            unsafe {
+                self.write(offset, value);
+            }
+        }
+    }
+}
+
+impl Default for Mode3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+use gba_portable::display::bitmap::Bitmap8;
+
+use super::{DisplayControl, FrameBuffer, Layer, Mode, HEIGHT, VRAM, WIDTH};
+
+/// Two 240x160 frame buffers of 8-bit palette indexes.
+pub struct Mode4 {
+    vram: *mut u16,
+}
+
+impl Mode4 {
+    const FRAME_SIZE: usize = 0xA000;
+    const HEIGHT: usize = HEIGHT;
+    const WIDTH: usize = WIDTH;
+
+    pub fn new() -> Self {
+        DisplayControl::new().with_mode(Mode::Mode4).enable(Layer::Bg2).set();
+        Self { vram: VRAM }
+    }
+
+    pub const fn height(&self) -> usize {
+        Self::HEIGHT
+    }
+
+    pub const fn width(&self) -> usize {
+        Self::WIDTH
+    }
+
+    pub fn vflip(&mut self) {
+        self.vram = (self.vram as usize ^ Self::FRAME_SIZE) as *mut u16;
+        DisplayControl::get().flip_frame().set();
+    }
+
+    /// The current frame buffer, for drawing with clipping.
+    pub(super) fn bitmap(&self) -> Bitmap8<FrameBuffer> {
+        Bitmap8::new(FrameBuffer(self.vram), self.width(), self.height())
+    }
+
+    pub unsafe fn write(&self, offset: usize, value: u16) {
+        self.vram.add(offset).write_volatile(value);
+    }
+
+    // Set the pixel at (x, y) to the color of the given palette index
+    pub fn draw_index(&self, x: usize, y: usize, color: u8) {
+        // In mode 4, each pixel is a byte, representing the palette index of
+        // the color. However, VRAM must be accessed with u16 or u32, so the
+        // bitmap keeps the other pixel of the halfword.
+        if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+            self.bitmap().set_pixel(x, y, color);
+        }
+    }
+}
+
+impl Default for Mode4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+#[cfg(feature = "embedded-graphics")]
+use gba_portable::display::bitmap::Bitmap16;
+
+use super::{DisplayControl, Layer, Mode, VRAM};
+use crate::color::Color;
+
+/// Two 160x128 frame buffers of 15-bit colors.
+pub struct Mode5 {
+    vram: *mut u16,
+}
+
+impl Mode5 {
+    const FRAME_SIZE: usize = 0xA000;
+    const HEIGHT: usize = 128;
+    const WIDTH: usize = 160;
+
+    pub fn new() -> Self {
+        DisplayControl::new().with_mode(Mode::Mode5).enable(Layer::Bg2).set();
+        Self { vram: VRAM }
+    }
+
+    pub const fn height(&self) -> usize {
+        Self::HEIGHT
+    }
+
+    pub const fn width(&self) -> usize {
+        Self::WIDTH
+    }
+
+    pub fn vflip(&mut self) {
+        self.vram = (self.vram as usize ^ Self::FRAME_SIZE) as *mut u16;
+        DisplayControl::get().flip_frame().set();
+    }
+
+    /// The current frame buffer, for drawing with clipping.
+    #[cfg(feature = "embedded-graphics")]
+    pub(super) fn bitmap(&self) -> Bitmap16<super::FrameBuffer> {
+        Bitmap16::new(super::FrameBuffer(self.vram), self.width(), self.height())
+    }
+
+    pub unsafe fn write(&self, offset: usize, value: u16) {
+        self.vram.add(offset).write_volatile(value);
+    }
+
+    /// Set the pixel at (x, y) to the given color.
+    pub fn draw(&self, x: usize, y: usize, color: Color) {
+        if x >= self.width() || y >= self.height() {
+            // No-op if not visible
+            return;
+        }
+
+        unsafe {
+            self.write(x + y * self.width(), u16::from(color));
+        }
+    }
+}
+
+impl Default for Mode5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
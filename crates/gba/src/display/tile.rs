@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Tile graphics and background map entries.
+
+/// An 8x8 tile using 4 bits per pixel.
+///
+/// Each pixel is an index into a 16-color palette bank. The lower nibble of a
+/// byte is the left pixel.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(C, align(4))]
+pub struct Tile4(pub [u32; 8]);
+
+/// An 8x8 tile using 8 bits per pixel.
+///
+/// Each pixel is an index into the 256-color palette.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(C, align(4))]
+pub struct Tile8(pub [u32; 16]);
+
+/// An entry of a text background map.
+///
+/// - Bits 0-9: Tile index
+/// - Bit 10: Horizontal flip
+/// - Bit 11: Vertical flip
+/// - Bits 12-15: Palette bank, for 4bpp tiles
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct TileMapEntry(u16);
+
+impl TileMapEntry {
+    const TILE_MASK: u16 = 0x3FF;
+    const HFLIP: u16 = 1 << 10;
+    const VFLIP: u16 = 1 << 11;
+    const PALETTE_SHIFT: u16 = 12;
+
+    /// Returns an entry for the tile index, with no flips and palette bank 0.
+    pub const fn new(tile: u16) -> Self {
+        Self(tile & Self::TILE_MASK)
+    }
+
+    /// The index of the tile in the charblock.
+    pub const fn tile(self) -> u16 {
+        self.0 & Self::TILE_MASK
+    }
+
+    /// Sets the index of the tile in the charblock.
+    pub const fn with_tile(self, tile: u16) -> Self {
+        Self((self.0 & !Self::TILE_MASK) | (tile & Self::TILE_MASK))
+    }
+
+    /// Checks if the tile is flipped horizontally.
+    pub const fn hflip(self) -> bool {
+        (self.0 & Self::HFLIP) != 0
+    }
+
+    /// Sets if the tile is flipped horizontally.
+    pub const fn with_hflip(self, flip: bool) -> Self {
+        self.with_flag(Self::HFLIP, flip)
+    }
+
+    /// Checks if the tile is flipped vertically.
+    pub const fn vflip(self) -> bool {
+        (self.0 & Self::VFLIP) != 0
+    }
+
+    /// Sets if the tile is flipped vertically.
+    pub const fn with_vflip(self, flip: bool) -> Self {
+        self.with_flag(Self::VFLIP, flip)
+    }
+
+    /// The palette bank used by a 4bpp tile.
+    pub const fn palette_bank(self) -> u16 {
+        self.0 >> Self::PALETTE_SHIFT
+    }
+
+    /// Sets the palette bank (0-15) used by a 4bpp tile.
+    pub const fn with_palette_bank(self, bank: u16) -> Self {
+        let mask = 0xF << Self::PALETTE_SHIFT;
+        Self((self.0 & !mask) | ((bank << Self::PALETTE_SHIFT) & mask))
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for TileMapEntry {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TileMapEntry> for u16 {
+    fn from(value: TileMapEntry) -> Self {
+        value.0
+    }
+}
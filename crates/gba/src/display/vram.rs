@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Allocation of background VRAM for tile data and maps.
+//!
+//! The 64KB of background VRAM is addressed as either four 16KB charblocks,
+//! which hold tile graphics, or thirty-two 2KB screenblocks, which hold maps.
+//! Both views share the same memory: charblock `n` covers screenblocks
+//! `8n..8n+8`.
+//!
+//! ```rust
+//! use gba::display::{Background, BackgroundControl, Vram};
+//!
+//! let mut vram = Vram::new();
+//! let tiles = vram.alloc_charblock().unwrap();
+//! let map = vram.alloc_screenblocks(1).unwrap();
+//!
+//! Background::Bg0.set_control(
+//!     BackgroundControl::new().with_char_base(tiles.index()).with_screen_base(map.index()),
+//! );
+//! ```
+
+use super::tile::{Tile4, Tile8, TileMapEntry};
+use super::VRAM;
+
+/// The number of charblocks used for backgrounds.
+const CHARBLOCKS: usize = 4;
+/// The number of screenblocks used for backgrounds.
+const SCREENBLOCKS: usize = 32;
+/// The number of screenblocks that share memory with a single charblock.
+const SCREENBLOCKS_PER_CHARBLOCK: usize = SCREENBLOCKS / CHARBLOCKS;
+
+/// Errors that can occur when allocating or loading VRAM.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VramError {
+    /// The block is already allocated.
+    InUse,
+    /// The block shares memory with an allocated block of the other kind.
+    Overlap,
+    /// There are no free blocks that satisfy the request.
+    Exhausted,
+    /// The block index or data extends past the end of the region.
+    OutOfRange,
+}
+
+/// A 16KB block of background VRAM for tile graphics.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CharBlock {
+    index: u16,
+}
+
+impl CharBlock {
+    /// The size of a charblock, in bytes.
+    pub const SIZE: usize = 0x4000;
+
+    /// The index (0-3) of the charblock, used for
+    /// [`BackgroundControl::with_char_base()`](super::BackgroundControl::with_char_base).
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Copies 4bpp tiles into the charblock, starting at tile `offset`.
+    pub fn load_tiles4(&self, offset: usize, tiles: &[Tile4]) -> Result<(), VramError> {
+        self.load(offset * 8, tiles.len() * 8, tiles.iter().flat_map(|tile| tile.0))
+    }
+
+    /// Copies 8bpp tiles into the charblock, starting at tile `offset`.
+    pub fn load_tiles8(&self, offset: usize, tiles: &[Tile8]) -> Result<(), VramError> {
+        self.load(offset * 16, tiles.len() * 16, tiles.iter().flat_map(|tile| tile.0))
+    }
+
+    fn load<I>(&self, offset: usize, len: usize, words: I) -> Result<(), VramError>
+    where
+        I: Iterator<Item = u32>,
+    {
+        if offset + len > Self::SIZE / 4 {
+            return Err(VramError::OutOfRange);
+        }
+
+        let base = unsafe { VRAM.add(usize::from(self.index) * Self::SIZE / 2) as *mut u32 };
+        for (i, word) in words.enumerate() {
+            unsafe {
+                base.add(offset + i).write_volatile(word);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// One or more consecutive 2KB blocks of background VRAM for maps.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ScreenBlock {
+    index: u16,
+    count: u16,
+}
+
+impl ScreenBlock {
+    /// The size of a screenblock, in bytes.
+    pub const SIZE: usize = 0x800;
+    /// The number of map entries in a screenblock.
+    pub const ENTRIES: usize = Self::SIZE / 2;
+
+    /// The index (0-31) of the first screenblock, used for
+    /// [`BackgroundControl::with_screen_base()`](super::BackgroundControl::with_screen_base).
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// The number of consecutive screenblocks.
+    pub const fn count(&self) -> u16 {
+        self.count
+    }
+
+    /// Sets a single map entry.
+    ///
+    /// Entries of a 32x32 screenblock are stored in rows. Maps that use
+    /// multiple screenblocks store each 32x32 section one after the other.
+    pub fn set(&self, index: usize, entry: TileMapEntry) -> Result<(), VramError> {
+        self.load_map(index, &[entry])
+    }
+
+    /// Copies map entries into the screenblocks, starting at entry `offset`.
+    pub fn load_map(&self, offset: usize, entries: &[TileMapEntry]) -> Result<(), VramError> {
+        if offset + entries.len() > usize::from(self.count) * Self::ENTRIES {
+            return Err(VramError::OutOfRange);
+        }
+
+        let base = unsafe { VRAM.add(usize::from(self.index) * Self::ENTRIES) };
+        for (i, &entry) in entries.iter().enumerate() {
+            unsafe {
+                base.add(offset + i).write_volatile(u16::from(entry));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tracks which charblocks and screenblocks of background VRAM are in use.
+///
+/// Charblocks are allocated from the start of VRAM and screenblocks from the
+/// end, so that tile data and maps can grow towards each other.
+#[derive(Debug, Default)]
+pub struct Vram {
+    charblocks: u8,
+    screenblocks: u32,
+}
+
+impl Vram {
+    pub const fn new() -> Self {
+        Self { charblocks: 0, screenblocks: 0 }
+    }
+
+    /// Allocates the first free charblock.
+    pub fn alloc_charblock(&mut self) -> Result<CharBlock, VramError> {
+        (0..CHARBLOCKS)
+            .find_map(|index| self.alloc_charblock_at(index).ok())
+            .ok_or(VramError::Exhausted)
+    }
+
+    /// Allocates the charblock at `index`.
+    pub fn alloc_charblock_at(&mut self, index: usize) -> Result<CharBlock, VramError> {
+        if index >= CHARBLOCKS {
+            return Err(VramError::OutOfRange);
+        }
+        if (self.charblocks & (1 << index)) != 0 {
+            return Err(VramError::InUse);
+        }
+        if (self.screenblocks & Self::charblock_screenblocks(index)) != 0 {
+            return Err(VramError::Overlap);
+        }
+
+        self.charblocks |= 1 << index;
+        Ok(CharBlock { index: index as u16 })
+    }
+
+    /// Releases the charblock so it can be allocated again.
+    pub fn free_charblock(&mut self, block: CharBlock) {
+        self.charblocks &= !(1 << block.index);
+    }
+
+    /// Allocates the last `count` consecutive free screenblocks.
+    pub fn alloc_screenblocks(&mut self, count: usize) -> Result<ScreenBlock, VramError> {
+        if count == 0 || count > SCREENBLOCKS {
+            return Err(VramError::OutOfRange);
+        }
+
+        (0..=(SCREENBLOCKS - count))
+            .rev()
+            .find_map(|index| self.alloc_screenblocks_at(index, count).ok())
+            .ok_or(VramError::Exhausted)
+    }
+
+    /// Allocates `count` consecutive screenblocks, starting at `index`.
+    pub fn alloc_screenblocks_at(
+        &mut self,
+        index: usize,
+        count: usize,
+    ) -> Result<ScreenBlock, VramError> {
+        if count == 0 || index + count > SCREENBLOCKS {
+            return Err(VramError::OutOfRange);
+        }
+
+        let mask = Self::screenblock_mask(index, count);
+        if (self.screenblocks & mask) != 0 {
+            return Err(VramError::InUse);
+        }
+
+        let overlap = (0..CHARBLOCKS)
+            .filter(|&cb| (self.charblocks & (1 << cb)) != 0)
+            .any(|cb| (Self::charblock_screenblocks(cb) & mask) != 0);
+        if overlap {
+            return Err(VramError::Overlap);
+        }
+
+        self.screenblocks |= mask;
+        Ok(ScreenBlock {
+            index: index as u16,
+            count: count as u16,
+        })
+    }
+
+    /// Releases the screenblocks so they can be allocated again.
+    pub fn free_screenblocks(&mut self, block: ScreenBlock) {
+        let mask = Self::screenblock_mask(usize::from(block.index), usize::from(block.count));
+        self.screenblocks &= !mask;
+    }
+
+    /// Returns the mask of screenblocks that share memory with a charblock.
+    const fn charblock_screenblocks(index: usize) -> u32 {
+        Self::screenblock_mask(index * SCREENBLOCKS_PER_CHARBLOCK, SCREENBLOCKS_PER_CHARBLOCK)
+    }
+
+    const fn screenblock_mask(index: usize, count: usize) -> u32 {
+        let mask = if count >= SCREENBLOCKS {
+            u32::MAX
+        } else {
+            (1 << count) - 1
+        };
+
+        mask << index
+    }
+}
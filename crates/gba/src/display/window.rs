@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Windows, which restrict the layers and effects shown in parts of the
+//! screen.
+//!
+//! Windows 0 and 1 are rectangles; the object window is the shape of the
+//! visible pixels of objects using [`ObjMode::Window`](crate::obj::ObjMode).
+//! Where windows overlap, window 0 takes precedence over window 1, which
+//! takes precedence over the object window. The rest of the screen uses the
+//! settings for outside all windows.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#lcdiowindowfeature>
+//!
+//! ```rust
+//! use gba::display::{DisplayControl, Layer, Window, WindowControl, WindowLayers};
+//!
+//! // Show only BG0 (a HUD) in the top 16 lines; everything else below.
+//! Window::Win0.set_bounds(0, 0, 240, 16);
+//! WindowControl::new()
+//!     .with_layers(Window::Win0, WindowLayers::new().with_layer(Layer::Bg0, true))
+//!     .with_outside(WindowLayers::all().with_layer(Layer::Bg0, false))
+//!     .set();
+//! DisplayControl::get().enable_window(Window::Win0).set();
+//! ```
+
+use super::Layer;
+use crate::register::{ReadWrite, Register};
+
+/// The memory-mapped address of WIN0H. WIN1H follows.
+const WINH: u32 = 0x0400_0040;
+/// The memory-mapped address of WIN0V. WIN1V follows.
+const WINV: u32 = 0x0400_0044;
+
+const WININ: Register<u16, ReadWrite, 0x0400_0048> = unsafe { Register::new() };
+const WINOUT: Register<u16, ReadWrite, 0x0400_004A> = unsafe { Register::new() };
+
+/// A window that can be enabled for display.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u16)]
+pub enum Window {
+    Win0 = 1 << 13,
+    Win1 = 1 << 14,
+    /// The window formed by objects in window mode.
+    Obj = 1 << 15,
+}
+
+impl Window {
+    /// Sets the rectangle covered by window 0 or 1.
+    ///
+    /// `right` and `bottom` are exclusive. If `left > right` or `right > 240`,
+    /// the window extends to the right edge of the screen; likewise for
+    /// `top > bottom` or `bottom > 160`. The bounds registers are write-only.
+    ///
+    /// The object window has no bounds, so this does nothing for
+    /// [`Window::Obj`].
+    pub fn set_bounds(self, left: u8, top: u8, right: u8, bottom: u8) {
+        let index = match self {
+            Self::Win0 => 0,
+            Self::Win1 => 1,
+            Self::Obj => return,
+        };
+
+        let h = (WINH + 2 * index) as *mut u16;
+        let v = (WINV + 2 * index) as *mut u16;
+        unsafe {
+            h.write_volatile(u16::from(left) << 8 | u16::from(right));
+            v.write_volatile(u16::from(top) << 8 | u16::from(bottom));
+        }
+    }
+}
+
+/// The layers and effects visible in a region of a window.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct WindowLayers(u8);
+
+impl WindowLayers {
+    const LAYER_SHIFT: u16 = 8;
+    const LAYER_MASK: u8 = 0b1_1111;
+    /// Enables color special effects.
+    const BLEND: u8 = 1 << 5;
+
+    /// Returns settings with nothing visible.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns settings with all layers and effects visible.
+    pub const fn all() -> Self {
+        Self(Self::LAYER_MASK | Self::BLEND)
+    }
+
+    /// Checks if the layer is visible.
+    pub const fn is_visible(self, layer: Layer) -> bool {
+        (self.0 & Self::layer_bit(layer)) != 0
+    }
+
+    /// Sets if the layer is visible.
+    pub const fn with_layer(self, layer: Layer, visible: bool) -> Self {
+        self.with_flag(Self::layer_bit(layer), visible)
+    }
+
+    /// Checks if color special effects (blending and fades) are applied.
+    pub const fn blend(self) -> bool {
+        (self.0 & Self::BLEND) != 0
+    }
+
+    /// Sets if color special effects (blending and fades) are applied.
+    pub const fn with_blend(self, enabled: bool) -> Self {
+        self.with_flag(Self::BLEND, enabled)
+    }
+
+    const fn layer_bit(layer: Layer) -> u8 {
+        // The layer bits match the order in DISPCNT.
+        ((layer as u16) >> Self::LAYER_SHIFT) as u8
+    }
+
+    const fn with_flag(self, flag: u8, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u8> for WindowLayers {
+    fn from(value: u8) -> Self {
+        Self(value & (Self::LAYER_MASK | Self::BLEND))
+    }
+}
+
+impl From<WindowLayers> for u8 {
+    fn from(value: WindowLayers) -> Self {
+        value.0
+    }
+}
+
+/// The window settings, from WININ and WINOUT.
+///
+/// Settings are built up and then applied with [`set()`](Self::set).
+/// Windows must also be enabled in
+/// [`DisplayControl`](super::DisplayControl::enable_window).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct WindowControl {
+    win0: WindowLayers,
+    win1: WindowLayers,
+    outside: WindowLayers,
+    obj: WindowLayers,
+}
+
+impl WindowControl {
+    /// Returns settings with nothing visible in any region.
+    pub const fn new() -> Self {
+        Self {
+            win0: WindowLayers::new(),
+            win1: WindowLayers::new(),
+            outside: WindowLayers::new(),
+            obj: WindowLayers::new(),
+        }
+    }
+
+    /// Returns the current window settings.
+    pub fn get() -> Self {
+        let [win0, win1] = WININ.read().to_le_bytes();
+        let [outside, obj] = WINOUT.read().to_le_bytes();
+
+        Self {
+            win0: WindowLayers::from(win0),
+            win1: WindowLayers::from(win1),
+            outside: WindowLayers::from(outside),
+            obj: WindowLayers::from(obj),
+        }
+    }
+
+    /// Applies the window settings.
+    pub fn set(self) {
+        WININ.write(u16::from_le_bytes([self.win0.0, self.win1.0]));
+        WINOUT.write(u16::from_le_bytes([self.outside.0, self.obj.0]));
+    }
+
+    /// Returns what is visible inside the window.
+    pub const fn layers(self, window: Window) -> WindowLayers {
+        match window {
+            Window::Win0 => self.win0,
+            Window::Win1 => self.win1,
+            Window::Obj => self.obj,
+        }
+    }
+
+    /// Sets what is visible inside the window.
+    pub const fn with_layers(mut self, window: Window, layers: WindowLayers) -> Self {
+        match window {
+            Window::Win0 => self.win0 = layers,
+            Window::Win1 => self.win1 = layers,
+            Window::Obj => self.obj = layers,
+        }
+        self
+    }
+
+    /// Returns what is visible outside all enabled windows.
+    pub const fn outside(self) -> WindowLayers {
+        self.outside
+    }
+
+    /// Sets what is visible outside all enabled windows.
+    pub const fn with_outside(mut self, layers: WindowLayers) -> Self {
+        self.outside = layers;
+        self
+    }
+}
@@ -0,0 +1,480 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Direct memory access.
+//!
+//! There are 4 DMA channels, with channel 0 having the highest priority:
+//!
+//! - DMA0: internal memory to internal memory; for time-critical transfers such
+//!   as HBlank effects
+//! - DMA1, DMA2: any memory to internal memory; used to feed the sound FIFOs
+//! - DMA3: any memory to any memory; for general copies
+//!
+//! The CPU is halted while a transfer runs, so immediate transfers are
+//! complete when the function starting them returns.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbadmatransfers>
+//!
+//! ```rust
+//! use gba::dma::Dma3;
+//!
+//! let mut dma = Dma3::take().unwrap();
+//! let src = [0x1234_5678u32; 64];
+//! let mut dst = [0u32; 64];
+//! dma.copy(&src, &mut dst).unwrap();
+//! dma.fill(0, &mut dst).unwrap();
+//! ```
+
+use core::mem::{self, ManuallyDrop};
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::interrupt;
+
+/// The memory-mapped address of DMA0SAD. The registers of each channel are
+/// 12 bytes apart.
+const DMA_BASE: u32 = 0x0400_00B0;
+/// The end of internal memory; cartridge memory follows.
+const INTERNAL_END: u32 = 0x0800_0000;
+/// The end of the address space usable by DMA.
+const ADDRESS_END: u32 = 0x1000_0000;
+
+/// Set for each channel that has been taken.
+static TAKEN: [AtomicBool; 4] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+];
+
+/// DMA channel 0.
+pub type Dma0 = Channel<0>;
+/// DMA channel 1.
+pub type Dma1 = Channel<1>;
+/// DMA channel 2.
+pub type Dma2 = Channel<2>;
+/// DMA channel 3.
+pub type Dma3 = Channel<3>;
+
+/// Errors from starting a transfer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DmaError {
+    /// Part of the source or destination is outside the range usable by the
+    /// channel.
+    InvalidAddress,
+    /// The data is not aligned to the transfer size.
+    Unaligned,
+    /// The source and destination have different lengths.
+    LengthMismatch,
+    /// The transfer has more units than the channel supports.
+    TooLong,
+    /// The timing is not supported by the channel.
+    InvalidTiming,
+}
+
+/// How the destination address changes after each unit.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum DestControl {
+    #[default]
+    Increment = 0,
+    Decrement = 1,
+    Fixed = 2,
+    /// Increment, and reset to the initial address on each repeat.
+    IncrementReload = 3,
+}
+
+/// How the source address changes after each unit.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SrcControl {
+    #[default]
+    Increment = 0,
+    Decrement = 1,
+    Fixed = 2,
+}
+
+/// The size of each unit transferred.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum TransferSize {
+    #[default]
+    Half = 0,
+    Word = 1,
+}
+
+/// When a transfer starts.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Timing {
+    #[default]
+    Immediate = 0,
+    VBlank = 1,
+    HBlank = 2,
+    /// Sound FIFO requests for DMA1 and DMA2; video capture for DMA3.
+    /// Prohibited for DMA0.
+    Special = 3,
+}
+
+/// The control settings of a transfer, from DMAxCNT_H.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct DmaControl(u16);
+
+impl DmaControl {
+    const DEST_SHIFT: u16 = 5;
+    const DEST_MASK: u16 = 0b11 << Self::DEST_SHIFT;
+    const SRC_SHIFT: u16 = 7;
+    const SRC_MASK: u16 = 0b11 << Self::SRC_SHIFT;
+    const REPEAT: u16 = 1 << 9;
+    const WORD: u16 = 1 << 10;
+    const TIMING_SHIFT: u16 = 12;
+    const TIMING_MASK: u16 = 0b11 << Self::TIMING_SHIFT;
+    const IRQ: u16 = 1 << 14;
+    const ENABLE: u16 = 1 << 15;
+
+    /// Returns settings for an immediate, incrementing, 16-bit transfer.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// How the destination address changes after each unit.
+    pub const fn dest(self) -> DestControl {
+        match (self.0 & Self::DEST_MASK) >> Self::DEST_SHIFT {
+            1 => DestControl::Decrement,
+            2 => DestControl::Fixed,
+            3 => DestControl::IncrementReload,
+            _ => DestControl::Increment,
+        }
+    }
+
+    /// Sets how the destination address changes after each unit.
+    pub const fn with_dest(self, control: DestControl) -> Self {
+        let control = (control as u16) << Self::DEST_SHIFT;
+        Self((self.0 & !Self::DEST_MASK) | control)
+    }
+
+    /// How the source address changes after each unit.
+    pub const fn src(self) -> SrcControl {
+        match (self.0 & Self::SRC_MASK) >> Self::SRC_SHIFT {
+            1 => SrcControl::Decrement,
+            2 => SrcControl::Fixed,
+            _ => SrcControl::Increment,
+        }
+    }
+
+    /// Sets how the source address changes after each unit.
+    pub const fn with_src(self, control: SrcControl) -> Self {
+        let control = (control as u16) << Self::SRC_SHIFT;
+        Self((self.0 & !Self::SRC_MASK) | control)
+    }
+
+    /// Checks if the transfer repeats on every VBlank, HBlank, or special
+    /// request.
+    pub const fn repeat(self) -> bool {
+        (self.0 & Self::REPEAT) != 0
+    }
+
+    /// Sets if the transfer repeats on every VBlank, HBlank, or special
+    /// request.
+    pub const fn with_repeat(self, enabled: bool) -> Self {
+        self.with_flag(Self::REPEAT, enabled)
+    }
+
+    /// The size of each unit transferred.
+    pub const fn size(self) -> TransferSize {
+        if (self.0 & Self::WORD) != 0 {
+            TransferSize::Word
+        } else {
+            TransferSize::Half
+        }
+    }
+
+    /// Sets the size of each unit transferred.
+    pub const fn with_size(self, size: TransferSize) -> Self {
+        self.with_flag(Self::WORD, matches!(size, TransferSize::Word))
+    }
+
+    /// When the transfer starts.
+    pub const fn timing(self) -> Timing {
+        match (self.0 & Self::TIMING_MASK) >> Self::TIMING_SHIFT {
+            1 => Timing::VBlank,
+            2 => Timing::HBlank,
+            3 => Timing::Special,
+            _ => Timing::Immediate,
+        }
+    }
+
+    /// Sets when the transfer starts.
+    pub const fn with_timing(self, timing: Timing) -> Self {
+        let timing = (timing as u16) << Self::TIMING_SHIFT;
+        Self((self.0 & !Self::TIMING_MASK) | timing)
+    }
+
+    /// Checks if an interrupt is requested when the transfer completes.
+    pub const fn irq(self) -> bool {
+        (self.0 & Self::IRQ) != 0
+    }
+
+    /// Sets if an interrupt is requested when the transfer completes.
+    ///
+    /// The interrupt must also be enabled with
+    /// [`interrupt::enable()`](crate::interrupt::enable).
+    pub const fn with_irq(self, enabled: bool) -> Self {
+        self.with_flag(Self::IRQ, enabled)
+    }
+
+    /// Checks if the channel is enabled (i.e., a transfer is pending or
+    /// repeating).
+    pub const fn is_enabled(self) -> bool {
+        (self.0 & Self::ENABLE) != 0
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for DmaControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DmaControl> for u16 {
+    fn from(value: DmaControl) -> Self {
+        value.0
+    }
+}
+
+/// Exclusive access to a DMA channel.
+///
+/// Dropping the channel stops any pending or repeating transfer and allows
+/// it to be taken again.
+#[derive(Debug)]
+pub struct Channel<const N: usize> {
+    _private: (),
+}
+
+impl<const N: usize> Channel<N> {
+    /// The highest source address, plus 1.
+    const SRC_END: u32 = if N == 0 {
+        INTERNAL_END
+    } else {
+        ADDRESS_END
+    };
+    /// The highest destination address, plus 1.
+    const DEST_END: u32 = if N == 3 {
+        ADDRESS_END
+    } else {
+        INTERNAL_END
+    };
+    /// The maximum number of units in a transfer.
+    pub const MAX_UNITS: u32 = if N == 3 {
+        0x1_0000
+    } else {
+        0x4000
+    };
+
+    /// Returns the channel, if it has not already been taken.
+    pub fn take() -> Option<Self> {
+        let taken = TAKEN.get(N)?;
+
+        interrupt::free(|| {
+            if taken.load(Ordering::Relaxed) {
+                return None;
+            }
+            taken.store(true, Ordering::Relaxed);
+            Some(Self { _private: () })
+        })
+    }
+
+    /// Returns a handle to the channel without taking it.
+    ///
+    /// For interrupt handlers of the owner of the channel. The handle must not
+    /// be dropped, which would release the channel.
+    pub(crate) unsafe fn steal() -> ManuallyDrop<Self> {
+        ManuallyDrop::new(Self { _private: () })
+    }
+
+    /// Returns the control settings of the current transfer.
+    pub fn control(&self) -> DmaControl {
+        unsafe { DmaControl(self.register::<u16>(10).read_volatile()) }
+    }
+
+    /// Checks if a transfer is pending or repeating.
+    pub fn is_busy(&self) -> bool {
+        self.control().is_enabled()
+    }
+
+    /// Stops a pending or repeating transfer.
+    pub fn stop(&mut self) {
+        unsafe {
+            self.register::<u16>(10).write_volatile(0);
+        }
+    }
+
+    /// Starts a transfer of `count` units.
+    ///
+    /// Immediate transfers complete before this returns. Other transfers run
+    /// when triggered, until stopped if repeating.
+    ///
+    /// # Safety
+    ///
+    /// `src` and `dst` must be valid for the whole transfer, including any
+    /// repeats, and aligned to the transfer size.
+    pub unsafe fn start<S, D>(
+        &mut self,
+        src: *const S,
+        dst: *mut D,
+        count: u32,
+        control: DmaControl,
+    ) -> Result<(), DmaError> {
+        let (src, dst) = (src as u32, dst as u32);
+
+        if N == 0 && control.timing() == Timing::Special {
+            return Err(DmaError::InvalidTiming);
+        }
+        if count > Self::MAX_UNITS {
+            return Err(DmaError::TooLong);
+        }
+
+        let align = match control.size() {
+            TransferSize::Half => 2,
+            TransferSize::Word => 4,
+        };
+        if src % align != 0 || dst % align != 0 {
+            return Err(DmaError::Unaligned);
+        }
+
+        // Both ends of each range must be usable by the channel.
+        let units = if count == 0 {
+            Self::MAX_UNITS
+        } else {
+            count
+        };
+        let span = (units - 1) * align;
+        let src_last = match control.src() {
+            SrcControl::Increment => src.checked_add(span),
+            SrcControl::Decrement => src.checked_sub(span),
+            SrcControl::Fixed => Some(src),
+        };
+        let dst_last = match control.dest() {
+            DestControl::Increment | DestControl::IncrementReload => dst.checked_add(span),
+            DestControl::Decrement => dst.checked_sub(span),
+            DestControl::Fixed => Some(dst),
+        };
+        let in_range = |start: u32, last: Option<u32>, end: u32| match last {
+            Some(last) => start < end && last < end,
+            None => false,
+        };
+        if !in_range(src, src_last, Self::SRC_END) || !in_range(dst, dst_last, Self::DEST_END) {
+            return Err(DmaError::InvalidAddress);
+        }
+
+        // A count of 0 transfers the maximum number of units.
+        let count = if count == Self::MAX_UNITS {
+            0
+        } else {
+            count as u16
+        };
+
+        self.stop();
+        self.register::<u32>(0).write_volatile(src);
+        self.register::<u32>(4).write_volatile(dst);
+        self.register::<u16>(8).write_volatile(count);
+        self.register::<u16>(10).write_volatile(control.0 | DmaControl::ENABLE);
+
+        Ok(())
+    }
+
+    /// Copies `src` into `dst`, which must have the same length.
+    ///
+    /// Words are used if the size and alignment of `T` allow it.
+    pub fn copy<T: Copy>(&mut self, src: &[T], dst: &mut [T]) -> Result<(), DmaError> {
+        if src.len() != dst.len() {
+            return Err(DmaError::LengthMismatch);
+        }
+
+        let control = DmaControl::new();
+        unsafe { self.run(src.as_ptr(), dst.as_mut_ptr(), src.len(), control) }
+    }
+
+    /// Fills `dst` with `value`.
+    pub fn fill<T: Copy>(&mut self, value: T, dst: &mut [T]) -> Result<(), DmaError> {
+        // A fixed source reads the same unit repeatedly, so the value must be
+        // exactly one unit.
+        let size = match mem::size_of::<T>() {
+            2 => TransferSize::Half,
+            4 => TransferSize::Word,
+            _ => return Err(DmaError::Unaligned),
+        };
+
+        let control = DmaControl::new().with_src(SrcControl::Fixed).with_size(size);
+        let src = ptr::addr_of!(value);
+        let count = dst.len();
+        let dst = dst.as_mut_ptr();
+
+        let mut done = 0;
+        while done < count {
+            let units = (count - done).min(Self::MAX_UNITS as usize);
+            unsafe {
+                self.start(src, dst.add(done), units as u32, control)?;
+            }
+            done += units;
+        }
+
+        Ok(())
+    }
+
+    /// Runs immediate transfers of `len` elements, split as needed.
+    unsafe fn run<T>(
+        &mut self,
+        src: *const T,
+        dst: *mut T,
+        len: usize,
+        control: DmaControl,
+    ) -> Result<(), DmaError> {
+        let bytes = len * mem::size_of::<T>();
+        let aligned = |align: usize| {
+            (mem::size_of::<T>() % align) == 0
+                && (src as usize) % align == 0
+                && (dst as usize) % align == 0
+        };
+
+        let (size, unit) = if aligned(4) {
+            (TransferSize::Word, 4)
+        } else if aligned(2) {
+            (TransferSize::Half, 2)
+        } else {
+            return Err(DmaError::Unaligned);
+        };
+
+        let control = control.with_size(size);
+        let (src, dst) = (src.cast::<u8>(), dst.cast::<u8>());
+        let chunk = Self::MAX_UNITS as usize * unit;
+
+        let mut done = 0;
+        while done < bytes {
+            let len = (bytes - done).min(chunk);
+            self.start(src.add(done), dst.add(done), (len / unit) as u32, control)?;
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    fn register<T>(&self, offset: u32) -> *mut T {
+        (DMA_BASE + 12 * N as u32 + offset) as *mut T
+    }
+}
+
+impl<const N: usize> Drop for Channel<N> {
+    fn drop(&mut self) {
+        self.stop();
+
+        if let Some(taken) = TAKEN.get(N) {
+            taken.store(false, Ordering::Relaxed);
+        }
+    }
+}
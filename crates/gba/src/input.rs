@@ -7,140 +7,143 @@
 //!
 //! - 0 indicated a key is pressed.
 //! - 1 indicated a key is released.
+//!
+//! [`Input`] inverts this so that 1 indicates a key is pressed. It reads keys
+//! from an [`InputSource`], which is the hardware by default, or a recording
+//! from the [`replay`] module. The [`keypad`] module handles the keypad
+//! interrupt, and the [`command`] module recognizes sequences of inputs.
+//! Games with remappable controls can bind keys to actions with the
+//! [`action`] module.
 
-use core::{fmt, ops};
+pub use gba_portable::input::{command, Direction, Iter, Keys};
 
+use self::action::{Action, ActionMap};
 use crate::register::{ReadOnly, Register};
 
-/// A bit field that represents the raw state of hardware keys.
+/// The memory-mapped register of input keys state.
+const KEYINPUT: Register<u16, ReadOnly, 0x0400_0130> = unsafe { Register::new() };
+
+/// Returns the current state of all hardware keys.
 ///
-/// Bits 0-9 are used to represent each key's state.
-/// Bits 10-15 are not used.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-#[repr(transparent)]
-pub struct Keys(u16);
-
-impl Keys {
-    /// A button
-    pub const A: Self = Self(1 << 0);
-    /// B button
-    pub const B: Self = Self(1 << 1);
-    /// Select button
-    pub const SELECT: Self = Self(1 << 2);
-    /// Start button
-    pub const START: Self = Self(1 << 3);
-    /// Control-pad right
-    pub const RIGHT: Self = Self(1 << 4);
-    /// Control-pad left
-    pub const LEFT: Self = Self(1 << 5);
-    /// Control-pad up
-    pub const UP: Self = Self(1 << 6);
-    /// Control-pad down
-    pub const DOWN: Self = Self(1 << 7);
-    /// Right shoulder button
-    pub const R: Self = Self(1 << 8);
-    /// Left shoulder button
-    pub const L: Self = Self(1 << 9);
-
-    /// The memory-mapped register of input keys state.
-    const KEYINPUT: Register<Self, ReadOnly, 0x0400_0130> = unsafe { Register::new() };
-    /// The mask of used bits from the register.
-    const MASK: u16 = 0b0000_0011_1111_1111;
+/// A key is pressed if its bit is 0.
+pub fn keys() -> Keys {
+    Keys::from(KEYINPUT.read())
 }
 
-impl Keys {
-    /// Returns the current state of all hardware keys.
-    pub fn get() -> Self {
-        Self::KEYINPUT.read() & Self(Self::MASK)
-    }
-}
+pub mod action;
+pub mod keypad;
+pub mod replay;
 
-impl Default for Keys {
-    /// Returns the default value of all keys released.
-    fn default() -> Self {
-        Self(Self::MASK)
-    }
+/// A source of key state for [`Input`], read once per frame.
+pub trait InputSource {
+    /// Returns the keys that are pressed, where 1 indicates a key is pressed.
+    fn read(&mut self) -> Keys;
 }
 
-impl From<u16> for Keys {
-    fn from(value: u16) -> Self {
-        Self(value & Keys::MASK)
-    }
-}
+/// Reads keys from the hardware.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Hardware;
 
-impl From<Keys> for u16 {
-    fn from(value: Keys) -> Self {
-        value.0
+impl InputSource for Hardware {
+    fn read(&mut self) -> Keys {
+        !keys()
     }
 }
 
-impl ops::BitAnd for Keys {
-    type Output = Self;
+/// The number of hardware keys.
+const KEY_COUNT: usize = 10;
 
-    fn bitand(self, rhs: Self) -> Self::Output {
-        Self(self.0 & rhs.0)
-    }
-}
-
-impl ops::BitOr for Keys {
-    type Output = Self;
-
-    fn bitor(self, rhs: Self) -> Self::Output {
-        Self(self.0 | rhs.0)
-    }
+/// Tracks basic state and provides convenience functions for key operations.
+#[derive(Debug)]
+pub struct Input {
+    previous: Keys,
+    current: Keys,
+    repeated: Keys,
+    /// The number of frames each key has been held, up to `u16::MAX`
+    held: [u16; KEY_COUNT],
+    /// The number of frames until each held key next repeats
+    until_repeat: [u16; KEY_COUNT],
+    repeat_delay: u16,
+    repeat_interval: u16,
 }
 
-impl ops::BitXor for Keys {
-    type Output = Self;
-
-    fn bitxor(self, rhs: Self) -> Self::Output {
-        Self(self.0 ^ rhs.0)
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            previous: Keys::default(),
+            current: Keys::default(),
+            repeated: Keys::from_bits(0),
+            held: [0; KEY_COUNT],
+            until_repeat: [0; KEY_COUNT],
+            repeat_delay: Self::REPEAT_DELAY,
+            repeat_interval: Self::REPEAT_INTERVAL,
+        }
     }
 }
 
-impl ops::Not for Keys {
-    type Output = Self;
+impl Input {
+    /// The default number of frames before a held key starts repeating.
+    pub const REPEAT_DELAY: u16 = 20;
+    /// The default number of frames between repeats of a held key.
+    pub const REPEAT_INTERVAL: u16 = 4;
 
-    fn not(self) -> Self::Output {
-        Self(!self.0 & Keys::MASK)
+    pub fn new() -> Self {
+        Self::default()
     }
-}
 
-impl fmt::Binary for Keys {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Binary::fmt(&self.0, f)
+    /// Sets the number of frames a key must be held before it repeats, and
+    /// the number of frames between repeats after that.
+    pub fn set_repeat(&mut self, delay: u16, interval: u16) {
+        self.repeat_delay = delay;
+        self.repeat_interval = interval.max(1);
     }
-}
 
-impl fmt::LowerHex for Keys {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::LowerHex::fmt(&self.0, f)
+    /// Updates the state of the keys from the hardware.
+    /// Should be called once per game loop.
+    pub fn update(&mut self) {
+        self.update_from(&mut Hardware);
     }
-}
 
-impl fmt::UpperHex for Keys {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::UpperHex::fmt(&self.0, f)
-    }
-}
+    /// Updates the state of the keys from `source`.
+    /// Should be called once per game loop, in place of [`Input::update()`].
+    pub fn update_from(&mut self, source: &mut impl InputSource) {
+        self.previous = self.current;
+        self.current = source.read();
 
-/// Tracks basic state and provides convenience functions for key operations.
-#[derive(Debug, Default)]
-pub struct Input {
-    previous: Keys,
-    current: Keys,
-}
+        let mut repeated = 0;
+        for (i, (held, until_repeat)) in
+            self.held.iter_mut().zip(self.until_repeat.iter_mut()).enumerate()
+        {
+            if (self.current.bits() & (1 << i)) == 0 {
+                *held = 0;
+                continue;
+            }
 
-impl Input {
-    pub fn new() -> Self {
-        Self::default()
-    }
+            // Repeats count down separately from `held`, which stops at its
+            // maximum, so that a key held indefinitely keeps repeating.
+            let repeat = if *held == 0 {
+                *until_repeat = if self.repeat_delay == 0 {
+                    self.repeat_interval
+                } else {
+                    self.repeat_delay
+                };
+                true
+            } else {
+                *until_repeat -= 1;
+                if *until_repeat == 0 {
+                    *until_repeat = self.repeat_interval;
+                    true
+                } else {
+                    false
+                }
+            };
+            *held = held.saturating_add(1);
 
-    /// Updates the state of the keys.
-    /// Should be called once per game loop.
-    pub fn update(&mut self) {
-        self.previous = self.current;
-        self.current = !Keys::get();
+            if repeat {
+                repeated |= 1 << i;
+            }
+        }
+        self.repeated = Keys::from_bits(repeated);
     }
 
     /// Returns the keys that are currently pressed.
@@ -178,4 +181,123 @@ impl Input {
     pub fn just_released(&self, keys: Keys) -> bool {
         (self.keys_just_released() & keys) == keys
     }
+
+    /// Returns the keys that were just pressed, or have been held long
+    /// enough to repeat on this frame.
+    ///
+    /// Bits set to 1 indicate that a key was pressed or repeated. Use this
+    /// for menu navigation.
+    pub fn keys_repeated(&self) -> Keys {
+        self.repeated
+    }
+
+    /// Checks if `keys` were just pressed or repeated.
+    pub fn repeated(&self, keys: Keys) -> bool {
+        (self.keys_repeated() & keys) == keys
+    }
+
+    /// Returns the number of frames `keys` have all been held, or 0 if any
+    /// are released.
+    ///
+    /// The count stops at `u16::MAX`, about 18 minutes.
+    pub fn held_frames(&self, keys: Keys) -> u16 {
+        self.held
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (keys.bits() & (1 << i)) != 0)
+            .map(|(_, &held)| held)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Checks if `keys` have all been held for at least `frames` frames.
+    pub fn held_for(&self, keys: Keys, frames: u16) -> bool {
+        self.pressed(keys) && self.held_frames(keys) >= frames
+    }
+
+    /// The control-pad as an x/y vector, with each axis -1, 0, or 1.
+    ///
+    /// Opposite directions pressed together cancel out.
+    pub fn dpad(&self) -> (i32, i32) {
+        self.current.dpad()
+    }
+
+    /// The control-pad vector of only the keys that were just pressed.
+    pub fn dpad_just_pressed(&self) -> (i32, i32) {
+        self.keys_just_pressed().dpad()
+    }
+
+    /// The control-pad vector of only the keys that were just pressed or
+    /// repeated.
+    pub fn dpad_repeated(&self) -> (i32, i32) {
+        self.keys_repeated().dpad()
+    }
+
+    /// The horizontal axis of the control-pad: -1 for left, 1 for right.
+    pub fn horizontal(&self) -> i32 {
+        self.current.horizontal()
+    }
+
+    /// The vertical axis of the control-pad: -1 for up, 1 for down.
+    pub fn vertical(&self) -> i32 {
+        self.current.vertical()
+    }
+
+    /// The direction of the control-pad, if any.
+    pub fn direction(&self) -> Option<Direction> {
+        self.current.direction()
+    }
+
+    /// Checks if any chord bound to `action` is pressed.
+    pub fn action_pressed<A: Action, const N: usize>(
+        &self,
+        map: &ActionMap<A, N>,
+        action: A,
+    ) -> bool {
+        map.bindings(action).any(|keys| self.current.contains(keys))
+    }
+
+    /// Checks if `action` is pressed and was not on the previous frame.
+    pub fn action_just_pressed<A: Action, const N: usize>(
+        &self,
+        map: &ActionMap<A, N>,
+        action: A,
+    ) -> bool {
+        let previous = map.bindings(action).any(|keys| self.previous.contains(keys));
+        !previous && self.action_pressed(map, action)
+    }
+
+    /// Checks if `action` was pressed on the previous frame and is not now.
+    pub fn action_just_released<A: Action, const N: usize>(
+        &self,
+        map: &ActionMap<A, N>,
+        action: A,
+    ) -> bool {
+        let previous = map.bindings(action).any(|keys| self.previous.contains(keys));
+        previous && !self.action_pressed(map, action)
+    }
+
+    /// Checks if `action` is pressed and any key of its chord was just
+    /// pressed or repeated.
+    pub fn action_repeated<A: Action, const N: usize>(
+        &self,
+        map: &ActionMap<A, N>,
+        action: A,
+    ) -> bool {
+        map.bindings(action)
+            .any(|keys| self.current.contains(keys) && !(self.repeated & keys).is_empty())
+    }
+
+    /// The direction of the control-pad if any of its keys were just pressed.
+    ///
+    /// Unlike [`dpad_just_pressed()`](Self::dpad_just_pressed), this includes
+    /// keys that were already held, so pressing up while holding right
+    /// returns [`Direction::UpRight`].
+    pub fn direction_just_pressed(&self) -> Option<Direction> {
+        if (self.keys_just_pressed() & Keys::DPAD).is_empty() {
+            None
+        } else {
+            self.direction()
+        }
+    }
 }
@@ -0,0 +1,266 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Remappable controls.
+//!
+//! Games define their actions as a type implementing [`Action`], and an
+//! [`ActionMap`] binds each action to up to [`MAX_BINDINGS`] key chords. An
+//! action is pressed when all keys of any of its chords are pressed. The
+//! bindings can be kept in SRAM with [`ActionMap::save_sram()`], or converted
+//! to bytes with [`ActionMap::to_bytes()`] for other save memory.
+//!
+//! ```rust
+//! use gba::input::action::{Action, ActionMap};
+//! use gba::input::{Input, Keys};
+//!
+//! #[derive(Clone, Copy)]
+//! enum Control {
+//!     Jump,
+//!     Attack,
+//!     Pause,
+//! }
+//!
+//! impl Action for Control {
+//!     fn index(self) -> usize {
+//!         self as usize
+//!     }
+//! }
+//!
+//! const CONTROLS: usize = 0x100;
+//!
+//! gba::save::sram::init();
+//! let mut controls = ActionMap::<Control, 3>::load_sram(CONTROLS).unwrap_or_else(|| {
+//!     ActionMap::new()
+//!         .with_binding(Control::Jump, Keys::A)
+//!         .with_binding(Control::Attack, Keys::B)
+//!         .with_binding(Control::Attack, Keys::R)
+//!         .with_binding(Control::Pause, Keys::START)
+//! });
+//!
+//! // Rebinding from an options menu:
+//! controls.unbind(Control::Jump);
+//! controls.bind(Control::Jump, Keys::L | Keys::A).unwrap();
+//! controls.save_sram(CONTROLS).unwrap();
+//!
+//! let mut input = Input::new();
+//! input.update();
+//! if input.action_just_pressed(&controls, Control::Jump) {
+//!     // ...
+//! }
+//! ```
+
+use core::marker::PhantomData;
+
+use super::Keys;
+use crate::save::{sram, SaveError};
+
+/// The number of chords that can be bound to each action.
+pub const MAX_BINDINGS: usize = 2;
+
+/// Marks the start of serialized bindings.
+const MAGIC: [u8; 4] = *b"BIND";
+/// The size of the magic, counts, and checksum before the bindings.
+const HEADER_SIZE: usize = 8;
+
+/// A named action that keys can be bound to.
+pub trait Action: Copy {
+    /// The index of the action, which must be less than the number of
+    /// actions in the [`ActionMap`].
+    fn index(self) -> usize;
+}
+
+/// Errors from binding keys to actions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BindError {
+    /// The action already has [`MAX_BINDINGS`] bindings.
+    Full,
+    /// No keys were given.
+    Empty,
+    /// The action is not in the map.
+    InvalidAction,
+}
+
+/// The key chords bound to each of `N` actions.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ActionMap<A: Action, const N: usize> {
+    /// The chords of each action, where no keys is unbound.
+    bindings: [[Keys; MAX_BINDINGS]; N],
+    action: PhantomData<A>,
+}
+
+impl<A: Action, const N: usize> Default for ActionMap<A, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Action, const N: usize> ActionMap<A, N> {
+    /// The size of the bindings converted to bytes.
+    pub const SERIALIZED_SIZE: usize = HEADER_SIZE + (N * MAX_BINDINGS * 2);
+
+    /// Returns a map with no keys bound.
+    pub const fn new() -> Self {
+        Self {
+            bindings: [[Keys::from_bits(0); MAX_BINDINGS]; N],
+            action: PhantomData,
+        }
+    }
+
+    /// Adds a binding, ignoring errors. Use for default bindings.
+    pub fn with_binding(mut self, action: A, keys: Keys) -> Self {
+        let _ = self.bind(action, keys);
+        self
+    }
+
+    /// Binds `keys` as a chord to `action`, in addition to its existing
+    /// bindings.
+    pub fn bind(&mut self, action: A, keys: Keys) -> Result<(), BindError> {
+        if keys.is_empty() {
+            return Err(BindError::Empty);
+        }
+
+        let bindings = self.bindings.get_mut(action.index()).ok_or(BindError::InvalidAction)?;
+        let slot = bindings.iter_mut().find(|b| b.is_empty()).ok_or(BindError::Full)?;
+        *slot = keys;
+        Ok(())
+    }
+
+    /// Removes all bindings of `action`.
+    pub fn unbind(&mut self, action: A) {
+        if let Some(bindings) = self.bindings.get_mut(action.index()) {
+            *bindings = [Keys::from_bits(0); MAX_BINDINGS];
+        }
+    }
+
+    /// Removes all bindings of all actions.
+    pub fn clear(&mut self) {
+        self.bindings = [[Keys::from_bits(0); MAX_BINDINGS]; N];
+    }
+
+    /// Returns the chords bound to `action`.
+    pub fn bindings(&self, action: A) -> impl Iterator<Item = Keys> + '_ {
+        self.bindings
+            .get(action.index())
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|keys| !keys.is_empty())
+    }
+
+    /// Checks if `keys` are bound as a chord to any action.
+    ///
+    /// Use to warn about conflicts when rebinding.
+    pub fn is_bound(&self, keys: Keys) -> bool {
+        self.bindings.iter().flatten().any(|&b| b == keys)
+    }
+
+    /// Converts the bindings to bytes in `out`, returning the size written.
+    ///
+    /// The bytes start with a magic value and a checksum of the bindings, so
+    /// that [`from_bytes()`](Self::from_bytes) rejects other data.
+    ///
+    /// Returns `None` if `out` is smaller than
+    /// [`SERIALIZED_SIZE`](Self::SERIALIZED_SIZE).
+    pub fn to_bytes(&self, out: &mut [u8]) -> Option<usize> {
+        let out = out.get_mut(..Self::SERIALIZED_SIZE)?;
+        let (header, data) = out.split_at_mut(HEADER_SIZE);
+        header.copy_from_slice(&self.header()?);
+        for (bytes, keys) in data.chunks_exact_mut(2).zip(self.key_bytes()) {
+            bytes.copy_from_slice(&keys);
+        }
+
+        Some(Self::SERIALIZED_SIZE)
+    }
+
+    /// Loads bindings converted to bytes by [`to_bytes()`](Self::to_bytes).
+    ///
+    /// Returns `None` if the bytes are not bindings for the same number of
+    /// actions or do not match their checksum, such as uninitialized or
+    /// corrupted save memory.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let data = data.get(..Self::SERIALIZED_SIZE)?;
+        let (header, data) = data.split_at(HEADER_SIZE);
+
+        let mut map = Self::new();
+        let keys = map.bindings.iter_mut().flatten();
+        for (bytes, keys) in data.chunks_exact(2).zip(keys) {
+            *keys = Keys::from(u16::from_le_bytes([bytes[0], bytes[1]]));
+        }
+
+        // Checked after loading, as the checksum covers the bindings.
+        map.check(header).then_some(map)
+    }
+
+    /// Saves the bindings to SRAM at `offset`, taking
+    /// [`SERIALIZED_SIZE`](Self::SERIALIZED_SIZE) bytes.
+    ///
+    /// SRAM must be initialized with [`sram::init()`]. Nothing is written if
+    /// the bindings do not fit.
+    pub fn save_sram(&self, offset: usize) -> Result<(), SaveError> {
+        let end = offset.checked_add(Self::SERIALIZED_SIZE).ok_or(SaveError::OutOfRange)?;
+        if end > sram::SIZE {
+            return Err(SaveError::OutOfRange);
+        }
+
+        let header = self.header().ok_or(SaveError::OutOfRange)?;
+        sram::write(offset, &header)?;
+        for (i, keys) in self.key_bytes().enumerate() {
+            sram::write(offset + HEADER_SIZE + (i * 2), &keys)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads bindings saved to SRAM by [`save_sram()`](Self::save_sram).
+    ///
+    /// SRAM must be initialized with [`sram::init()`]. Returns `None` if no
+    /// valid bindings for the same number of actions are saved at `offset`,
+    /// so the game can fall back to its defaults.
+    pub fn load_sram(offset: usize) -> Option<Self> {
+        let mut header = [0; HEADER_SIZE];
+        sram::read(offset, &mut header).ok()?;
+
+        let mut map = Self::new();
+        for (i, keys) in map.bindings.iter_mut().flatten().enumerate() {
+            let mut bytes = [0; 2];
+            sram::read(offset + HEADER_SIZE + (i * 2), &mut bytes).ok()?;
+            *keys = Keys::from(u16::from_le_bytes(bytes));
+        }
+
+        map.check(&header).then_some(map)
+    }
+
+    /// The bindings in order, as little-endian bytes.
+    fn key_bytes(&self) -> impl Iterator<Item = [u8; 2]> + '_ {
+        self.bindings.iter().flatten().map(|&keys| u16::from(keys).to_le_bytes())
+    }
+
+    /// The header for the bindings, or `None` if there are more than 255
+    /// actions.
+    fn header(&self) -> Option<[u8; HEADER_SIZE]> {
+        let checksum = checksum(self.key_bytes().flatten()).to_le_bytes();
+
+        let mut header = [0; HEADER_SIZE];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4] = u8::try_from(N).ok()?;
+        header[5] = MAX_BINDINGS as u8;
+        header[6..].copy_from_slice(&checksum);
+        Some(header)
+    }
+
+    /// Checks that `header` was saved with the loaded bindings.
+    ///
+    /// Unknown key bits are dropped when loading, so they fail the checksum.
+    fn check(&self, header: &[u8]) -> bool {
+        self.header().is_some_and(|h| h[..] == *header)
+    }
+}
+
+/// The Fletcher-16 checksum of `bytes`.
+fn checksum(bytes: impl Iterator<Item = u8>) -> u16 {
+    let (a, b) = bytes.fold((0u16, 0u16), |(a, b), byte| {
+        let a = (a + u16::from(byte)) % 255;
+        (a, (b + a) % 255)
+    });
+    (b << 8) | a
+}
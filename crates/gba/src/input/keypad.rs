@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Keypad interrupt.
+//!
+//! The keypad can request an interrupt when any or all of a set of keys are
+//! pressed. It is one of the few interrupts that wakes the GBA from the low
+//! power state of [`bios::stop()`], which makes it suitable for sleep mode and
+//! for detecting a soft reset combo without polling.
+//!
+//! The interrupt is requested for as long as the condition holds, not just
+//! when it first becomes true.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbakeypadinput>
+//!
+//! ```rust
+//! use gba::input::{keypad, Input, Keys};
+//!
+//! keypad::enable_reset(Keys::A | Keys::B | Keys::SELECT | Keys::START);
+//!
+//! let mut input = Input::new();
+//! input.update();
+//! if input.just_pressed(Keys::L | Keys::R | Keys::SELECT) {
+//!     keypad::sleep(Keys::L | Keys::R | Keys::SELECT);
+//! }
+//! ```
+
+use super::Keys;
+use crate::display::DisplayControl;
+use crate::interrupt::{self, Irq};
+use crate::register::{ReadWrite, Register};
+use crate::{bios, sound};
+
+const KEYCNT: Register<KeyControl, ReadWrite, 0x0400_0132> = unsafe { Register::new() };
+
+/// The keys that reset the GBA while held, checked by the keypad interrupt.
+static mut RESET_KEYS: Option<Keys> = None;
+
+/// The condition for requesting the keypad interrupt.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum KeyCondition {
+    /// Any of the selected keys is pressed.
+    #[default]
+    Any = 0,
+    /// All of the selected keys are pressed.
+    All = 1,
+}
+
+/// The keypad interrupt settings, from KEYCNT.
+///
+/// Settings are built up and then applied with [`set()`](Self::set).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct KeyControl(u16);
+
+impl KeyControl {
+    const IRQ: u16 = 1 << 14;
+    const CONDITION: u16 = 1 << 15;
+
+    /// Returns settings with no keys selected and the interrupt disabled.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current keypad interrupt settings.
+    pub fn get() -> Self {
+        KEYCNT.read()
+    }
+
+    /// Applies the keypad interrupt settings.
+    pub fn set(self) {
+        KEYCNT.write(self);
+    }
+
+    /// The keys that are checked for the interrupt.
+    pub const fn keys(self) -> Keys {
+        Keys::from_bits(self.0)
+    }
+
+    /// Sets the keys that are checked for the interrupt.
+    pub const fn with_keys(self, keys: Keys) -> Self {
+        Self((self.0 & !Keys::ALL.bits()) | keys.bits())
+    }
+
+    /// Checks if the interrupt is requested.
+    pub const fn irq(self) -> bool {
+        (self.0 & Self::IRQ) != 0
+    }
+
+    /// Sets if the interrupt is requested.
+    pub const fn with_irq(self, enabled: bool) -> Self {
+        self.with_flag(Self::IRQ, enabled)
+    }
+
+    /// The condition for requesting the interrupt.
+    pub const fn condition(self) -> KeyCondition {
+        if (self.0 & Self::CONDITION) != 0 {
+            KeyCondition::All
+        } else {
+            KeyCondition::Any
+        }
+    }
+
+    /// Sets the condition for requesting the interrupt.
+    pub const fn with_condition(self, condition: KeyCondition) -> Self {
+        self.with_flag(Self::CONDITION, matches!(condition, KeyCondition::All))
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for KeyControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<KeyControl> for u16 {
+    fn from(value: KeyControl) -> Self {
+        value.0
+    }
+}
+
+/// Requests the keypad interrupt when `keys` meet `condition`.
+///
+/// The interrupt is also enabled. A handler can be set with
+/// [`interrupt::set_handler()`].
+pub fn arm(keys: Keys, condition: KeyCondition) {
+    KeyControl::new().with_keys(keys).with_condition(condition).with_irq(true).set();
+    interrupt::enable(Irq::Keypad);
+}
+
+/// Stops requesting the keypad interrupt.
+pub fn disarm() {
+    KeyControl::new().set();
+    interrupt::disable(Irq::Keypad);
+}
+
+/// Resets the GBA when all of `keys` are held.
+///
+/// This replaces the keypad interrupt handler of the master ISR.
+pub fn enable_reset(keys: Keys) {
+    interrupt::free(|| unsafe {
+        RESET_KEYS = Some(keys);
+    });
+
+    interrupt::set_handler(Irq::Keypad, Some(check_reset));
+    arm(keys, KeyCondition::All);
+}
+
+/// Stops checking for the reset combo.
+pub fn disable_reset() {
+    interrupt::free(|| unsafe {
+        RESET_KEYS = None;
+    });
+
+    interrupt::set_handler(Irq::Keypad, None);
+    disarm();
+}
+
+fn check_reset() {
+    let keys = unsafe { RESET_KEYS };
+    if let Some(keys) = keys {
+        if (!super::keys() & keys) == keys {
+            unsafe {
+                bios::reset();
+            }
+        }
+    }
+}
+
+/// Puts the GBA to sleep until all of `wake` are pressed.
+///
+/// The display and sound are turned off while asleep; turning off sound
+/// resets the PSG channel settings. Waits for the keys to be released before
+/// sleeping and after waking, so the same combo can be used to enter and leave
+/// sleep mode. The previous keypad interrupt settings are restored after
+/// waking.
+pub fn sleep(wake: Keys) {
+    wait_release(wake);
+
+    let keycnt = KeyControl::get();
+    let display = DisplayControl::get();
+    let sound = sound::is_enabled();
+
+    display.with_forced_blank(true).set();
+    if sound {
+        sound::disable();
+    }
+
+    KeyControl::new().with_keys(wake).with_condition(KeyCondition::All).with_irq(true).set();
+    interrupt::enable(Irq::Keypad);
+
+    bios::stop();
+
+    // The interrupt is requested until the keys are released.
+    keycnt.set();
+    if !keycnt.irq() {
+        interrupt::disable(Irq::Keypad);
+    }
+
+    if sound {
+        sound::enable();
+    }
+    display.set();
+
+    wait_release(wake);
+}
+
+/// Busy waits until none of `keys` are pressed.
+fn wait_release(keys: Keys) {
+    while (!super::keys() & keys) != Keys::from_bits(0) {}
+}
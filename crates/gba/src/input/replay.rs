@@ -0,0 +1,278 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Input recording and replay.
+//!
+//! A [`Recorder`] stores the keys read from another source on each frame.
+//! The frames can be replayed directly with [`Replay`], or compressed with
+//! [`encode_rle()`] and replayed with [`RleReplay`]. Compressed recordings
+//! can be saved to SRAM with [`save_sram()`], or dumped to the mGBA log with
+//! the `mgba` feature.
+//!
+//! Replays are deterministic only if the game is otherwise deterministic,
+//! such as by seeding random numbers from a value saved with the recording.
+//!
+//! ```rust
+//! use gba::input::replay::{self, Recorder, RleReplay};
+//! use gba::input::{Hardware, Input};
+//!
+//! static mut FRAMES: [u16; 3600] = [0; 3600];
+//!
+//! let mut input = Input::new();
+//! let mut recorder = Recorder::new(Hardware, unsafe { &mut FRAMES });
+//! for _ in 0..600 {
+//!     gba::bios::vblank();
+//!     input.update_from(&mut recorder);
+//! }
+//!
+//! let mut data = [0; 1024];
+//! let len = replay::encode_rle(recorder.frames(), &mut data).unwrap();
+//! replay::save_sram(0, &data[..len]).unwrap();
+//!
+//! // Later, as an attract-mode demo:
+//! let mut buffer = [0; 1024];
+//! let data = replay::load_sram(0, &mut buffer).unwrap();
+//! let mut demo = RleReplay::new(data);
+//! let mut input = Input::new();
+//! while !demo.is_finished() {
+//!     gba::bios::vblank();
+//!     input.update_from(&mut demo);
+//! }
+//! ```
+
+use super::{InputSource, Keys};
+use crate::save::sram::{self, Sram};
+
+/// Marks the start of a recording saved to SRAM.
+const MAGIC: [u8; 4] = *b"KEYS";
+/// The size of the magic and length before the recording data.
+const HEADER_SIZE: usize = 8;
+/// The size of each run of an RLE recording.
+const RUN_SIZE: usize = 4;
+
+/// Errors from saving and loading recordings.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RecordingError {
+    /// The recording does not fit in the buffer or SRAM.
+    TooLarge,
+    /// No recording was found in SRAM.
+    NotFound,
+}
+
+/// Records the keys read from another source on each frame.
+///
+/// Recording stops when the buffer is full; keys are still read from the
+/// source.
+#[derive(Debug)]
+pub struct Recorder<'a, S: InputSource> {
+    source: S,
+    frames: &'a mut [u16],
+    len: usize,
+}
+
+impl<'a, S: InputSource> Recorder<'a, S> {
+    /// Records from `source` into `frames`, one entry per frame.
+    pub fn new(source: S, frames: &'a mut [u16]) -> Self {
+        Self { source, frames, len: 0 }
+    }
+
+    /// Returns the recorded frames.
+    pub fn frames(&self) -> &[u16] {
+        &self.frames[..self.len]
+    }
+
+    /// Checks if the buffer is full.
+    pub fn is_full(&self) -> bool {
+        self.len >= self.frames.len()
+    }
+
+    /// Discards the recorded frames.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Releases the source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: InputSource> InputSource for Recorder<'_, S> {
+    fn read(&mut self) -> Keys {
+        let keys = self.source.read();
+        if let Some(frame) = self.frames.get_mut(self.len) {
+            *frame = u16::from(keys);
+            self.len += 1;
+        }
+        keys
+    }
+}
+
+/// Replays recorded frames.
+///
+/// After the last frame, no keys are pressed.
+#[derive(Debug, Clone)]
+pub struct Replay<'a> {
+    frames: &'a [u16],
+    position: usize,
+}
+
+impl<'a> Replay<'a> {
+    pub const fn new(frames: &'a [u16]) -> Self {
+        Self { frames, position: 0 }
+    }
+
+    /// Checks if all frames have been replayed.
+    pub const fn is_finished(&self) -> bool {
+        self.position >= self.frames.len()
+    }
+
+    /// Restarts from the first frame.
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+}
+
+impl InputSource for Replay<'_> {
+    fn read(&mut self) -> Keys {
+        let keys = self.frames.get(self.position).copied().unwrap_or(0);
+        self.position = (self.position + 1).min(self.frames.len());
+        Keys::from(keys)
+    }
+}
+
+/// Compresses recorded frames into runs of identical keys.
+///
+/// Each run is 4 bytes: the keys and the number of frames, both
+/// little-endian. Returns the size of the compressed data, or `None` if it
+/// does not fit in `out`.
+pub fn encode_rle(frames: &[u16], out: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    let mut frames = frames.iter().copied().peekable();
+
+    while let Some(keys) = frames.next() {
+        let mut count: u16 = 1;
+        while count < u16::MAX && frames.next_if_eq(&keys).is_some() {
+            count += 1;
+        }
+
+        let run = out.get_mut(len..(len + RUN_SIZE))?;
+        run[..2].copy_from_slice(&keys.to_le_bytes());
+        run[2..].copy_from_slice(&count.to_le_bytes());
+        len += RUN_SIZE;
+    }
+
+    Some(len)
+}
+
+/// Replays frames compressed by [`encode_rle()`].
+///
+/// After the last frame, no keys are pressed.
+#[derive(Debug, Clone)]
+pub struct RleReplay<'a> {
+    data: &'a [u8],
+    /// The offset of the next run
+    offset: usize,
+    keys: u16,
+    /// Frames left in the current run
+    remaining: u16,
+}
+
+impl<'a> RleReplay<'a> {
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            keys: 0,
+            remaining: 0,
+        }
+    }
+
+    /// Checks if all frames have been replayed.
+    pub const fn is_finished(&self) -> bool {
+        self.remaining == 0 && self.offset + RUN_SIZE > self.data.len()
+    }
+
+    /// Restarts from the first frame.
+    pub fn rewind(&mut self) {
+        self.offset = 0;
+        self.remaining = 0;
+    }
+}
+
+impl InputSource for RleReplay<'_> {
+    fn read(&mut self) -> Keys {
+        while self.remaining == 0 {
+            let Some(run) = self.data.get(self.offset..(self.offset + RUN_SIZE)) else {
+                return Keys::from(0);
+            };
+            self.keys = u16::from_le_bytes([run[0], run[1]]);
+            self.remaining = u16::from_le_bytes([run[2], run[3]]);
+            self.offset += RUN_SIZE;
+        }
+
+        self.remaining -= 1;
+        Keys::from(self.keys)
+    }
+}
+
+/// Saves a recording to SRAM at `offset`, with a header to find it again.
+///
+/// Nothing is written if the header and recording do not fit in SRAM.
+pub fn save_sram(offset: usize, data: &[u8]) -> Result<(), RecordingError> {
+    let len = u32::try_from(data.len()).map_err(|_| RecordingError::TooLarge)?;
+
+    let mut header = [0; HEADER_SIZE];
+    header[..4].copy_from_slice(&MAGIC);
+    header[4..].copy_from_slice(&len.to_le_bytes());
+
+    // Checked first so that a recording that does not fit leaves SRAM as is.
+    let end = offset.checked_add(HEADER_SIZE + data.len());
+    if end.map_or(true, |end| end > sram::SIZE) {
+        return Err(RecordingError::TooLarge);
+    }
+
+    let mut save = Sram::new();
+    save.seek(offset);
+    save.write_all(&header).map_err(|_| RecordingError::TooLarge)?;
+    save.write_all(data).map_err(|_| RecordingError::TooLarge)
+}
+
+/// Loads a recording saved to SRAM at `offset` into `buffer`, returning the
+/// recording data.
+pub fn load_sram(offset: usize, buffer: &mut [u8]) -> Result<&[u8], RecordingError> {
+    let mut save = Sram::new();
+    save.seek(offset);
+
+    let mut header = [0; HEADER_SIZE];
+    save.read_exact(&mut header).map_err(|_| RecordingError::NotFound)?;
+    if header[..4] != MAGIC {
+        return Err(RecordingError::NotFound);
+    }
+
+    let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let data = buffer.get_mut(..len).ok_or(RecordingError::TooLarge)?;
+    save.read_exact(data).map_err(|_| RecordingError::NotFound)?;
+
+    Ok(data)
+}
+
+/// Writes a recording to the mGBA log as lines of hex, to be saved from the
+/// log on the host.
+#[cfg(feature = "mgba")]
+pub fn dump_mgba(data: &[u8]) {
+    use core::fmt;
+
+    struct Hex<'a>(&'a [u8]);
+
+    impl fmt::Display for Hex<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+        }
+    }
+
+    mgba::info!("input recording: {} bytes", data.len());
+    for line in data.chunks(64) {
+        mgba::info!("{}", Hex(line));
+    }
+}
@@ -1,6 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 // SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
 
+//! Interrupt handling.
+//!
+//! The default master ISR, [`master_isr`], acknowledges all enabled
+//! interrupts that occurred and then calls the handler registered for each
+//! with [`set_handler()`]. Handlers run in system mode with interrupts
+//! disabled, and should return quickly.
+//!
+//! Several modules need work done at the start of VBlank, such as copying
+//! the shadow OAM. Rather than replacing the VBlank handler, they register
+//! hooks with [`add_vblank_hook()`], which are called before the handler.
+
+use core::ptr;
+
 use crate::register::{ReadWrite, Register};
 
 const IRQ_HANDLER: Register<IrqHandler, ReadWrite, 0x0300_7FFC> = unsafe { Register::new() };
@@ -9,6 +22,12 @@ const IE: Register<u16, ReadWrite, 0x0400_0200> = unsafe { Register::new() };
 const IF: Register<u16, ReadWrite, 0x0400_0202> = unsafe { Register::new() };
 const IME: Register<u16, ReadWrite, 0x0400_0208> = unsafe { Register::new() };
 
+/// The number of interrupt sources.
+const IRQ_COUNT: usize = 14;
+
+/// The number of functions that can be registered with [`add_vblank_hook()`].
+pub const MAX_VBLANK_HOOKS: usize = 8;
+
 pub type IrqHandler = unsafe extern "C" fn();
 
 extern "C" {
@@ -16,22 +35,43 @@ extern "C" {
     pub fn master_isr();
 }
 
-#[rustfmt::skip]
+/// Handlers called by the default master ISR, indexed by IRQ.
+static mut HANDLERS: [Option<fn()>; IRQ_COUNT] = [None; IRQ_COUNT];
+/// Functions called at the start of VBlank, in the order they were added.
+static mut VBLANK_HOOKS: [Option<fn()>; MAX_VBLANK_HOOKS] = [None; MAX_VBLANK_HOOKS];
+
+/// Errors from registering VBlank hooks.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HookError {
+    /// [`MAX_VBLANK_HOOKS`] hooks are already registered.
+    Full,
+}
+
+/// Interrupt sources.
+///
+/// Each value is the bit used for the source in the IE and IF registers.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Irq {
-    VBlank,
-    //HBlank,
-    //VCount,
-    //Timer0,
-    //Timer1,
-    //Timer2,
-    //Timer3,
-    //Serial,
-    //DMA1,
-    //DMA2,
-    //DMA3,
-    //DMA4,
-    //Keypad,
-    //GamePak,
+    VBlank = 0,
+    HBlank = 1,
+    VCount = 2,
+    Timer0 = 3,
+    Timer1 = 4,
+    Timer2 = 5,
+    Timer3 = 6,
+    Serial = 7,
+    Dma0 = 8,
+    Dma1 = 9,
+    Dma2 = 10,
+    Dma3 = 11,
+    Keypad = 12,
+    GamePak = 13,
+}
+
+impl Irq {
+    const fn mask(self) -> u16 {
+        1 << self as u16
+    }
 }
 
 /// Sets the master ISR and enables interrupt handling.
@@ -50,16 +90,125 @@ pub fn reset() {
 }
 
 /// Enables handling of the specified IRQ type.
+///
+/// The display interrupts are also enabled in DISPSTAT. For other sources,
+/// the interrupt must also be requested by the source itself (e.g., the timer,
+/// DMA, or keypad control registers).
 pub fn enable(irq: Irq) {
+    free(|| {
+        match irq {
+            Irq::VBlank => DISPSTAT.write(DISPSTAT.read() | (1 << 3)),
+            Irq::HBlank => DISPSTAT.write(DISPSTAT.read() | (1 << 4)),
+            Irq::VCount => DISPSTAT.write(DISPSTAT.read() | (1 << 5)),
+            _ => (),
+        }
+
+        IE.write(IE.read() | irq.mask());
+    });
+}
+
+/// Disables handling of the specified IRQ type.
+pub fn disable(irq: Irq) {
+    free(|| {
+        match irq {
+            Irq::VBlank => DISPSTAT.write(DISPSTAT.read() & !(1 << 3)),
+            Irq::HBlank => DISPSTAT.write(DISPSTAT.read() & !(1 << 4)),
+            Irq::VCount => DISPSTAT.write(DISPSTAT.read() & !(1 << 5)),
+            _ => (),
+        }
+
+        IE.write(IE.read() & !irq.mask());
+    });
+}
+
+/// Sets the line (0-227) at which the VCount interrupt is requested.
+pub fn set_vcount(line: u8) {
+    free(|| {
+        DISPSTAT.write((DISPSTAT.read() & 0x00FF) | (u16::from(line) << 8));
+    });
+}
+
+/// Sets the function called by the default master ISR for the IRQ.
+///
+/// Returns the previous handler.
+pub fn set_handler(irq: Irq, handler: Option<fn()>) -> Option<fn()> {
+    free(|| unsafe {
+        let slot = ptr::addr_of_mut!(HANDLERS[irq as usize]);
+        slot.replace(handler)
+    })
+}
+
+/// Adds a function to call at the start of every VBlank, and enables the
+/// VBlank interrupt.
+///
+/// Hooks are called in the order they were added, before the handler set
+/// with [`set_handler()`]. Adding a hook that is already registered does
+/// nothing.
+pub fn add_vblank_hook(hook: fn()) -> Result<(), HookError> {
+    free(|| unsafe {
+        let hooks = &mut *ptr::addr_of_mut!(VBLANK_HOOKS);
+        if hooks.iter().flatten().any(|&h| h as usize == hook as usize) {
+            return Ok(());
+        }
+
+        let slot = hooks.iter_mut().find(|h| h.is_none()).ok_or(HookError::Full)?;
+        *slot = Some(hook);
+        Ok(())
+    })?;
+
+    enable(Irq::VBlank);
+    Ok(())
+}
+
+/// Removes a function added with [`add_vblank_hook()`].
+///
+/// The VBlank interrupt is left enabled.
+pub fn remove_vblank_hook(hook: fn()) {
+    free(|| unsafe {
+        let hooks = &mut *ptr::addr_of_mut!(VBLANK_HOOKS);
+        let Some(index) = hooks.iter().position(|h| h.map(|h| h as usize) == Some(hook as usize))
+        else {
+            return;
+        };
+
+        // Keep the remaining hooks in order.
+        hooks.copy_within((index + 1).., index);
+        hooks[MAX_VBLANK_HOOKS - 1] = None;
+    });
+}
+
+/// Runs `f` with interrupts disabled.
+pub fn free<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
     let ime = IME.read();
     IME.write(0);
 
-    match irq {
-        Irq::VBlank => {
-            DISPSTAT.write(DISPSTAT.read() | (1 << 3));
-            IE.write(1);
+    let r = f();
+
+    IME.write(ime);
+    r
+}
+
+/// Calls the registered handler of each IRQ in `flags`, after the VBlank
+/// hooks for VBlank.
+///
+/// Called by the default master ISR.
+#[doc(hidden)]
+#[no_mangle]
+extern "C" fn gba_irq_dispatch(flags: u16) {
+    if (flags & Irq::VBlank.mask()) != 0 {
+        for hook in unsafe { (*ptr::addr_of!(VBLANK_HOOKS)).iter().flatten() } {
+            hook();
         }
     }
 
-    IME.write(ime);
+    for (irq, &handler) in unsafe { (*ptr::addr_of!(HANDLERS)).iter().enumerate() } {
+        if (flags & (1 << irq)) != 0 {
+            if let Some(handler) = handler {
+                handler();
+            }
+        }
+    }
 }
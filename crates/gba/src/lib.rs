@@ -9,13 +9,45 @@
 #![deny(clippy::panic)]
 #![deny(clippy::unwrap_used)]
 
-pub use gba_proc_macros::entry;
+pub use gba_proc_macros::{entry, include_tracker};
 
 pub mod bios;
 pub mod color;
+pub mod display;
+pub mod dma;
 pub mod input;
 pub mod interrupt;
+pub mod obj;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod raster;
 pub mod register;
+pub mod save;
+pub mod sound;
+pub mod timer;
+
+/// Starts the profiler. Expands to nothing without the `profile` feature.
+#[cfg(not(feature = "profile"))]
+#[macro_export]
+macro_rules! profile_init {
+    () => {};
+}
+
+/// Measures the rest of the enclosing block as a named scope. Expands to
+/// nothing without the `profile` feature.
+#[cfg(not(feature = "profile"))]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {};
+}
+
+/// Ends the current frame of the profiler. Expands to nothing without the
+/// `profile` feature.
+#[cfg(not(feature = "profile"))]
+#[macro_export]
+macro_rules! profile_frame {
+    () => {};
+}
 
 #[doc(hidden)]
 #[panic_handler]
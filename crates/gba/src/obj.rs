@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Objects (sprites).
+//!
+//! Objects are configured through a shadow copy of OAM, which is copied to
+//! the hardware during VBlank.
+//!
+//! ```rust
+//! use gba::obj::{Oam, ObjAttr, ObjSize};
+//!
+//! let mut oam = Oam::take().unwrap();
+//! oam.commit_on_vblank().unwrap();
+//!
+//! let player = oam.alloc().unwrap();
+//! oam.set(&player, ObjAttr::new().with_size(ObjSize::S16x16).with_position(120, 80));
+//!
+//! loop {
+//!     // Update objects, then mark them ready for the next VBlank.
+//!     oam.ready();
+//!     gba::bios::vblank();
+//! }
+//! ```
+
+mod animation;
+mod attr;
+mod oam;
+mod sprite;
+mod tiles;
+
+pub use self::animation::{Animation, AnimationEvent, AnimationPlayer, Frame};
+pub use self::attr::{ObjAttr, ObjAttr0, ObjAttr1, ObjAttr2, ObjMode, ObjSize, Shape};
+pub use self::oam::{
+    commit,
+    commit_ready,
+    AffineId,
+    AffineMatrix,
+    Oam,
+    ObjId,
+    AFFINE_COUNT,
+    OBJ_COUNT,
+};
+pub use self::sprite::{MetaSprite, Part};
+pub use self::tiles::TileSet;
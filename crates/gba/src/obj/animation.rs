@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Frame-based sprite animation.
+//!
+//! ```rust
+//! use gba::obj::{Animation, AnimationPlayer, Frame, MetaSprite, Oam, ObjSize, Part};
+//!
+//! const WALK_0: [Part; 2] =
+//!     [Part::new(0, 0, ObjSize::S16x16, 0), Part::new(0, 16, ObjSize::S16x16, 4)];
+//! const WALK_1: [Part; 2] =
+//!     [Part::new(0, 0, ObjSize::S16x16, 8), Part::new(0, 16, ObjSize::S16x16, 12)];
+//! const WALK: Animation = Animation::new(&[Frame::new(&WALK_0, 8), Frame::new(&WALK_1, 8)], true);
+//!
+//! let mut oam = Oam::take().unwrap();
+//! let mut sprite = MetaSprite::<2>::new(&mut oam).unwrap();
+//! let mut player = AnimationPlayer::new(&WALK);
+//!
+//! loop {
+//!     player.update_sprite(&sprite, &mut oam);
+//!     oam.ready();
+//!     gba::bios::vblank();
+//! }
+//! ```
+
+use super::oam::Oam;
+use super::sprite::{MetaSprite, Part};
+
+/// A single frame of an animation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Frame {
+    /// The layout of the sprite for the frame
+    pub parts: &'static [Part],
+    /// The number of video frames to show the frame for
+    pub duration: u16,
+}
+
+impl Frame {
+    pub const fn new(parts: &'static [Part], duration: u16) -> Self {
+        Self { parts, duration }
+    }
+}
+
+/// A sequence of frames.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Animation {
+    pub frames: &'static [Frame],
+    /// Restart from the first frame after the last frame
+    pub looping: bool,
+}
+
+impl Animation {
+    pub const fn new(frames: &'static [Frame], looping: bool) -> Self {
+        Self { frames, looping }
+    }
+}
+
+/// Events reported to the [`AnimationPlayer`] callback.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AnimationEvent {
+    /// A new frame, by index, is shown.
+    FrameChanged(usize),
+    /// A looping animation restarted from the first frame.
+    Looped,
+    /// A non-looping animation reached the end of its last frame.
+    Finished,
+}
+
+/// Plays an [`Animation`], one video frame at a time.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    animation: &'static Animation,
+    frame: usize,
+    elapsed: u16,
+    finished: bool,
+    callback: Option<fn(AnimationEvent)>,
+}
+
+impl AnimationPlayer {
+    pub const fn new(animation: &'static Animation) -> Self {
+        Self {
+            animation,
+            frame: 0,
+            elapsed: 0,
+            finished: false,
+            callback: None,
+        }
+    }
+
+    /// Sets a function to call when the animation changes frame, loops, or
+    /// finishes.
+    pub fn set_callback(&mut self, callback: Option<fn(AnimationEvent)>) {
+        self.callback = callback;
+    }
+
+    /// Switches to a different animation, starting from its first frame.
+    ///
+    /// Does nothing if the animation is already playing.
+    pub fn play(&mut self, animation: &'static Animation) {
+        if !core::ptr::eq(self.animation, animation) {
+            self.animation = animation;
+            self.restart();
+        }
+    }
+
+    /// Restarts the animation from the first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0;
+        self.finished = false;
+    }
+
+    /// Checks if a non-looping animation has finished.
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The index of the current frame.
+    pub const fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    /// The current frame, or `None` if the animation has no frames.
+    pub fn frame(&self) -> Option<&'static Frame> {
+        self.animation.frames.get(self.frame)
+    }
+
+    /// The sprite layout of the current frame.
+    pub fn parts(&self) -> &'static [Part] {
+        self.frame().map_or(&[], |frame| frame.parts)
+    }
+
+    /// Advances the animation by one video frame, and draws the current
+    /// frame of the sprite to the shadow OAM.
+    ///
+    /// Should be called once per game loop, in place of
+    /// [`update()`](Self::update).
+    pub fn update_sprite<const N: usize>(&mut self, sprite: &MetaSprite<N>, oam: &mut Oam) {
+        self.update();
+        sprite.draw(oam, self.parts());
+    }
+
+    /// Advances the animation by one video frame.
+    ///
+    /// Should be called once per game loop. The sprite is drawn separately
+    /// with [`MetaSprite::draw()`] and [`parts()`](Self::parts).
+    pub fn update(&mut self) {
+        let frames = self.animation.frames;
+        if self.finished || frames.is_empty() {
+            return;
+        }
+
+        self.elapsed += 1;
+        if self.elapsed < frames[self.frame].duration {
+            return;
+        }
+
+        self.elapsed = 0;
+        if self.frame + 1 < frames.len() {
+            self.frame += 1;
+            self.notify(AnimationEvent::FrameChanged(self.frame));
+        } else if self.animation.looping {
+            self.frame = 0;
+            self.notify(AnimationEvent::Looped);
+            self.notify(AnimationEvent::FrameChanged(self.frame));
+        } else {
+            self.finished = true;
+            self.notify(AnimationEvent::Finished);
+        }
+    }
+
+    fn notify(&self, event: AnimationEvent) {
+        if let Some(callback) = self.callback {
+            callback(event);
+        }
+    }
+}
@@ -0,0 +1,535 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Object attributes.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#lcdobjoamattributes>
+
+use crate::display::ColorMode;
+
+/// The general shape of an object.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Shape {
+    #[default]
+    Square = 0,
+    Wide = 1,
+    Tall = 2,
+}
+
+/// The dimensions of an object, in pixels.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ObjSize {
+    #[default]
+    S8x8,
+    S16x16,
+    S32x32,
+    S64x64,
+    S16x8,
+    S32x8,
+    S32x16,
+    S64x32,
+    S8x16,
+    S8x32,
+    S16x32,
+    S32x64,
+}
+
+impl ObjSize {
+    /// The shape used for the size in attribute 0.
+    pub const fn shape(self) -> Shape {
+        match self {
+            Self::S8x8 | Self::S16x16 | Self::S32x32 | Self::S64x64 => Shape::Square,
+            Self::S16x8 | Self::S32x8 | Self::S32x16 | Self::S64x32 => Shape::Wide,
+            Self::S8x16 | Self::S8x32 | Self::S16x32 | Self::S32x64 => Shape::Tall,
+        }
+    }
+
+    /// The size value (0-3) used for the size in attribute 1.
+    pub const fn size(self) -> u16 {
+        match self {
+            Self::S8x8 | Self::S16x8 | Self::S8x16 => 0,
+            Self::S16x16 | Self::S32x8 | Self::S8x32 => 1,
+            Self::S32x32 | Self::S32x16 | Self::S16x32 => 2,
+            Self::S64x64 | Self::S64x32 | Self::S32x64 => 3,
+        }
+    }
+
+    /// Returns the size for the shape and size value, as used in the
+    /// attributes.
+    pub const fn from_shape(shape: Shape, size: u16) -> Self {
+        match (shape, size & 0b11) {
+            (Shape::Square, 0) => Self::S8x8,
+            (Shape::Square, 1) => Self::S16x16,
+            (Shape::Square, 2) => Self::S32x32,
+            (Shape::Square, _) => Self::S64x64,
+            (Shape::Wide, 0) => Self::S16x8,
+            (Shape::Wide, 1) => Self::S32x8,
+            (Shape::Wide, 2) => Self::S32x16,
+            (Shape::Wide, _) => Self::S64x32,
+            (Shape::Tall, 0) => Self::S8x16,
+            (Shape::Tall, 1) => Self::S8x32,
+            (Shape::Tall, 2) => Self::S16x32,
+            (Shape::Tall, _) => Self::S32x64,
+        }
+    }
+
+    /// The width and height of the object, in pixels.
+    pub const fn dimensions(self) -> (u16, u16) {
+        match self {
+            Self::S8x8 => (8, 8),
+            Self::S16x16 => (16, 16),
+            Self::S32x32 => (32, 32),
+            Self::S64x64 => (64, 64),
+            Self::S16x8 => (16, 8),
+            Self::S32x8 => (32, 8),
+            Self::S32x16 => (32, 16),
+            Self::S64x32 => (64, 32),
+            Self::S8x16 => (8, 16),
+            Self::S8x32 => (8, 32),
+            Self::S16x32 => (16, 32),
+            Self::S32x64 => (32, 64),
+        }
+    }
+
+    /// The number of 8x8 tiles used by the object.
+    pub const fn tiles(self) -> u16 {
+        let (width, height) = self.dimensions();
+        (width / 8) * (height / 8)
+    }
+}
+
+/// The special effect applied to an object.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ObjMode {
+    #[default]
+    Normal = 0,
+    /// The object is a first target for alpha blending.
+    SemiTransparent = 1,
+    /// The object is not drawn, but its opaque pixels define the object
+    /// window.
+    Window = 2,
+}
+
+/// Object attribute 0.
+///
+/// - Bits 0-7: Y coordinate
+/// - Bit 8: Affine
+/// - Bit 9: Double-size (affine) or hidden (not affine)
+/// - Bits 10-11: Mode
+/// - Bit 12: Mosaic
+/// - Bit 13: Color mode
+/// - Bits 14-15: Shape
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct ObjAttr0(u16);
+
+impl ObjAttr0 {
+    const Y_MASK: u16 = 0xFF;
+    const AFFINE: u16 = 1 << 8;
+    const DOUBLE_SIZE: u16 = 1 << 9;
+    const HIDDEN: u16 = 1 << 9;
+    const MODE_SHIFT: u16 = 10;
+    const MODE_MASK: u16 = 0b11 << Self::MODE_SHIFT;
+    const MOSAIC: u16 = 1 << 12;
+    const COLOR_MODE: u16 = 1 << 13;
+    const SHAPE_SHIFT: u16 = 14;
+    const SHAPE_MASK: u16 = 0b11 << Self::SHAPE_SHIFT;
+
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// The Y coordinate of the top of the object.
+    pub const fn y(self) -> u16 {
+        self.0 & Self::Y_MASK
+    }
+
+    /// Sets the Y coordinate of the top of the object.
+    ///
+    /// Values wrap at 256; the object is drawn above the screen for values
+    /// greater than 160 that would cause it to overlap the bottom edge.
+    pub const fn with_y(self, y: u16) -> Self {
+        Self((self.0 & !Self::Y_MASK) | (y & Self::Y_MASK))
+    }
+
+    /// Checks if the object uses an affine matrix.
+    pub const fn affine(self) -> bool {
+        (self.0 & Self::AFFINE) != 0
+    }
+
+    /// Sets if the object uses an affine matrix.
+    pub const fn with_affine(self, enabled: bool) -> Self {
+        self.with_flag(Self::AFFINE, enabled)
+    }
+
+    /// Checks if an affine object is drawn in double its area.
+    pub const fn double_size(self) -> bool {
+        self.affine() && (self.0 & Self::DOUBLE_SIZE) != 0
+    }
+
+    /// Sets if an affine object is drawn in double its area, to avoid
+    /// clipping when rotated.
+    ///
+    /// Only valid for affine objects; shares a bit with hidden.
+    pub const fn with_double_size(self, enabled: bool) -> Self {
+        self.with_flag(Self::DOUBLE_SIZE, enabled)
+    }
+
+    /// Checks if a regular object is hidden.
+    pub const fn hidden(self) -> bool {
+        !self.affine() && (self.0 & Self::HIDDEN) != 0
+    }
+
+    /// Sets if a regular object is hidden.
+    ///
+    /// Only valid for regular objects; shares a bit with double-size.
+    pub const fn with_hidden(self, hidden: bool) -> Self {
+        self.with_flag(Self::HIDDEN, hidden)
+    }
+
+    /// The special effect applied to the object.
+    pub const fn mode(self) -> ObjMode {
+        match (self.0 & Self::MODE_MASK) >> Self::MODE_SHIFT {
+            1 => ObjMode::SemiTransparent,
+            2 => ObjMode::Window,
+            _ => ObjMode::Normal,
+        }
+    }
+
+    /// Sets the special effect applied to the object.
+    pub const fn with_mode(self, mode: ObjMode) -> Self {
+        let mode = (mode as u16) << Self::MODE_SHIFT;
+        Self((self.0 & !Self::MODE_MASK) | mode)
+    }
+
+    /// Checks if the mosaic effect is applied.
+    pub const fn mosaic(self) -> bool {
+        (self.0 & Self::MOSAIC) != 0
+    }
+
+    /// Sets if the mosaic effect is applied.
+    pub const fn with_mosaic(self, enabled: bool) -> Self {
+        self.with_flag(Self::MOSAIC, enabled)
+    }
+
+    /// The number of bits per pixel of the tile data.
+    pub const fn color_mode(self) -> ColorMode {
+        if (self.0 & Self::COLOR_MODE) != 0 {
+            ColorMode::Bpp8
+        } else {
+            ColorMode::Bpp4
+        }
+    }
+
+    /// Sets the number of bits per pixel of the tile data.
+    pub const fn with_color_mode(self, mode: ColorMode) -> Self {
+        self.with_flag(Self::COLOR_MODE, matches!(mode, ColorMode::Bpp8))
+    }
+
+    /// The general shape of the object.
+    pub const fn shape(self) -> Shape {
+        match (self.0 & Self::SHAPE_MASK) >> Self::SHAPE_SHIFT {
+            1 => Shape::Wide,
+            2 => Shape::Tall,
+            _ => Shape::Square,
+        }
+    }
+
+    /// Sets the general shape of the object.
+    pub const fn with_shape(self, shape: Shape) -> Self {
+        let shape = (shape as u16) << Self::SHAPE_SHIFT;
+        Self((self.0 & !Self::SHAPE_MASK) | shape)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+/// Object attribute 1.
+///
+/// - Bits 0-8: X coordinate
+/// - Bits 9-13: Affine matrix index (affine)
+/// - Bit 12: Horizontal flip (not affine)
+/// - Bit 13: Vertical flip (not affine)
+/// - Bits 14-15: Size
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct ObjAttr1(u16);
+
+impl ObjAttr1 {
+    const X_MASK: u16 = 0x1FF;
+    const AFFINE_SHIFT: u16 = 9;
+    const AFFINE_MASK: u16 = 0b1_1111 << Self::AFFINE_SHIFT;
+    const HFLIP: u16 = 1 << 12;
+    const VFLIP: u16 = 1 << 13;
+    const SIZE_SHIFT: u16 = 14;
+    const SIZE_MASK: u16 = 0b11 << Self::SIZE_SHIFT;
+
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// The X coordinate of the left of the object.
+    pub const fn x(self) -> u16 {
+        self.0 & Self::X_MASK
+    }
+
+    /// Sets the X coordinate of the left of the object.
+    ///
+    /// Values wrap at 512; negative positions are represented by values
+    /// greater than 240.
+    pub const fn with_x(self, x: u16) -> Self {
+        Self((self.0 & !Self::X_MASK) | (x & Self::X_MASK))
+    }
+
+    /// The index of the affine matrix used by an affine object.
+    pub const fn affine_index(self) -> u16 {
+        (self.0 & Self::AFFINE_MASK) >> Self::AFFINE_SHIFT
+    }
+
+    /// Sets the index (0-31) of the affine matrix used by an affine object.
+    ///
+    /// Only valid for affine objects; shares bits with the flips.
+    pub const fn with_affine_index(self, index: u16) -> Self {
+        let index = (index << Self::AFFINE_SHIFT) & Self::AFFINE_MASK;
+        Self((self.0 & !Self::AFFINE_MASK) | index)
+    }
+
+    /// Checks if a regular object is flipped horizontally.
+    pub const fn hflip(self) -> bool {
+        (self.0 & Self::HFLIP) != 0
+    }
+
+    /// Sets if a regular object is flipped horizontally.
+    pub const fn with_hflip(self, flip: bool) -> Self {
+        self.with_flag(Self::HFLIP, flip)
+    }
+
+    /// Checks if a regular object is flipped vertically.
+    pub const fn vflip(self) -> bool {
+        (self.0 & Self::VFLIP) != 0
+    }
+
+    /// Sets if a regular object is flipped vertically.
+    pub const fn with_vflip(self, flip: bool) -> Self {
+        self.with_flag(Self::VFLIP, flip)
+    }
+
+    /// The size value (0-3) of the object, interpreted by the shape.
+    pub const fn size(self) -> u16 {
+        (self.0 & Self::SIZE_MASK) >> Self::SIZE_SHIFT
+    }
+
+    /// Sets the size value (0-3) of the object, interpreted by the shape.
+    pub const fn with_size(self, size: u16) -> Self {
+        let size = (size << Self::SIZE_SHIFT) & Self::SIZE_MASK;
+        Self((self.0 & !Self::SIZE_MASK) | size)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+/// Object attribute 2.
+///
+/// - Bits 0-9: Tile index
+/// - Bits 10-11: Priority
+/// - Bits 12-15: Palette bank, for 4bpp tiles
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct ObjAttr2(u16);
+
+impl ObjAttr2 {
+    const TILE_MASK: u16 = 0x3FF;
+    const PRIORITY_SHIFT: u16 = 10;
+    const PRIORITY_MASK: u16 = 0b11 << Self::PRIORITY_SHIFT;
+    const PALETTE_SHIFT: u16 = 12;
+    const PALETTE_MASK: u16 = 0xF << Self::PALETTE_SHIFT;
+
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// The index of the first tile, in 32-byte units.
+    pub const fn tile(self) -> u16 {
+        self.0 & Self::TILE_MASK
+    }
+
+    /// Sets the index of the first tile, in 32-byte units.
+    pub const fn with_tile(self, tile: u16) -> Self {
+        Self((self.0 & !Self::TILE_MASK) | (tile & Self::TILE_MASK))
+    }
+
+    /// The drawing priority relative to backgrounds, where 0 is drawn in
+    /// front.
+    pub const fn priority(self) -> u16 {
+        (self.0 & Self::PRIORITY_MASK) >> Self::PRIORITY_SHIFT
+    }
+
+    /// Sets the drawing priority (0-3) relative to backgrounds.
+    pub const fn with_priority(self, priority: u16) -> Self {
+        let priority = (priority << Self::PRIORITY_SHIFT) & Self::PRIORITY_MASK;
+        Self((self.0 & !Self::PRIORITY_MASK) | priority)
+    }
+
+    /// The palette bank used by 4bpp tiles.
+    pub const fn palette_bank(self) -> u16 {
+        (self.0 & Self::PALETTE_MASK) >> Self::PALETTE_SHIFT
+    }
+
+    /// Sets the palette bank (0-15) used by 4bpp tiles.
+    pub const fn with_palette_bank(self, bank: u16) -> Self {
+        let bank = (bank << Self::PALETTE_SHIFT) & Self::PALETTE_MASK;
+        Self((self.0 & !Self::PALETTE_MASK) | bank)
+    }
+}
+
+macro_rules! impl_from_u16 {
+    ($attr:ty) => {
+        impl From<u16> for $attr {
+            fn from(value: u16) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$attr> for u16 {
+            fn from(value: $attr) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+impl_from_u16!(ObjAttr0);
+impl_from_u16!(ObjAttr1);
+impl_from_u16!(ObjAttr2);
+
+/// The attributes of a single object.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ObjAttr {
+    pub attr0: ObjAttr0,
+    pub attr1: ObjAttr1,
+    pub attr2: ObjAttr2,
+}
+
+impl ObjAttr {
+    /// An object that is not drawn.
+    pub const HIDDEN: Self = Self {
+        attr0: ObjAttr0::new().with_hidden(true),
+        attr1: ObjAttr1::new(),
+        attr2: ObjAttr2::new(),
+    };
+
+    /// Returns a visible 8x8 object at (0, 0) using tile 0.
+    pub const fn new() -> Self {
+        Self {
+            attr0: ObjAttr0::new(),
+            attr1: ObjAttr1::new(),
+            attr2: ObjAttr2::new(),
+        }
+    }
+
+    /// The position of the top left of the object.
+    ///
+    /// Coordinates past the right or bottom of the screen are returned as
+    /// negative values.
+    pub const fn position(self) -> (i16, i16) {
+        let x = self.attr1.x() as i16;
+        let y = self.attr0.y() as i16;
+        let x = if x >= 240 {
+            x - 512
+        } else {
+            x
+        };
+        let y = if y >= 160 {
+            y - 256
+        } else {
+            y
+        };
+        (x, y)
+    }
+
+    /// Sets the position of the top left of the object.
+    ///
+    /// Negative coordinates place the object partially off the top or left of
+    /// the screen.
+    pub const fn with_position(self, x: i16, y: i16) -> Self {
+        Self {
+            attr0: self.attr0.with_y(y as u16),
+            attr1: self.attr1.with_x(x as u16),
+            attr2: self.attr2,
+        }
+    }
+
+    /// The dimensions of the object.
+    pub const fn size(self) -> ObjSize {
+        ObjSize::from_shape(self.attr0.shape(), self.attr1.size())
+    }
+
+    /// Sets the shape and size of the object.
+    pub const fn with_size(self, size: ObjSize) -> Self {
+        Self {
+            attr0: self.attr0.with_shape(size.shape()),
+            attr1: self.attr1.with_size(size.size()),
+            attr2: self.attr2,
+        }
+    }
+
+    /// Sets the index of the first tile.
+    pub const fn with_tile(self, tile: u16) -> Self {
+        Self {
+            attr0: self.attr0,
+            attr1: self.attr1,
+            attr2: self.attr2.with_tile(tile),
+        }
+    }
+
+    /// Sets the palette bank used by 4bpp tiles.
+    pub const fn with_palette_bank(self, bank: u16) -> Self {
+        Self {
+            attr0: self.attr0,
+            attr1: self.attr1,
+            attr2: self.attr2.with_palette_bank(bank),
+        }
+    }
+
+    /// Sets the drawing priority relative to backgrounds.
+    pub const fn with_priority(self, priority: u16) -> Self {
+        Self {
+            attr0: self.attr0,
+            attr1: self.attr1,
+            attr2: self.attr2.with_priority(priority),
+        }
+    }
+
+    /// Sets if a regular object is flipped horizontally and vertically.
+    pub const fn with_flip(self, hflip: bool, vflip: bool) -> Self {
+        Self {
+            attr0: self.attr0,
+            attr1: self.attr1.with_hflip(hflip).with_vflip(vflip),
+            attr2: self.attr2,
+        }
+    }
+
+    /// Makes the object use the affine matrix.
+    ///
+    /// Affine objects cannot be flipped or hidden; use the matrix to flip and
+    /// [`ObjAttr::HIDDEN`] to hide them.
+    pub const fn with_affine(self, index: u16, double_size: bool) -> Self {
+        Self {
+            attr0: self.attr0.with_affine(true).with_double_size(double_size),
+            attr1: self.attr1.with_affine_index(index),
+            attr2: self.attr2,
+        }
+    }
+}
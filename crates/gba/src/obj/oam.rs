@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Shadow object attribute memory.
+//!
+//! OAM can only be safely written during VBlank (or HBlank, if enabled in
+//! DISPCNT). Changes are made to a copy of OAM in work RAM, which is then
+//! copied to OAM during VBlank.
+
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::attr::{ObjAttr, ObjAttr0, ObjAttr1, ObjAttr2};
+use crate::bios;
+use crate::interrupt::{self, HookError};
+
+/// The start of object attribute memory.
+const OAM: *mut u32 = 0x0700_0000 as *mut u32;
+
+/// The number of objects in OAM.
+pub const OBJ_COUNT: usize = 128;
+/// The number of object affine matrices in OAM.
+pub const AFFINE_COUNT: usize = 32;
+
+/// An object entry as stored in OAM.
+///
+/// The fourth halfword of each entry holds one parameter of an affine matrix.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct OamEntry {
+    attr0: ObjAttr0,
+    attr1: ObjAttr1,
+    attr2: ObjAttr2,
+    affine: i16,
+}
+
+impl OamEntry {
+    const HIDDEN: Self = Self {
+        attr0: ObjAttr::HIDDEN.attr0,
+        attr1: ObjAttr::HIDDEN.attr1,
+        attr2: ObjAttr::HIDDEN.attr2,
+        affine: 0,
+    };
+}
+
+#[repr(C, align(4))]
+struct Shadow([OamEntry; OBJ_COUNT]);
+
+/// The copy of OAM that is committed during VBlank.
+static mut SHADOW: Shadow = Shadow([OamEntry::HIDDEN; OBJ_COUNT]);
+/// Set when the shadow OAM is ready to be committed.
+static READY: AtomicBool = AtomicBool::new(false);
+/// Set when the [`Oam`] has been taken.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+/// The allocated object slots, one bit per object.
+///
+/// Kept outside of [`Oam`] so that objects can be released when their owner
+/// is dropped.
+static mut OBJECTS: [u32; OBJ_COUNT / 32] = [0; OBJ_COUNT / 32];
+
+/// The 2x2 matrix used to transform affine objects.
+///
+/// Each value is signed 8.8 fixed point, mapping screen space to texture
+/// space.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AffineMatrix {
+    pub pa: i16,
+    pub pb: i16,
+    pub pc: i16,
+    pub pd: i16,
+}
+
+impl AffineMatrix {
+    /// No rotation or scaling.
+    pub const IDENTITY: Self = Self {
+        pa: 1 << 8,
+        pb: 0,
+        pc: 0,
+        pd: 1 << 8,
+    };
+}
+
+impl Default for AffineMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// An allocated object slot in OAM.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ObjId(u8);
+
+impl ObjId {
+    /// The index (0-127) of the object in OAM.
+    pub const fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// An allocated affine matrix in OAM.
+#[derive(Debug, Eq, PartialEq)]
+pub struct AffineId(u8);
+
+impl AffineId {
+    /// The index (0-31) of the matrix, used for
+    /// [`ObjAttr::with_affine()`](super::ObjAttr::with_affine).
+    pub const fn index(&self) -> u16 {
+        self.0 as u16
+    }
+}
+
+/// Exclusive access to the shadow OAM and allocation of its objects and
+/// affine matrices.
+///
+/// Objects are drawn in order of priority and then index, so objects
+/// allocated first are drawn in front of those allocated later.
+#[derive(Debug)]
+pub struct Oam {
+    matrices: u32,
+}
+
+impl Oam {
+    /// Returns the OAM, if it has not already been taken.
+    ///
+    /// All objects are hidden.
+    pub fn take() -> Option<Self> {
+        interrupt::free(|| {
+            if TAKEN.load(Ordering::Relaxed) {
+                return None;
+            }
+            TAKEN.store(true, Ordering::Relaxed);
+
+            unsafe {
+                *ptr::addr_of_mut!(OBJECTS) = [0; OBJ_COUNT / 32];
+            }
+
+            let mut oam = Self { matrices: 0 };
+            oam.clear();
+            Some(oam)
+        })
+    }
+
+    /// Hides all objects.
+    pub fn clear(&mut self) {
+        for index in 0..OBJ_COUNT {
+            self.write(index, ObjAttr::HIDDEN);
+        }
+    }
+
+    /// Allocates the first free object slot.
+    pub fn alloc(&mut self) -> Option<ObjId> {
+        interrupt::free(|| {
+            let objects = unsafe { &mut *ptr::addr_of_mut!(OBJECTS) };
+            let (word, bits) =
+                objects.iter_mut().enumerate().find(|(_, bits)| **bits != u32::MAX)?;
+
+            let bit = bits.trailing_ones();
+            *bits |= 1 << bit;
+            Some(ObjId((word * 32) as u8 + bit as u8))
+        })
+    }
+
+    /// Releases the object slot, hiding the object.
+    pub fn free(&mut self, id: ObjId) {
+        release(id);
+    }
+
+    /// Returns the attributes of the object.
+    pub fn get(&self, id: &ObjId) -> ObjAttr {
+        let entry = unsafe { ptr::addr_of!(SHADOW.0[id.index()]).read() };
+        ObjAttr {
+            attr0: entry.attr0,
+            attr1: entry.attr1,
+            attr2: entry.attr2,
+        }
+    }
+
+    /// Sets the attributes of the object.
+    pub fn set(&mut self, id: &ObjId, attr: ObjAttr) {
+        self.write(id.index(), attr);
+    }
+
+    /// Allocates the first free affine matrix, initialized to the identity.
+    pub fn alloc_affine(&mut self) -> Option<AffineId> {
+        if self.matrices == u32::MAX {
+            return None;
+        }
+
+        let index = self.matrices.trailing_ones();
+        self.matrices |= 1 << index;
+
+        let id = AffineId(index as u8);
+        self.set_affine(&id, AffineMatrix::IDENTITY);
+        Some(id)
+    }
+
+    /// Releases the affine matrix.
+    pub fn free_affine(&mut self, id: AffineId) {
+        self.matrices &= !(1 << id.0);
+    }
+
+    /// Sets the parameters of the affine matrix.
+    pub fn set_affine(&mut self, id: &AffineId, matrix: AffineMatrix) {
+        // The parameters are spread across the unused halfword of 4 entries.
+        let base = usize::from(id.0) * 4;
+        let params = [matrix.pa, matrix.pb, matrix.pc, matrix.pd];
+        for (i, param) in params.into_iter().enumerate() {
+            unsafe {
+                ptr::addr_of_mut!(SHADOW.0[base + i].affine).write(param);
+            }
+        }
+    }
+
+    /// Marks the shadow OAM as ready to be committed by [`commit_ready()`].
+    ///
+    /// Call once all objects have been updated for the frame.
+    pub fn ready(&mut self) {
+        READY.store(true, Ordering::Release);
+    }
+
+    /// Commits the shadow OAM during every VBlank in which it is ready.
+    ///
+    /// [`commit_ready()`] is added as a VBlank hook with
+    /// [`interrupt::add_vblank_hook()`], so it runs alongside the game's own
+    /// VBlank handler.
+    pub fn commit_on_vblank(&mut self) -> Result<(), HookError> {
+        interrupt::add_vblank_hook(commit_ready)
+    }
+
+    fn write(&mut self, index: usize, attr: ObjAttr) {
+        write(index, attr);
+    }
+}
+
+fn write(index: usize, attr: ObjAttr) {
+    unsafe {
+        let entry = ptr::addr_of_mut!(SHADOW.0[index]);
+        ptr::addr_of_mut!((*entry).attr0).write(attr.attr0);
+        ptr::addr_of_mut!((*entry).attr1).write(attr.attr1);
+        ptr::addr_of_mut!((*entry).attr2).write(attr.attr2);
+    }
+}
+
+/// Releases an object slot, hiding the object, without the [`Oam`].
+///
+/// The ID proves the slot was allocated, so this is used to free objects
+/// when their owner is dropped.
+pub(super) fn release(id: ObjId) {
+    let index = id.index();
+    interrupt::free(|| unsafe {
+        write(index, ObjAttr::HIDDEN);
+        (*ptr::addr_of_mut!(OBJECTS))[index / 32] &= !(1 << (index % 32));
+    });
+}
+
+/// Copies the shadow OAM to OAM.
+///
+/// Must be called during VBlank.
+pub fn commit() {
+    const WORDS: usize = core::mem::size_of::<Shadow>() / 4;
+
+    unsafe {
+        bios::cpu_fast_set(ptr::addr_of!(SHADOW) as *const u32, OAM, WORDS);
+    }
+}
+
+/// Copies the shadow OAM to OAM if it has been marked as
+/// [`ready`](Oam::ready).
+///
+/// Intended to be called from the VBlank interrupt handler.
+pub fn commit_ready() {
+    if READY.load(Ordering::Acquire) {
+        commit();
+        READY.store(false, Ordering::Release);
+    }
+}
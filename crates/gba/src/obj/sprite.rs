@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Sprites composed of multiple hardware objects.
+
+use super::attr::{ObjAttr, ObjSize};
+use super::oam::{self, Oam, ObjId};
+
+/// A single hardware object within a [`MetaSprite`].
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct Part {
+    /// Horizontal offset from the sprite's origin
+    pub x: i16,
+    /// Vertical offset from the sprite's origin
+    pub y: i16,
+    pub size: ObjSize,
+    /// Index of the first tile, relative to the sprite's base tile
+    pub tile: u16,
+    pub hflip: bool,
+    pub vflip: bool,
+}
+
+impl Part {
+    pub const fn new(x: i16, y: i16, size: ObjSize, tile: u16) -> Self {
+        Self {
+            x,
+            y,
+            size,
+            tile,
+            hflip: false,
+            vflip: false,
+        }
+    }
+}
+
+/// A group of up to `N` objects that are positioned and flipped as a unit.
+///
+/// The layout of the objects is given by a list of [`Part`]s, which can
+/// change each frame (e.g., from an [`Animation`](super::Animation)). The
+/// objects are hidden and released when the sprite is dropped.
+#[derive(Debug)]
+pub struct MetaSprite<const N: usize> {
+    objs: [Option<ObjId>; N],
+    x: i16,
+    y: i16,
+    hflip: bool,
+    vflip: bool,
+    base_tile: u16,
+    palette_bank: u16,
+    priority: u16,
+}
+
+impl<const N: usize> MetaSprite<N> {
+    /// Allocates `N` objects for the sprite.
+    ///
+    /// Returns `None` if there are not enough free objects.
+    pub fn new(oam: &mut Oam) -> Option<Self> {
+        let objs = core::array::from_fn(|_| oam.alloc());
+
+        if objs.iter().any(Option::is_none) {
+            for id in objs.into_iter().flatten() {
+                oam.free(id);
+            }
+            return None;
+        }
+
+        Some(Self {
+            objs,
+            x: 0,
+            y: 0,
+            hflip: false,
+            vflip: false,
+            base_tile: 0,
+            palette_bank: 0,
+            priority: 0,
+        })
+    }
+
+    /// The position of the sprite's origin.
+    pub const fn position(&self) -> (i16, i16) {
+        (self.x, self.y)
+    }
+
+    /// Sets the position of the sprite's origin.
+    pub fn set_position(&mut self, x: i16, y: i16) {
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Sets if the whole sprite is flipped around its origin.
+    pub fn set_flip(&mut self, hflip: bool, vflip: bool) {
+        self.hflip = hflip;
+        self.vflip = vflip;
+    }
+
+    /// Sets the tile that part tile indexes are relative to, such as from a
+    /// [`TileSet`](super::TileSet).
+    pub fn set_base_tile(&mut self, tile: u16) {
+        self.base_tile = tile;
+    }
+
+    /// Sets the palette bank used by all parts.
+    pub fn set_palette_bank(&mut self, bank: u16) {
+        self.palette_bank = bank;
+    }
+
+    /// Sets the drawing priority of all parts.
+    pub fn set_priority(&mut self, priority: u16) {
+        self.priority = priority;
+    }
+
+    /// Writes the parts to the shadow OAM.
+    ///
+    /// Objects without a part are hidden. Parts beyond `N` are ignored.
+    pub fn draw(&self, oam: &mut Oam, parts: &[Part]) {
+        let mut parts = parts.iter();
+
+        for id in self.objs.iter().flatten() {
+            let attr = match parts.next() {
+                Some(part) => self.attr(part),
+                None => ObjAttr::HIDDEN,
+            };
+
+            oam.set(id, attr);
+        }
+    }
+
+    fn attr(&self, part: &Part) -> ObjAttr {
+        let (width, height) = part.size.dimensions();
+
+        // Mirror the part around the origin.
+        let x = if self.hflip {
+            -part.x - width as i16
+        } else {
+            part.x
+        };
+        let y = if self.vflip {
+            -part.y - height as i16
+        } else {
+            part.y
+        };
+
+        ObjAttr::new()
+            .with_size(part.size)
+            .with_position(self.x + x, self.y + y)
+            .with_flip(part.hflip ^ self.hflip, part.vflip ^ self.vflip)
+            .with_tile(self.base_tile + part.tile)
+            .with_palette_bank(self.palette_bank)
+            .with_priority(self.priority)
+    }
+}
+
+impl<const N: usize> Drop for MetaSprite<N> {
+    fn drop(&mut self) {
+        for id in self.objs.iter_mut().filter_map(Option::take) {
+            oam::release(id);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Allocation of object VRAM for object tiles.
+//!
+//! Object VRAM is 32KB, addressed in 32-byte units: one 4bpp tile, or half
+//! of an 8bpp tile. In the bitmap modes, the first 16KB is used by the frame
+//! buffers and only units 512-1023 can be used. A single allocation can use
+//! all of the available units: up to 1024 4bpp tiles or 512 8bpp tiles, or
+//! half that in the bitmap modes.
+//!
+//! The arrangement of an object's tiles depends on the
+//! [`ObjMapping`](crate::display::ObjMapping) in DISPCNT, which is read when
+//! tiles are allocated. Changing the mapping or video mode after allocating
+//! tiles invalidates the allocations.
+
+use core::ptr;
+
+use super::attr::ObjSize;
+use crate::display::{ColorMode, DisplayControl, ObjMapping, Tile4, Tile8, VramError};
+use crate::interrupt;
+
+/// The start of object VRAM.
+const OBJ_VRAM: *mut u32 = 0x0601_0000 as *mut u32;
+/// The number of 32-byte units in object VRAM.
+const UNITS: usize = 1024;
+/// The first unit that can be used in the bitmap modes.
+const BITMAP_FIRST_UNIT: usize = 512;
+/// The number of units in each row when using 2D mapping.
+const ROW_UNITS: usize = 32;
+/// The maximum number of tile sets that can be allocated at once.
+const MAX_SETS: usize = 128;
+
+/// A region of object VRAM shared by one or more [`TileSet`]s.
+#[derive(Clone, Copy)]
+struct Allocation {
+    start: u16,
+    /// Width of each row, in units
+    width: u16,
+    /// Number of rows
+    height: u16,
+    /// Number of tile sets sharing the allocation. Saturates at `u16::MAX`,
+    /// after which the allocation is never freed.
+    refs: u16,
+}
+
+impl Allocation {
+    const FREE: Self = Self {
+        start: 0,
+        width: 0,
+        height: 0,
+        refs: 0,
+    };
+}
+
+/// Allocations, indexed by [`TileSet::slot`].
+static mut ALLOCATIONS: [Allocation; MAX_SETS] = [Allocation::FREE; MAX_SETS];
+/// One bit per unit of object VRAM that is in use.
+static mut USED: [u32; UNITS / 32] = [0; UNITS / 32];
+
+/// A reference-counted allocation of object tiles.
+///
+/// Cloning a tile set shares the same tiles. The tiles are freed when the
+/// last clone is dropped. If there are ever `u16::MAX` clones at once, the
+/// count saturates and the tiles are never freed.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TileSet {
+    slot: u8,
+    start: u16,
+    /// Width of each row, in tiles
+    width: u16,
+    height: u16,
+    color_mode: ColorMode,
+    mapping: ObjMapping,
+}
+
+impl TileSet {
+    /// Allocates tiles for an object of the given size.
+    pub fn alloc(size: ObjSize, color_mode: ColorMode) -> Result<Self, VramError> {
+        let (width, height) = size.dimensions();
+        Self::alloc_rect(width / 8, height / 8, color_mode)
+    }
+
+    /// Allocates `count` tiles.
+    ///
+    /// This is intended for 1D mapping, such as for storing all frames of an
+    /// animation, and accepts any count that fits in object VRAM. With 2D
+    /// mapping, the tiles are allocated as a single row of at most 32 units.
+    pub fn alloc_tiles(count: u16, color_mode: ColorMode) -> Result<Self, VramError> {
+        Self::alloc_rect(count, 1, color_mode)
+    }
+
+    fn alloc_rect(width: u16, height: u16, color_mode: ColorMode) -> Result<Self, VramError> {
+        let display = DisplayControl::get();
+        let mapping = display.obj_mapping();
+        let first = if display.mode().is_bitmap() {
+            BITMAP_FIRST_UNIT
+        } else {
+            0
+        };
+
+        let units_per_tile = match color_mode {
+            ColorMode::Bpp4 => 1,
+            ColorMode::Bpp8 => 2,
+        };
+
+        // 1D mapping stores the rows one after the other.
+        let (unit_width, rows) = match mapping {
+            ObjMapping::OneDimensional => {
+                ((usize::from(width) * usize::from(height)).saturating_mul(units_per_tile), 1)
+            }
+            ObjMapping::TwoDimensional => {
+                (usize::from(width) * units_per_tile, usize::from(height))
+            }
+        };
+
+        if unit_width == 0 || rows == 0 {
+            return Err(VramError::OutOfRange);
+        }
+        // Larger than all of the available object VRAM.
+        if unit_width.saturating_mul(rows) > UNITS - first {
+            return Err(VramError::OutOfRange);
+        }
+        if mapping == ObjMapping::TwoDimensional && unit_width > ROW_UNITS {
+            return Err(VramError::OutOfRange);
+        }
+
+        interrupt::free(|| unsafe {
+            let allocations = &mut *ptr::addr_of_mut!(ALLOCATIONS);
+            let used = &mut *ptr::addr_of_mut!(USED);
+
+            let slot = allocations.iter().position(|a| a.refs == 0).ok_or(VramError::Exhausted)?;
+            let start = find_free(used, first, unit_width, rows, units_per_tile, mapping)
+                .ok_or(VramError::Exhausted)?;
+
+            set_used(used, start, unit_width, rows, true);
+            allocations[slot] = Allocation {
+                start: start as u16,
+                width: unit_width as u16,
+                height: rows as u16,
+                refs: 1,
+            };
+
+            Ok(Self {
+                slot: slot as u8,
+                start: start as u16,
+                width,
+                height,
+                color_mode,
+                mapping,
+            })
+        })
+    }
+
+    /// The index of the first tile, used for
+    /// [`ObjAttr::with_tile()`](super::ObjAttr::with_tile).
+    pub const fn tile(&self) -> u16 {
+        self.start
+    }
+
+    /// The number of tiles in the set.
+    pub const fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Checks if the set has no tiles.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of bits per pixel of the tiles.
+    pub const fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// Copies 4bpp tiles into the set, starting at tile `offset`.
+    ///
+    /// Tiles are given in row order; the layout of 2D mapping is handled.
+    pub fn load4(&self, offset: usize, tiles: &[Tile4]) -> Result<(), VramError> {
+        if self.color_mode != ColorMode::Bpp4 {
+            return Err(VramError::OutOfRange);
+        }
+
+        self.load(offset, tiles.len(), tiles.iter().map(|tile| &tile.0[..]))
+    }
+
+    /// Copies 8bpp tiles into the set, starting at tile `offset`.
+    ///
+    /// Tiles are given in row order; the layout of 2D mapping is handled.
+    pub fn load8(&self, offset: usize, tiles: &[Tile8]) -> Result<(), VramError> {
+        if self.color_mode != ColorMode::Bpp8 {
+            return Err(VramError::OutOfRange);
+        }
+
+        self.load(offset, tiles.len(), tiles.iter().map(|tile| &tile.0[..]))
+    }
+
+    fn load<'a, I>(&self, offset: usize, len: usize, tiles: I) -> Result<(), VramError>
+    where
+        I: Iterator<Item = &'a [u32]>,
+    {
+        if offset + len > self.len() {
+            return Err(VramError::OutOfRange);
+        }
+
+        let units_per_tile = match self.color_mode {
+            ColorMode::Bpp4 => 1,
+            ColorMode::Bpp8 => 2,
+        };
+
+        for (i, words) in tiles.enumerate() {
+            let index = offset + i;
+            let unit = match self.mapping {
+                ObjMapping::OneDimensional => index * units_per_tile,
+                ObjMapping::TwoDimensional => {
+                    let width = usize::from(self.width);
+                    (index / width) * ROW_UNITS + (index % width) * units_per_tile
+                }
+            };
+
+            let dest = unsafe { OBJ_VRAM.add((usize::from(self.start) + unit) * 8) };
+            for (j, &word) in words.iter().enumerate() {
+                unsafe {
+                    dest.add(j).write_volatile(word);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Clone for TileSet {
+    fn clone(&self) -> Self {
+        interrupt::free(|| unsafe {
+            let refs = ptr::addr_of_mut!(ALLOCATIONS[usize::from(self.slot)].refs);
+            *refs = (*refs).saturating_add(1);
+        });
+
+        Self {
+            slot: self.slot,
+            start: self.start,
+            width: self.width,
+            height: self.height,
+            color_mode: self.color_mode,
+            mapping: self.mapping,
+        }
+    }
+}
+
+impl Drop for TileSet {
+    fn drop(&mut self) {
+        interrupt::free(|| unsafe {
+            let allocation = &mut *ptr::addr_of_mut!(ALLOCATIONS[usize::from(self.slot)]);
+            // A saturated count no longer knows how many clones remain.
+            if allocation.refs == u16::MAX {
+                return;
+            }
+
+            allocation.refs -= 1;
+
+            if allocation.refs == 0 {
+                let used = &mut *ptr::addr_of_mut!(USED);
+                set_used(
+                    used,
+                    usize::from(allocation.start),
+                    usize::from(allocation.width),
+                    usize::from(allocation.height),
+                    false,
+                );
+            }
+        });
+    }
+}
+
+/// Returns the first free unit, aligned to `align`, that can hold `rows` rows
+/// of `width` units.
+fn find_free(
+    used: &[u32],
+    first: usize,
+    width: usize,
+    rows: usize,
+    align: usize,
+    mapping: ObjMapping,
+) -> Option<usize> {
+    match mapping {
+        ObjMapping::OneDimensional => {
+            (first..=(UNITS - width)).step_by(align).find(|&start| is_free(used, start, width, 1))
+        }
+        ObjMapping::TwoDimensional => {
+            let first_row = first / ROW_UNITS;
+            let last_row = (UNITS / ROW_UNITS).checked_sub(rows)?;
+            (first_row..=last_row).find_map(|row| {
+                (0..=(ROW_UNITS - width))
+                    .step_by(align)
+                    .map(|column| row * ROW_UNITS + column)
+                    .find(|&start| is_free(used, start, width, rows))
+            })
+        }
+    }
+}
+
+fn is_free(used: &[u32], start: usize, width: usize, rows: usize) -> bool {
+    (0..rows).all(|row| {
+        let start = start + row * ROW_UNITS;
+        (start..(start + width)).all(|unit| (used[unit / 32] & (1 << (unit % 32))) == 0)
+    })
+}
+
+fn set_used(used: &mut [u32], start: usize, width: usize, rows: usize, value: bool) {
+    for row in 0..rows {
+        let start = start + row * ROW_UNITS;
+        for unit in start..(start + width) {
+            if value {
+                used[unit / 32] |= 1 << (unit % 32);
+            } else {
+                used[unit / 32] &= !(1 << (unit % 32));
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Frame profiler.
+//!
+//! Measures named scopes in CPU cycles using the cascaded timers 2 and 3, and
+//! periodically logs the per-frame minimum, average, and maximum of each
+//! scope to mGBA.
+//!
+//! The profiler is only available with the `profile` feature. Without it,
+//! the `profile_init!`, `profile_scope!`, and `profile_frame!` macros expand
+//! to nothing, so they can be left in release builds.
+//!
+//! ```rust
+//! gba::profile_init!();
+//!
+//! loop {
+//!     {
+//!         gba::profile_scope!("update");
+//!         update();
+//!     }
+//!     {
+//!         gba::profile_scope!("draw");
+//!         draw();
+//!     }
+//!
+//!     gba::profile_frame!();
+//!     gba::bios::vblank();
+//! }
+//! ```
+
+use core::ptr;
+
+use crate::interrupt;
+use crate::timer::{Stopwatch, Timer2, Timer3, FRAME_CYCLES};
+
+/// The maximum number of distinct scopes.
+pub const MAX_SCOPES: usize = 16;
+/// The default number of frames between reports.
+const DEFAULT_INTERVAL: u32 = 60;
+
+/// Statistics for a named scope.
+#[derive(Clone, Copy)]
+struct Stats {
+    name: &'static str,
+    /// Cycles in the current frame
+    frame: u32,
+    /// Per-frame minimum, maximum, and sum over the report interval
+    min: u32,
+    max: u32,
+    sum: u64,
+}
+
+impl Stats {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            frame: 0,
+            min: u32::MAX,
+            max: 0,
+            sum: 0,
+        }
+    }
+}
+
+struct Profiler {
+    stopwatch: Stopwatch,
+    scopes: [Option<Stats>; MAX_SCOPES],
+    frames: u32,
+    interval: u32,
+}
+
+static mut PROFILER: Option<Profiler> = None;
+
+/// Starts the profiler, taking timers 2 and 3 and enabling mGBA logging.
+///
+/// Returns `false` if the timers have already been taken.
+pub fn init() -> bool {
+    let (Some(low), Some(high)) = (Timer2::take(), Timer3::take()) else {
+        return false;
+    };
+
+    mgba::enable();
+
+    let mut stopwatch = Stopwatch::new(low, high);
+    stopwatch.start();
+
+    interrupt::free(|| unsafe {
+        *ptr::addr_of_mut!(PROFILER) = Some(Profiler {
+            stopwatch,
+            scopes: [None; MAX_SCOPES],
+            frames: 0,
+            interval: DEFAULT_INTERVAL,
+        });
+    });
+
+    true
+}
+
+/// Sets the number of frames between reports.
+pub fn set_interval(frames: u32) {
+    with_profiler(|profiler| profiler.interval = frames.max(1));
+}
+
+/// A scope being measured, which records the elapsed cycles when dropped.
+///
+/// Created by [`profile_scope!`](crate::profile_scope).
+#[must_use]
+pub struct Scope {
+    name: &'static str,
+    start: u32,
+}
+
+impl Scope {
+    /// Starts measuring a scope.
+    pub fn enter(name: &'static str) -> Self {
+        let start = with_profiler(|profiler| profiler.stopwatch.elapsed()).unwrap_or(0);
+        Self { name, start }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        with_profiler(|profiler| {
+            let cycles = profiler.stopwatch.elapsed().wrapping_sub(self.start);
+
+            let scopes = &mut profiler.scopes;
+            let index = scopes
+                .iter()
+                .position(|s| s.is_some_and(|s| s.name == self.name))
+                .or_else(|| scopes.iter().position(Option::is_none));
+
+            // Scopes beyond the maximum are ignored.
+            match index.map(|i| &mut scopes[i]) {
+                Some(Some(stats)) => stats.frame = stats.frame.saturating_add(cycles),
+                Some(slot) => {
+                    let mut stats = Stats::new(self.name);
+                    stats.frame = cycles;
+                    *slot = Some(stats);
+                }
+                None => (),
+            }
+        });
+    }
+}
+
+/// Ends the current frame, reporting to mGBA at the end of each interval.
+///
+/// The report is logged with interrupts enabled, so that logging does not
+/// delay the interrupts of the frames being measured.
+pub fn frame() {
+    let report = with_profiler(|profiler| {
+        for stats in profiler.scopes.iter_mut().flatten() {
+            stats.min = stats.min.min(stats.frame);
+            stats.max = stats.max.max(stats.frame);
+            stats.sum += u64::from(stats.frame);
+            stats.frame = 0;
+        }
+
+        profiler.frames += 1;
+        if profiler.frames < profiler.interval {
+            return None;
+        }
+
+        // Take the finished statistics, leaving the scopes for the next
+        // interval.
+        let scopes = profiler.scopes;
+        for stats in profiler.scopes.iter_mut().flatten() {
+            *stats = Stats::new(stats.name);
+        }
+
+        let frames = profiler.frames;
+        profiler.frames = 0;
+        Some((scopes, frames))
+    });
+
+    let Some(Some((scopes, frames))) = report else {
+        return;
+    };
+
+    mgba::info!("profile: {} frames", frames);
+    for stats in scopes.iter().flatten() {
+        let avg = (stats.sum / u64::from(frames)) as u32;
+        mgba::info!(
+            "  {}: min {} avg {} max {} cycles ({}% of frame)",
+            stats.name,
+            stats.min,
+            avg,
+            stats.max,
+            percent(avg),
+        );
+    }
+}
+
+/// Formats cycles as a percentage of a frame, to one decimal place.
+fn percent(cycles: u32) -> Percent {
+    let tenths = u64::from(cycles) * 1000 / u64::from(FRAME_CYCLES);
+    Percent(tenths as u32)
+}
+
+struct Percent(u32);
+
+impl core::fmt::Display for Percent {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}.{}", self.0 / 10, self.0 % 10)
+    }
+}
+
+fn with_profiler<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&mut Profiler) -> R,
+{
+    interrupt::free(|| unsafe { (*ptr::addr_of_mut!(PROFILER)).as_mut().map(f) })
+}
+
+/// Starts the profiler. Expands to nothing without the `profile` feature.
+#[macro_export]
+macro_rules! profile_init {
+    () => {
+        $crate::profile::init();
+    };
+}
+
+/// Measures the rest of the enclosing block as a named scope. Expands to
+/// nothing without the `profile` feature.
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::profile::Scope::enter($name);
+    };
+}
+
+/// Ends the current frame of the profiler. Expands to nothing without the
+/// `profile` feature.
+#[macro_export]
+macro_rules! profile_frame {
+    () => {
+        $crate::profile::frame();
+    };
+}
@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Raster (scanline) effects.
+//!
+//! A raster effect writes a value from a table to a register at the start of
+//! each of the 160 visible lines, for effects such as wavy backgrounds,
+//! gradients, and parallax.
+//!
+//! Effects can be driven by HBlank DMA, which has no CPU overhead but is
+//! limited to one effect, or by the HBlank interrupt, which supports several
+//! effects at the cost of an interrupt on every line.
+//!
+//! Mode 7 style effects write the matrix and reference point of an affine
+//! background on each line. The reference point registers are 32 bits, so
+//! their effects are created from tables of words with
+//! [`RasterEffect::new32()`].
+//!
+//! ```rust
+//! use gba::display::{Background, HEIGHT};
+//! use gba::raster::{self, RasterEffect, RasterMode, RasterTarget};
+//!
+//! static mut WAVE: [u16; HEIGHT] = [0; HEIGHT];
+//!
+//! let effect = RasterEffect::new(RasterTarget::HScroll(Background::Bg0), unsafe { &WAVE });
+//! raster::start(effect, RasterMode::Dma).unwrap();
+//! raster::enable_on_vblank().unwrap();
+//! ```
+//!
+//! A floor in perspective, scaling each line of BG2 and moving its reference
+//! point to match:
+//!
+//! ```rust
+//! use gba::display::{AffineBackground, HEIGHT};
+//! use gba::raster::{self, RasterEffect, RasterMode, RasterTarget};
+//!
+//! static mut SCALE: [u16; HEIGHT] = [0; HEIGHT];
+//! static mut X: [i32; HEIGHT] = [0; HEIGHT];
+//! static mut Y: [i32; HEIGHT] = [0; HEIGHT];
+//!
+//! let bg = AffineBackground::Bg2;
+//! for effect in unsafe {
+//!     [
+//!         RasterEffect::new(RasterTarget::AffinePa(bg), &SCALE),
+//!         RasterEffect::new32(RasterTarget::AffineX(bg), &X),
+//!         RasterEffect::new32(RasterTarget::AffineY(bg), &Y),
+//!     ]
+//! } {
+//!     raster::start(effect, RasterMode::Interrupt).unwrap();
+//! }
+//! ```
+
+use core::ptr;
+
+use crate::display::{AffineBackground, Background, HEIGHT};
+use crate::dma::{DestControl, Dma0, DmaControl, DmaError, Timing, TransferSize};
+use crate::interrupt::{self, HookError, Irq};
+use crate::register::{ReadOnly, Register};
+
+const VCOUNT: Register<u16, ReadOnly, 0x0400_0006> = unsafe { Register::new() };
+
+/// Writes one unit to the same register on every HBlank.
+const DMA_HBLANK_REPEAT: DmaControl =
+    DmaControl::new().with_dest(DestControl::Fixed).with_repeat(true).with_timing(Timing::HBlank);
+/// The end of internal memory, which DMA0 is limited to.
+const INTERNAL_END: usize = 0x0800_0000;
+
+/// The last line of VBlank, before line 0 is drawn.
+const LAST_LINE: u16 = 227;
+/// The maximum number of effects driven by the HBlank interrupt, enough for
+/// every parameter of an affine background.
+const MAX_IRQ_EFFECTS: usize = 8;
+
+/// The effect driven by HBlank DMA, and the channel it uses.
+static mut DMA_EFFECT: Option<(Dma0, RasterEffect)> = None;
+/// The effects driven by the HBlank interrupt.
+static mut IRQ_EFFECTS: [Option<RasterEffect>; MAX_IRQ_EFFECTS] = [None; MAX_IRQ_EFFECTS];
+
+/// The register a raster effect writes to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RasterTarget {
+    /// The horizontal scroll offset of a text background.
+    HScroll(Background),
+    /// The vertical scroll offset of a text background.
+    VScroll(Background),
+    /// The backdrop color (palette entry 0).
+    Backdrop,
+    /// The alpha blending coefficients (BLDALPHA).
+    BlendAlpha,
+    /// The brightness coefficient (BLDY).
+    Brightness,
+    /// The mosaic sizes (MOSAIC).
+    Mosaic,
+    /// The `pa` parameter of the matrix of an affine background (BGxPA).
+    AffinePa(AffineBackground),
+    /// The `pb` parameter of the matrix of an affine background (BGxPB).
+    AffinePb(AffineBackground),
+    /// The `pc` parameter of the matrix of an affine background (BGxPC).
+    AffinePc(AffineBackground),
+    /// The `pd` parameter of the matrix of an affine background (BGxPD).
+    AffinePd(AffineBackground),
+    /// The x coordinate of the reference point of an affine background
+    /// (BGxX), which is 32 bits.
+    AffineX(AffineBackground),
+    /// The y coordinate of the reference point of an affine background
+    /// (BGxY), which is 32 bits.
+    AffineY(AffineBackground),
+}
+
+impl RasterTarget {
+    const fn address(self) -> u32 {
+        match self {
+            Self::HScroll(bg) => 0x0400_0010 + 4 * bg as u32,
+            Self::VScroll(bg) => 0x0400_0012 + 4 * bg as u32,
+            Self::Backdrop => 0x0500_0000,
+            Self::BlendAlpha => 0x0400_0052,
+            Self::Brightness => 0x0400_0054,
+            Self::Mosaic => 0x0400_004C,
+            Self::AffinePa(bg) => 0x0400_0020 + 0x10 * bg as u32,
+            Self::AffinePb(bg) => 0x0400_0022 + 0x10 * bg as u32,
+            Self::AffinePc(bg) => 0x0400_0024 + 0x10 * bg as u32,
+            Self::AffinePd(bg) => 0x0400_0026 + 0x10 * bg as u32,
+            Self::AffineX(bg) => 0x0400_0028 + 0x10 * bg as u32,
+            Self::AffineY(bg) => 0x0400_002C + 0x10 * bg as u32,
+        }
+    }
+
+    /// Checks if the register is written as a 32-bit word.
+    pub const fn is_word(self) -> bool {
+        matches!(self, Self::AffineX(_) | Self::AffineY(_))
+    }
+}
+
+/// How a raster effect is driven.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum RasterMode {
+    /// HBlank DMA on channel 0, which has the highest priority. Only one
+    /// effect can use DMA at a time, and its table must be in work RAM.
+    ///
+    /// [`vblank()`] must be called every VBlank to restart the transfer.
+    #[default]
+    Dma,
+    /// The HBlank interrupt, through [`hblank()`].
+    Interrupt,
+}
+
+/// Errors from starting a raster effect.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RasterError {
+    /// An effect is already using HBlank DMA, or DMA0 has been taken.
+    DmaInUse,
+    /// The effect cannot be transferred by DMA0.
+    Dma(DmaError),
+    /// The maximum number of interrupt-driven effects are running.
+    Exhausted,
+    /// The values of the table are not the size of the register.
+    TableSize,
+}
+
+/// The values of a raster effect, one for each line.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Table {
+    Half(&'static [u16; HEIGHT]),
+    Word(&'static [i32; HEIGHT]),
+}
+
+/// A table of values written to a register, one for each line.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RasterEffect {
+    target: RasterTarget,
+    table: Table,
+}
+
+impl RasterEffect {
+    /// Creates an effect that writes `table[line]` to the target before
+    /// each line is drawn.
+    ///
+    /// The table is read while the effect runs, so it can be updated between
+    /// frames to animate the effect. The target must be a 16-bit register.
+    pub const fn new(target: RasterTarget, table: &'static [u16; HEIGHT]) -> Self {
+        Self {
+            target,
+            table: Table::Half(table),
+        }
+    }
+
+    /// Creates an effect that writes `table[line]` to a 32-bit target, such
+    /// as [`RasterTarget::AffineX`], before each line is drawn.
+    ///
+    /// Reference points are signed 20.8 fixed point; the unused upper bits
+    /// are ignored.
+    pub const fn new32(target: RasterTarget, table: &'static [i32; HEIGHT]) -> Self {
+        Self {
+            target,
+            table: Table::Word(table),
+        }
+    }
+
+    /// The register the effect writes to.
+    pub const fn target(&self) -> RasterTarget {
+        self.target
+    }
+
+    /// The address of the table.
+    fn table_address(&self) -> usize {
+        match self.table {
+            Table::Half(table) => table.as_ptr() as usize,
+            Table::Word(table) => table.as_ptr() as usize,
+        }
+    }
+
+    fn write(&self, line: usize) {
+        let address = self.target.address();
+        match self.table {
+            Table::Half(table) => {
+                if let Some(&value) = table.get(line) {
+                    unsafe {
+                        (address as *mut u16).write_volatile(value);
+                    }
+                }
+            }
+            Table::Word(table) => {
+                if let Some(&value) = table.get(line) {
+                    unsafe {
+                        (address as *mut i32).write_volatile(value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Starts a raster effect.
+///
+/// Starting an effect for a target that already has an effect replaces it.
+/// Interrupt-driven effects register [`hblank()`] as the HBlank handler.
+pub fn start(effect: RasterEffect, mode: RasterMode) -> Result<(), RasterError> {
+    if effect.target.is_word() != matches!(effect.table, Table::Word(_)) {
+        return Err(RasterError::TableSize);
+    }
+
+    stop(effect.target);
+
+    interrupt::free(|| unsafe {
+        match mode {
+            RasterMode::Dma => {
+                if effect.table_address() >= INTERNAL_END {
+                    return Err(RasterError::Dma(DmaError::InvalidAddress));
+                }
+
+                let slot = &mut *ptr::addr_of_mut!(DMA_EFFECT);
+                if slot.is_some() {
+                    return Err(RasterError::DmaInUse);
+                }
+                let dma = Dma0::take().ok_or(RasterError::DmaInUse)?;
+                *slot = Some((dma, effect));
+            }
+            RasterMode::Interrupt => {
+                let effects = &mut *ptr::addr_of_mut!(IRQ_EFFECTS);
+                let slot =
+                    effects.iter_mut().find(|e| e.is_none()).ok_or(RasterError::Exhausted)?;
+                *slot = Some(effect);
+
+                interrupt::set_handler(Irq::HBlank, Some(hblank));
+                interrupt::enable(Irq::HBlank);
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Stops the effect for the target, if any.
+///
+/// The register keeps the last value written.
+pub fn stop(target: RasterTarget) {
+    interrupt::free(|| unsafe {
+        let dma = &mut *ptr::addr_of_mut!(DMA_EFFECT);
+        if dma.as_ref().is_some_and(|(_, e)| e.target == target) {
+            // Dropping the channel stops the transfer.
+            *dma = None;
+        }
+
+        let effects = &mut *ptr::addr_of_mut!(IRQ_EFFECTS);
+        let mut removed = false;
+        for slot in effects.iter_mut() {
+            if slot.is_some_and(|e| e.target == target) {
+                *slot = None;
+                removed = true;
+            }
+        }
+
+        if removed && effects.iter().all(Option::is_none) {
+            interrupt::disable(Irq::HBlank);
+        }
+    });
+}
+
+/// Stops all raster effects.
+pub fn stop_all() {
+    interrupt::free(|| unsafe {
+        *ptr::addr_of_mut!(DMA_EFFECT) = None;
+
+        *ptr::addr_of_mut!(IRQ_EFFECTS) = [None; MAX_IRQ_EFFECTS];
+        interrupt::disable(Irq::HBlank);
+    });
+}
+
+/// Restarts the DMA-driven effect for the next frame.
+///
+/// The value for line 0 is written immediately, and the DMA writes the rest
+/// during the HBlank of the preceding line. Intended to be called from the
+/// VBlank interrupt handler.
+pub fn vblank() {
+    let Some((dma, effect)) = (unsafe { &mut *ptr::addr_of_mut!(DMA_EFFECT) }) else {
+        return;
+    };
+
+    dma.stop();
+    effect.write(0);
+
+    // The DMA also runs in the HBlank of line 159, reading one entry past the
+    // table. That value is overwritten here before the next frame is drawn.
+    let dst = effect.target.address() as *mut u16;
+    unsafe {
+        // The table was checked to be in internal memory when started.
+        let _ = match effect.table {
+            Table::Half(table) => dma.start(table[1..].as_ptr(), dst, 1, DMA_HBLANK_REPEAT),
+            Table::Word(table) => {
+                let control = DMA_HBLANK_REPEAT.with_size(TransferSize::Word);
+                dma.start(table[1..].as_ptr(), dst, 1, control)
+            }
+        };
+    }
+}
+
+/// Restarts the DMA-driven effect during every VBlank.
+///
+/// [`vblank()`] is added as a VBlank hook with
+/// [`interrupt::add_vblank_hook()`], so it runs alongside the game's own
+/// VBlank handler.
+pub fn enable_on_vblank() -> Result<(), HookError> {
+    interrupt::add_vblank_hook(vblank)
+}
+
+/// Writes the values for the next line of the interrupt-driven effects.
+///
+/// Registered as the HBlank handler by [`start()`].
+pub fn hblank() {
+    // HBlank also occurs during VBlank; the last line of VBlank sets up line 0.
+    let line = VCOUNT.read();
+    let next = if line >= LAST_LINE {
+        0
+    } else {
+        line + 1
+    };
+
+    for effect in unsafe { (*ptr::addr_of!(IRQ_EFFECTS)).iter().flatten() } {
+        effect.write(usize::from(next));
+    }
+}
+
+/// Calls `handler` when the display reaches `line`, for split-screen changes.
+///
+/// The interrupt is requested when VCOUNT reaches `line`, at the start of the
+/// line and before its HDraw, so the line is already being drawn by the time
+/// the handler runs. Changes made by the handler may appear partway across
+/// `line`, and only take effect for whole lines from the one after it. For a
+/// clean split at a line, trigger on the line before it. Pass `None` to
+/// disable the trigger.
+pub fn on_vcount(line: u8, handler: Option<fn()>) {
+    interrupt::set_handler(Irq::VCount, handler);
+
+    if handler.is_some() {
+        interrupt::set_vcount(line);
+        interrupt::enable(Irq::VCount);
+    } else {
+        interrupt::disable(Irq::VCount);
+    }
+}
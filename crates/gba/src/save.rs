@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Save memory.
+//!
+//! Cartridges keep saves in battery-backed SRAM, Flash, or EEPROM, which is
+//! mapped at 0x0E00_0000 with an 8-bit bus. The type is not reported by the
+//! hardware, so each driver emits a marker string into the ROM when it is
+//! used, which emulators and flash carts search for to pick the save type.
+//! A game should only use one driver.
+//!
+//! The access time of save memory and the ROM is set by WAITCNT, with
+//! [`WaitControl`].
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbacartbackupidstrings>
+
+pub mod flash;
+pub mod sram;
+
+pub use gba_portable::save::SaveError;
+
+use crate::register::{ReadWrite, Register};
+
+const WAITCNT: Register<WaitControl, ReadWrite, 0x0400_0204> = unsafe { Register::new() };
+
+/// A save type string in the ROM, such as `SRAM_V113`.
+///
+/// The string must be word aligned and is padded to a multiple of 4 bytes.
+#[repr(C, align(4))]
+struct Marker([u8; 12]);
+
+/// The number of cycles for an access to save memory or the ROM.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Wait {
+    #[default]
+    Cycles4 = 0,
+    Cycles3 = 1,
+    Cycles2 = 2,
+    Cycles8 = 3,
+}
+
+/// A ROM region, each of which has its own wait states.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum WaitRegion {
+    /// 0x0800_0000-0x09FF_FFFF
+    Ws0 = 0,
+    /// 0x0A00_0000-0x0BFF_FFFF
+    Ws1 = 1,
+    /// 0x0C00_0000-0x0DFF_FFFF
+    Ws2 = 2,
+}
+
+/// The memory access settings, from WAITCNT.
+///
+/// Settings are built up and then applied with [`set()`](Self::set).
+///
+/// ```rust
+/// use gba::save::{Wait, WaitControl, WaitRegion};
+///
+/// // The settings used by most commercial games.
+/// WaitControl::new()
+///     .with_sram_wait(Wait::Cycles8)
+///     .with_rom_wait(WaitRegion::Ws0, Wait::Cycles3, true)
+///     .with_prefetch(true)
+///     .set();
+/// ```
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct WaitControl(u16);
+
+impl WaitControl {
+    const WAIT_MASK: u16 = 0b11;
+    const ROM_SHIFT: u16 = 2;
+    const SEQUENTIAL_SHIFT: u16 = 4;
+    /// The distance between the settings of each ROM region.
+    const REGION_STRIDE: u16 = 3;
+    const PREFETCH: u16 = 1 << 14;
+
+    /// Returns settings with the slowest access times and no prefetch, as
+    /// set by the BIOS.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current memory access settings.
+    pub fn get() -> Self {
+        WAITCNT.read()
+    }
+
+    /// Applies the memory access settings.
+    pub fn set(self) {
+        WAITCNT.write(self);
+    }
+
+    /// The access time of save memory.
+    pub const fn sram_wait(self) -> Wait {
+        Self::wait(self.0)
+    }
+
+    /// Sets the access time of save memory.
+    ///
+    /// Most save memory requires [`Wait::Cycles8`].
+    pub const fn with_sram_wait(self, wait: Wait) -> Self {
+        Self((self.0 & !Self::WAIT_MASK) | wait as u16)
+    }
+
+    /// The access time of the first access to a ROM region, and if
+    /// sequential accesses are fast.
+    pub const fn rom_wait(self, region: WaitRegion) -> (Wait, bool) {
+        let shift = Self::ROM_SHIFT + Self::region_shift(region);
+        let sequential = 1 << (Self::SEQUENTIAL_SHIFT + Self::region_shift(region));
+        (Self::wait(self.0 >> shift), (self.0 & sequential) != 0)
+    }
+
+    /// Sets the access time of the first access to a ROM region, and if
+    /// sequential accesses are fast.
+    ///
+    /// Fast sequential accesses take 1 cycle; otherwise they take 2, 4, or 8
+    /// cycles for WS0, WS1, or WS2.
+    pub const fn with_rom_wait(self, region: WaitRegion, first: Wait, fast: bool) -> Self {
+        let shift = Self::ROM_SHIFT + Self::region_shift(region);
+        let sequential = 1 << (Self::SEQUENTIAL_SHIFT + Self::region_shift(region));
+        Self((self.0 & !(Self::WAIT_MASK << shift)) | ((first as u16) << shift))
+            .with_flag(sequential, fast)
+    }
+
+    /// Checks if the ROM is prefetched while the CPU is not using the bus.
+    pub const fn prefetch(self) -> bool {
+        (self.0 & Self::PREFETCH) != 0
+    }
+
+    /// Sets if the ROM is prefetched while the CPU is not using the bus.
+    pub const fn with_prefetch(self, enabled: bool) -> Self {
+        self.with_flag(Self::PREFETCH, enabled)
+    }
+
+    const fn wait(value: u16) -> Wait {
+        match value & Self::WAIT_MASK {
+            0 => Wait::Cycles4,
+            1 => Wait::Cycles3,
+            2 => Wait::Cycles2,
+            _ => Wait::Cycles8,
+        }
+    }
+
+    const fn region_shift(region: WaitRegion) -> u16 {
+        region as u16 * Self::REGION_STRIDE
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for WaitControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<WaitControl> for u16 {
+    fn from(value: WaitControl) -> Self {
+        value.0
+    }
+}
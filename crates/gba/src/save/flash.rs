@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Flash save memory.
+//!
+//! Flash is 64 KiB or 128 KiB of memory that is read directly, but written
+//! and erased with command sequences. Bits can only be cleared by writing, so
+//! a sector must be erased (set to 0xFF) before it is written again, except
+//! on Atmel chips, which erase each 128-byte page as it is written. 128 KiB
+//! chips are accessed as two banks of 64 KiB.
+//!
+//! The chip is identified by its manufacturer and device IDs. Games expecting
+//! 64 KiB use [`new_64k()`], which emits the `FLASH_V126` marker into the ROM,
+//! and games expecting 128 KiB use [`new_128k()`], which emits
+//! `FLASH1M_V103`.
+//!
+//! The driver accesses the chip through a [`Bus`], so save code can be run
+//! on the host against the `Simulator` of `gba-portable`, enabled by its
+//! `simulator` feature.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbacartbackupflashrom>
+//!
+//! ```rust
+//! use gba::save::flash;
+//!
+//! let mut flash = flash::new_64k().unwrap();
+//!
+//! flash.erase_sector(0).unwrap();
+//! flash.write(0, b"SAVE").unwrap();
+//!
+//! let mut header = [0; 4];
+//! flash.read(0, &mut header).unwrap();
+//! ```
+
+use core::hint;
+
+pub use gba_portable::save::flash::{Bus, Chip, Manufacturer, BANK_SIZE};
+
+use super::{Marker, SaveError, Wait, WaitControl};
+use crate::interrupt;
+use crate::register::{ReadOnly, Register};
+
+const VCOUNT: Register<u16, ReadOnly, 0x0400_0006> = unsafe { Register::new() };
+
+/// The memory-mapped address of Flash.
+const FLASH: *mut u8 = 0x0E00_0000 as *mut u8;
+
+static MARKER_64K: Marker = Marker(*b"FLASH_V126\0\0");
+static MARKER_128K: Marker = Marker(*b"FLASH1M_V103");
+
+/// A Flash chip driver, for the cartridge chip by default.
+pub type Flash<B = Hardware> = gba_portable::save::flash::Flash<B>;
+
+/// The Flash chip of the cartridge.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Hardware;
+
+impl Bus for Hardware {
+    fn read(&mut self, offset: usize) -> u8 {
+        unsafe { FLASH.add(offset).read_volatile() }
+    }
+
+    fn write(&mut self, offset: usize, value: u8) {
+        unsafe {
+            FLASH.add(offset).write_volatile(value);
+        }
+    }
+
+    fn scanline(&mut self) -> u16 {
+        VCOUNT.read()
+    }
+
+    fn critical<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        interrupt::free(|| f(self));
+    }
+}
+
+/// Detects the cartridge Flash chip, for a game that uses 64 KiB.
+///
+/// Emits the `FLASH_V126` marker. Only the first bank of a 128 KiB chip is
+/// used.
+pub fn new_64k() -> Result<Flash, SaveError> {
+    hint::black_box(&MARKER_64K);
+    init(BANK_SIZE)
+}
+
+/// Detects the cartridge Flash chip, for a game that uses 128 KiB.
+///
+/// Emits the `FLASH1M_V103` marker. Returns an error if the chip is only
+/// 64 KiB.
+pub fn new_128k() -> Result<Flash, SaveError> {
+    hint::black_box(&MARKER_128K);
+    init(2 * BANK_SIZE)
+}
+
+fn init(size: usize) -> Result<Flash, SaveError> {
+    WaitControl::get().with_sram_wait(Wait::Cycles8).set();
+    Flash::detect(Hardware)?.with_size(size)
+}
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2022 Tim Crawford <crawfxrd@gmail.com>
+
+//! Battery-backed SRAM.
+//!
+//! SRAM is 32 KiB of memory that can be read and written directly, but only
+//! a byte at a time. Using this module emits the `SRAM_V113` marker into the
+//! ROM.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbacartbackupsramfram>
+//!
+//! ```rust
+//! use gba::save::sram::{self, Sram};
+//!
+//! sram::init();
+//!
+//! let mut save = Sram::new();
+//! save.write_all(b"SAVE").unwrap();
+//! save.write_all(&[1, 2, 3]).unwrap();
+//!
+//! let mut header = [0; 4];
+//! save.seek(0);
+//! save.read_exact(&mut header).unwrap();
+//! ```
+
+use core::hint;
+
+use super::{Marker, SaveError, Wait, WaitControl};
+
+/// The memory-mapped address of SRAM.
+const SRAM: *mut u8 = 0x0E00_0000 as *mut u8;
+
+/// The size of SRAM.
+pub const SIZE: usize = 0x8000;
+
+static MARKER: Marker = Marker(*b"SRAM_V113\0\0\0");
+
+/// Keeps the marker in the ROM when SRAM is used.
+fn mark() {
+    hint::black_box(&MARKER);
+}
+
+/// Checks that `len` bytes at `offset` are within SRAM.
+fn check(offset: usize, len: usize) -> Result<(), SaveError> {
+    match offset.checked_add(len) {
+        Some(end) if end <= SIZE => Ok(()),
+        _ => Err(SaveError::OutOfRange),
+    }
+}
+
+/// Sets the access time of SRAM, keeping the ROM settings.
+///
+/// Should be called before accessing SRAM.
+pub fn init() {
+    mark();
+    WaitControl::get().with_sram_wait(Wait::Cycles8).set();
+}
+
+/// Reads the bytes at `offset` into `buf`.
+pub fn read(offset: usize, buf: &mut [u8]) -> Result<(), SaveError> {
+    mark();
+    check(offset, buf.len())?;
+
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = unsafe { SRAM.add(offset + i).read_volatile() };
+    }
+
+    Ok(())
+}
+
+/// Writes `data` at `offset`.
+pub fn write(offset: usize, data: &[u8]) -> Result<(), SaveError> {
+    mark();
+    check(offset, data.len())?;
+
+    for (i, &byte) in data.iter().enumerate() {
+        unsafe {
+            SRAM.add(offset + i).write_volatile(byte);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks if the bytes at `offset` match `data`.
+pub fn verify(offset: usize, data: &[u8]) -> Result<bool, SaveError> {
+    mark();
+    check(offset, data.len())?;
+
+    let matches = data
+        .iter()
+        .enumerate()
+        .all(|(i, &byte)| unsafe { SRAM.add(offset + i).read_volatile() } == byte);
+
+    Ok(matches)
+}
+
+/// A cursor over SRAM, for reading and writing in sequence.
+#[derive(Debug)]
+pub struct Sram {
+    position: usize,
+}
+
+impl Sram {
+    /// Returns a cursor at the start of SRAM.
+    pub fn new() -> Self {
+        mark();
+        Self { position: 0 }
+    }
+
+    /// The offset of the next access.
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves the cursor to `offset`, which may be past the end of SRAM.
+    pub fn seek(&mut self, offset: usize) {
+        self.position = offset;
+    }
+
+    /// Reads into `buf` up to the end of SRAM, returning the number of bytes
+    /// read.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(SIZE.saturating_sub(self.position));
+        if read(self.position, &mut buf[..len]).is_err() {
+            return 0;
+        }
+
+        self.position += len;
+        len
+    }
+
+    /// Fills `buf`, or returns an error without reading if SRAM ends first.
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), SaveError> {
+        read(self.position, buf)?;
+        self.position += buf.len();
+        Ok(())
+    }
+
+    /// Writes `data` up to the end of SRAM, returning the number of bytes
+    /// written.
+    pub fn write(&mut self, data: &[u8]) -> usize {
+        let len = data.len().min(SIZE.saturating_sub(self.position));
+        if write(self.position, &data[..len]).is_err() {
+            return 0;
+        }
+
+        self.position += len;
+        len
+    }
+
+    /// Writes all of `data`, or returns an error without writing if SRAM
+    /// ends first.
+    pub fn write_all(&mut self, data: &[u8]) -> Result<(), SaveError> {
+        write(self.position, data)?;
+        self.position += data.len();
+        Ok(())
+    }
+}
+
+impl Default for Sram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
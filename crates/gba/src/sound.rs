@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Sound.
+//!
+//! The GBA has two Direct Sound channels, which play 8-bit signed PCM
+//! samples from a FIFO, and four PSG channels compatible with the Game Boy.
+//! All sound is disabled until the master enable is set with [`enable()`].
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbasoundcontroller>
+
+pub mod direct;
+pub mod mixer;
+pub mod psg;
+pub mod tracker;
+
+use crate::register::{ReadWrite, Register};
+
+const SOUNDCNT_X: Register<u16, ReadWrite, 0x0400_0084> = unsafe { Register::new() };
+
+/// Enables the sound circuits.
+///
+/// Must be enabled before any other sound registers are written.
+pub fn enable() {
+    SOUNDCNT_X.write(1 << 7);
+}
+
+/// Disables the sound circuits, saving power.
+///
+/// The PSG registers are reset.
+pub fn disable() {
+    SOUNDCNT_X.write(0);
+}
+
+/// Checks if the sound circuits are enabled.
+pub fn is_enabled() -> bool {
+    (SOUNDCNT_X.read() & (1 << 7)) != 0
+}
@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Direct Sound.
+//!
+//! Each Direct Sound channel plays 8-bit signed samples from a 32-byte FIFO.
+//! A sample is taken from the FIFO each time the selected timer (0 or 1)
+//! overflows, and DMA1 (FIFO A) or DMA2 (FIFO B) refills it with 16 samples
+//! when it runs low.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbasoundchannelaandbdmasound>
+//!
+//! ```rust
+//! use gba::dma::Dma1;
+//! use gba::sound::direct::{PcmChannel, SoundTimer};
+//! use gba::sound::{self, direct};
+//! use gba::timer::Timer0;
+//!
+//! static JUMP: [i8; 4096] = [0; 4096];
+//!
+//! sound::enable();
+//!
+//! let mut timer = Timer0::take().unwrap();
+//! direct::start_clock(&mut timer, direct::RATE_18K);
+//!
+//! let mut channel = PcmChannel::new_a(Dma1::take().unwrap(), SoundTimer::Timer0);
+//! channel.play(&JUMP, false).unwrap();
+//! ```
+
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+use super::psg::PsgVolume;
+use crate::dma::{Channel, DestControl, Dma1, Dma2, DmaControl, DmaError, Timing, TransferSize};
+use crate::interrupt::{self, Irq};
+use crate::register::{ReadWrite, Register};
+use crate::timer::{Prescaler, Timer, TimerControl, CPU_FREQUENCY};
+
+const SOUNDCNT_H: Register<DirectSoundControl, ReadWrite, 0x0400_0082> = unsafe { Register::new() };
+
+/// The number of samples the DMA writes to a FIFO on each request.
+const SAMPLES_PER_REQUEST: u32 = 16;
+
+/// 10512 Hz; 176 samples per frame.
+pub const RATE_10K: u32 = 10512;
+/// 13379 Hz; 224 samples per frame.
+pub const RATE_13K: u32 = 13379;
+/// 18157 Hz; 304 samples per frame.
+pub const RATE_18K: u32 = 18157;
+/// 21024 Hz; 352 samples per frame.
+pub const RATE_21K: u32 = 21024;
+/// 26758 Hz; 448 samples per frame.
+pub const RATE_26K: u32 = 26758;
+/// 31536 Hz; 528 samples per frame.
+pub const RATE_31K: u32 = 31536;
+
+/// Playback state of each FIFO, updated by the DMA interrupt.
+#[derive(Clone, Copy)]
+struct Playback {
+    sample: &'static [i8],
+    /// Samples left to be transferred
+    remaining: u32,
+    looping: bool,
+    playing: bool,
+    callback: Option<fn()>,
+}
+
+impl Playback {
+    const STOPPED: Self = Self {
+        sample: &[],
+        remaining: 0,
+        looping: false,
+        playing: false,
+        callback: None,
+    };
+}
+
+static mut PLAYBACK: [Playback; 2] = [Playback::STOPPED; 2];
+
+/// A Direct Sound FIFO.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Fifo {
+    A = 0,
+    B = 1,
+}
+
+impl Fifo {
+    /// The memory-mapped address of the FIFO.
+    pub const fn address(self) -> u32 {
+        match self {
+            Self::A => 0x0400_00A0,
+            Self::B => 0x0400_00A4,
+        }
+    }
+
+    /// Clears the samples in the FIFO.
+    pub fn reset(self) {
+        DirectSoundControl::get().with_reset(self).set();
+    }
+}
+
+/// The output volume of a Direct Sound channel.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum DirectVolume {
+    Half = 0,
+    #[default]
+    Full = 1,
+}
+
+/// The timer that clocks a Direct Sound channel.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SoundTimer {
+    #[default]
+    Timer0 = 0,
+    Timer1 = 1,
+}
+
+/// The Direct Sound settings, from SOUNDCNT_H.
+///
+/// Settings are built up and then applied with [`set()`](Self::set).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct DirectSoundControl(u16);
+
+impl DirectSoundControl {
+    const PSG_VOLUME_MASK: u16 = 0b11;
+    const VOLUME_SHIFT: u16 = 2;
+    const RIGHT_SHIFT: u16 = 8;
+    const LEFT_SHIFT: u16 = 9;
+    const TIMER_SHIFT: u16 = 10;
+    const RESET_SHIFT: u16 = 11;
+    /// The distance between the settings of FIFO A and FIFO B.
+    const FIFO_STRIDE: u16 = 4;
+
+    /// Returns settings with both channels at half volume and not output, and
+    /// the PSG channels at quarter volume.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current Direct Sound settings.
+    pub fn get() -> Self {
+        // The reset bits always read as 0.
+        SOUNDCNT_H.read()
+    }
+
+    /// Applies the Direct Sound settings.
+    pub fn set(self) {
+        SOUNDCNT_H.write(self);
+    }
+
+    /// The volume of the PSG channels.
+    pub const fn psg_volume(self) -> PsgVolume {
+        match self.0 & Self::PSG_VOLUME_MASK {
+            0 => PsgVolume::Quarter,
+            1 => PsgVolume::Half,
+            _ => PsgVolume::Full,
+        }
+    }
+
+    /// Sets the volume of the PSG channels.
+    pub const fn with_psg_volume(self, volume: PsgVolume) -> Self {
+        Self((self.0 & !Self::PSG_VOLUME_MASK) | volume as u16)
+    }
+
+    /// The volume of the channel.
+    pub const fn volume(self, fifo: Fifo) -> DirectVolume {
+        if (self.0 & (1 << (Self::VOLUME_SHIFT + fifo as u16))) != 0 {
+            DirectVolume::Full
+        } else {
+            DirectVolume::Half
+        }
+    }
+
+    /// Sets the volume of the channel.
+    pub const fn with_volume(self, fifo: Fifo, volume: DirectVolume) -> Self {
+        let flag = 1 << (Self::VOLUME_SHIFT + fifo as u16);
+        self.with_flag(flag, matches!(volume, DirectVolume::Full))
+    }
+
+    /// Returns if the channel is output to the left and right speakers.
+    pub const fn output(self, fifo: Fifo) -> (bool, bool) {
+        let left = 1 << (Self::LEFT_SHIFT + Self::fifo_shift(fifo));
+        let right = 1 << (Self::RIGHT_SHIFT + Self::fifo_shift(fifo));
+        ((self.0 & left) != 0, (self.0 & right) != 0)
+    }
+
+    /// Sets if the channel is output to the left and right speakers.
+    pub const fn with_output(self, fifo: Fifo, left: bool, right: bool) -> Self {
+        let shift = Self::fifo_shift(fifo);
+        self.with_flag(1 << (Self::LEFT_SHIFT + shift), left)
+            .with_flag(1 << (Self::RIGHT_SHIFT + shift), right)
+    }
+
+    /// The timer that clocks the channel.
+    pub const fn timer(self, fifo: Fifo) -> SoundTimer {
+        if (self.0 & (1 << (Self::TIMER_SHIFT + Self::fifo_shift(fifo)))) != 0 {
+            SoundTimer::Timer1
+        } else {
+            SoundTimer::Timer0
+        }
+    }
+
+    /// Sets the timer that clocks the channel.
+    pub const fn with_timer(self, fifo: Fifo, timer: SoundTimer) -> Self {
+        let flag = 1 << (Self::TIMER_SHIFT + Self::fifo_shift(fifo));
+        self.with_flag(flag, matches!(timer, SoundTimer::Timer1))
+    }
+
+    /// Clears the FIFO when the settings are applied.
+    pub const fn with_reset(self, fifo: Fifo) -> Self {
+        self.with_flag(1 << (Self::RESET_SHIFT + Self::fifo_shift(fifo)), true)
+    }
+
+    const fn fifo_shift(fifo: Fifo) -> u16 {
+        fifo as u16 * Self::FIFO_STRIDE
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for DirectSoundControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DirectSoundControl> for u16 {
+    fn from(value: DirectSoundControl) -> Self {
+        value.0
+    }
+}
+
+/// Returns the timer reload value for the sample rate, in Hz.
+pub const fn reload_for_rate(rate: u32) -> u16 {
+    let cycles = CPU_FREQUENCY / rate;
+    (0x1_0000 - cycles) as u16
+}
+
+/// Returns the exact sample rate, in Hz, of a timer reload value.
+pub const fn rate_for_reload(reload: u16) -> u32 {
+    CPU_FREQUENCY / (0x1_0000 - reload as u32)
+}
+
+/// Starts a timer as the sample clock for Direct Sound.
+///
+/// Only timers 0 and 1 can clock the FIFOs.
+pub fn start_clock<const N: usize>(timer: &mut Timer<N>, rate: u32) {
+    timer.set_reload(reload_for_rate(rate));
+    timer.start(TimerControl::new().with_prescaler(Prescaler::Div1));
+}
+
+/// Plays PCM samples on a Direct Sound channel.
+///
+/// Channels are created from DMA1 for FIFO A or DMA2 for FIFO B. The sample
+/// clock must be started separately with [`start_clock()`], and sound must
+/// be [enabled](super::enable).
+///
+/// Dropping the channel stops playback and removes its interrupt handler.
+#[derive(Debug)]
+pub struct PcmChannel<const D: usize> {
+    dma: Channel<D>,
+    fifo: Fifo,
+}
+
+impl PcmChannel<1> {
+    /// Uses DMA1 to play on FIFO A, at full volume on both speakers.
+    pub fn new_a(dma: Dma1, timer: SoundTimer) -> Self {
+        Self::new(dma, Fifo::A, timer)
+    }
+}
+
+impl PcmChannel<2> {
+    /// Uses DMA2 to play on FIFO B, at full volume on both speakers.
+    pub fn new_b(dma: Dma2, timer: SoundTimer) -> Self {
+        Self::new(dma, Fifo::B, timer)
+    }
+}
+
+impl<const D: usize> PcmChannel<D> {
+    /// The interrupt requested by the DMA channel.
+    const IRQ: Irq = if D == 1 {
+        Irq::Dma1
+    } else {
+        Irq::Dma2
+    };
+
+    fn new(dma: Channel<D>, fifo: Fifo, timer: SoundTimer) -> Self {
+        DirectSoundControl::get()
+            .with_volume(fifo, DirectVolume::Full)
+            .with_output(fifo, true, true)
+            .with_timer(fifo, timer)
+            .with_reset(fifo)
+            .set();
+
+        interrupt::set_handler(Self::IRQ, Some(dma_irq::<D>));
+        interrupt::enable(Self::IRQ);
+
+        Self { dma, fifo }
+    }
+
+    /// The FIFO the channel plays on.
+    pub const fn fifo(&self) -> Fifo {
+        self.fifo
+    }
+
+    /// Plays the samples, replacing any that are playing.
+    ///
+    /// The samples must be 4-byte aligned. Playback ends within 16 samples of
+    /// the end, so a short run of silence at the end of the samples avoids
+    /// noise from the data that follows.
+    pub fn play(&mut self, sample: &'static [i8], looping: bool) -> Result<(), DmaError> {
+        self.stop();
+
+        interrupt::free(|| unsafe {
+            let playback = &mut *ptr::addr_of_mut!(PLAYBACK[self.fifo as usize]);
+            playback.sample = sample;
+            playback.remaining = sample.len() as u32;
+            playback.looping = looping;
+            playback.playing = !sample.is_empty();
+        });
+
+        if sample.is_empty() {
+            return Ok(());
+        }
+
+        unsafe { start(&mut self.dma, self.fifo, sample) }
+    }
+
+    /// Streams from a buffer that is refilled by the caller, such as the
+    /// [`Mixer`](super::mixer::Mixer), with no end-of-sample handling.
+    ///
+    /// Restarting the stream with a new buffer keeps the samples already in
+    /// the FIFO, for gapless double buffering.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be 4-byte aligned and remain valid until the stream is
+    /// restarted or stopped, and must be refilled before the DMA reaches its
+    /// end.
+    pub unsafe fn stream(&mut self, buffer: *const i8) -> Result<(), DmaError> {
+        let control = fifo_control().with_irq(false);
+        self.dma.start(buffer, self.fifo.address() as *mut u32, 4, control)
+    }
+
+    /// Stops playback and clears the FIFO.
+    pub fn stop(&mut self) {
+        self.dma.stop();
+        self.fifo.reset();
+
+        interrupt::free(|| unsafe {
+            ptr::addr_of_mut!(PLAYBACK[self.fifo as usize].playing).write(false);
+        });
+    }
+
+    /// Checks if samples are playing.
+    pub fn is_playing(&self) -> bool {
+        unsafe { ptr::addr_of!(PLAYBACK[self.fifo as usize].playing).read_volatile() }
+    }
+
+    /// Sets a function to call each time the end of the samples is reached,
+    /// including each loop.
+    ///
+    /// Called from the DMA interrupt handler.
+    pub fn set_callback(&mut self, callback: Option<fn()>) {
+        interrupt::free(|| unsafe {
+            ptr::addr_of_mut!(PLAYBACK[self.fifo as usize].callback).write(callback);
+        });
+    }
+
+    /// Stops playback and releases the DMA channel.
+    pub fn into_inner(self) -> Channel<D> {
+        let mut channel = ManuallyDrop::new(self);
+        channel.release();
+        // The channel is not dropped, so the DMA channel is moved out once.
+        unsafe { ptr::read(ptr::addr_of!(channel.dma)) }
+    }
+
+    /// Stops playback, removes the interrupt handler, and clears the
+    /// playback state.
+    fn release(&mut self) {
+        self.stop();
+        interrupt::disable(Self::IRQ);
+        interrupt::set_handler(Self::IRQ, None);
+
+        interrupt::free(|| unsafe {
+            *ptr::addr_of_mut!(PLAYBACK[self.fifo as usize]) = Playback::STOPPED;
+        });
+    }
+}
+
+impl<const D: usize> Drop for PcmChannel<D> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Word transfers to a fixed FIFO, on FIFO requests.
+const fn fifo_control() -> DmaControl {
+    DmaControl::new()
+        .with_dest(DestControl::Fixed)
+        .with_size(TransferSize::Word)
+        .with_repeat(true)
+        .with_timing(Timing::Special)
+        .with_irq(true)
+}
+
+/// Starts the FIFO transfer of the samples.
+unsafe fn start<const D: usize>(
+    dma: &mut Channel<D>,
+    fifo: Fifo,
+    sample: &[i8],
+) -> Result<(), DmaError> {
+    // The word count is ignored in FIFO mode; 4 words are sent per request.
+    dma.start(sample.as_ptr(), fifo.address() as *mut u32, 4, fifo_control())
+}
+
+/// Tracks the samples sent by DMA `D`, stopping or looping at the end.
+fn dma_irq<const D: usize>() {
+    let fifo = if D == 1 {
+        Fifo::A
+    } else {
+        Fifo::B
+    };
+    let playback = unsafe { &mut *ptr::addr_of_mut!(PLAYBACK[fifo as usize]) };
+    if !playback.playing {
+        return;
+    }
+
+    playback.remaining = playback.remaining.saturating_sub(SAMPLES_PER_REQUEST);
+    if playback.remaining > 0 {
+        return;
+    }
+
+    let mut dma = unsafe { Channel::<D>::steal() };
+    if playback.looping {
+        playback.remaining = playback.sample.len() as u32;
+        // The sample was already accepted when playback started.
+        let _ = unsafe { start(&mut dma, fifo, playback.sample) };
+    } else {
+        dma.stop();
+        playback.playing = false;
+    }
+
+    if let Some(callback) = playback.callback {
+        callback();
+    }
+}
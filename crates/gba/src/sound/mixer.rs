@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Software mixer.
+//!
+//! Mixes up to [`MAX_VOICES`] voices into stereo output, with FIFO A as the
+//! left channel and FIFO B as the right. Each frame's samples are mixed into
+//! one half of a double buffer while the Direct Sound DMA plays the other.
+//!
+//! [`Mixer::frame()`] must be called at the start of every VBlank. Sample
+//! rates must give a whole number of samples per frame that is a multiple of
+//! 16, such as the `RATE_*` constants in [`direct`](super::direct).
+//!
+//! The inner loop is ARM code in IWRAM. [`mix_stereo_reference()`] is a
+//! portable implementation of the same loop, for checking its output.
+//!
+//! ```rust
+//! use gba::dma::{Dma1, Dma2};
+//! use gba::sound::direct::RATE_18K;
+//! use gba::sound::mixer::{self, Mixer, Sound};
+//! use gba::timer::Timer0;
+//!
+//! static MUSIC: [i8; 8192] = [0; 8192];
+//!
+//! gba::sound::enable();
+//!
+//! let dma = (Dma1::take().unwrap(), Dma2::take().unwrap());
+//! let mut mixer = Mixer::new(dma.0, dma.1, Timer0::take().unwrap(), RATE_18K).unwrap();
+//! let music = mixer.play(Sound::new(&MUSIC, RATE_18K).with_loop(0)).unwrap();
+//! if let Some(voice) = mixer.voice_mut(&music) {
+//!     voice.set_volume(32);
+//! }
+//!
+//! mixer::install(mixer).unwrap();
+//! ```
+
+use core::ptr;
+
+use gba_portable::sound::mixer::clip;
+pub use gba_portable::sound::mixer::{
+    mix_stereo_reference,
+    Sound,
+    Voice,
+    VoiceId,
+    Voices,
+    MAX_VOICES,
+    MAX_VOLUME,
+    PAN_CENTER,
+};
+
+use super::direct::{DirectSoundControl, Fifo, PcmChannel, SoundTimer};
+use crate::dma::{Dma1, Dma2, DmaError};
+use crate::interrupt::{self, HookError};
+use crate::timer::{Timer0, CPU_FREQUENCY, FRAME_CYCLES};
+
+/// The most samples that can be mixed per frame, at 31536 Hz.
+const MAX_FRAME_SAMPLES: usize = 528;
+
+extern "C" {
+    /// The ARM inner loop; see `mixer.S`.
+    fn gba_mix_stereo(
+        acc: *mut [i32; 2],
+        count: usize,
+        sample: *const i8,
+        pos: u32,
+        step: u32,
+        lvol: i32,
+        rvol: i32,
+    ) -> u32;
+}
+
+/// The output buffers, played by DMA.
+#[repr(C, align(4))]
+struct Buffers([[[i8; MAX_FRAME_SAMPLES]; 2]; 2]);
+
+/// Output samples, indexed by half and then channel (left, right).
+static mut BUFFERS: Buffers = Buffers([[[0; MAX_FRAME_SAMPLES]; 2]; 2]);
+/// The mixing accumulators, one left/right pair per output sample.
+static mut ACCUMULATOR: [[i32; 2]; MAX_FRAME_SAMPLES] = [[0; 2]; MAX_FRAME_SAMPLES];
+/// The mixer run by [`install()`].
+static mut MIXER: Option<Mixer> = None;
+
+/// Errors from creating a mixer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MixerError {
+    /// The sample rate does not give a supported number of samples per
+    /// frame.
+    InvalidRate,
+    /// The output could not be started.
+    Dma(DmaError),
+}
+
+/// Mixes voices into Direct Sound output.
+#[derive(Debug)]
+pub struct Mixer {
+    left: PcmChannel<1>,
+    right: PcmChannel<2>,
+    _timer: Timer0,
+    samples: usize,
+    /// The half of the buffers being played
+    playing: usize,
+    voices: Voices,
+}
+
+impl Mixer {
+    /// Creates a mixer with output at `rate` Hz, clocked by timer 0.
+    ///
+    /// Sound must be [enabled](super::enable).
+    pub fn new(left: Dma1, right: Dma2, mut timer: Timer0, rate: u32) -> Result<Self, MixerError> {
+        // Only exact rates keep the buffers in step with VBlank.
+        let cycles = CPU_FREQUENCY.checked_div(rate).unwrap_or(0);
+        if cycles == 0 || FRAME_CYCLES % cycles != 0 {
+            return Err(MixerError::InvalidRate);
+        }
+        let samples = (FRAME_CYCLES / cycles) as usize;
+        if samples % 16 != 0 || samples > MAX_FRAME_SAMPLES {
+            return Err(MixerError::InvalidRate);
+        }
+
+        let mut left = PcmChannel::new_a(left, SoundTimer::Timer0);
+        let mut right = PcmChannel::new_b(right, SoundTimer::Timer0);
+        DirectSoundControl::get()
+            .with_output(Fifo::A, true, false)
+            .with_output(Fifo::B, false, true)
+            .set();
+
+        unsafe {
+            let buffers = &mut *ptr::addr_of_mut!(BUFFERS);
+            *buffers = Buffers([[[0; MAX_FRAME_SAMPLES]; 2]; 2]);
+            left.stream(buffers.0[0][0].as_ptr()).map_err(MixerError::Dma)?;
+            right.stream(buffers.0[0][1].as_ptr()).map_err(MixerError::Dma)?;
+        }
+        super::direct::start_clock(&mut timer, rate);
+
+        Ok(Self {
+            left,
+            right,
+            _timer: timer,
+            samples,
+            playing: 0,
+            voices: Voices::new(rate),
+        })
+    }
+
+    /// The output sample rate, in Hz.
+    pub const fn rate(&self) -> u32 {
+        self.voices.rate()
+    }
+
+    /// Starts playing a sound at full volume, centered, and at its original
+    /// pitch.
+    ///
+    /// Returns `None` if all voices are in use.
+    pub fn play(&mut self, sound: Sound) -> Option<VoiceId> {
+        self.voices.play(sound)
+    }
+
+    /// Stops a voice.
+    pub fn stop(&mut self, id: VoiceId) {
+        self.voices.stop(id);
+    }
+
+    /// Checks if a voice is still playing.
+    pub fn is_playing(&self, id: &VoiceId) -> bool {
+        self.voices.is_playing(id)
+    }
+
+    /// Returns the state of a voice, to change its volume, pan, or pitch.
+    ///
+    /// Returns `None` if the voice has ended.
+    pub fn voice_mut(&mut self, id: &VoiceId) -> Option<&mut Voice> {
+        self.voices.voice_mut(id)
+    }
+
+    /// Switches to the buffers mixed in the previous frame and mixes the
+    /// next frame.
+    ///
+    /// Must be called at the start of every VBlank.
+    pub fn frame(&mut self) {
+        let next = self.playing;
+        self.playing ^= 1;
+
+        unsafe {
+            let buffers = &*ptr::addr_of!(BUFFERS);
+            // The buffers are static and were accepted by `new()`.
+            let _ = self.left.stream(buffers.0[self.playing][0].as_ptr());
+            let _ = self.right.stream(buffers.0[self.playing][1].as_ptr());
+        }
+
+        let acc = unsafe { &mut (*ptr::addr_of_mut!(ACCUMULATOR))[..self.samples] };
+        self.voices.mix(acc, mix_stereo);
+
+        let buffers = unsafe { &mut *ptr::addr_of_mut!(BUFFERS) };
+        let [left, right] = &mut buffers.0[next];
+        for (i, &[l, r]) in acc.iter().enumerate() {
+            left[i] = clip(l);
+            right[i] = clip(r);
+        }
+    }
+}
+
+impl AsMut<Voices> for Mixer {
+    fn as_mut(&mut self) -> &mut Voices {
+        &mut self.voices
+    }
+}
+
+/// Calls the ARM inner loop.
+unsafe fn mix_stereo(
+    acc: &mut [[i32; 2]],
+    sample: *const i8,
+    pos: u32,
+    step: u32,
+    lvol: i32,
+    rvol: i32,
+) -> u32 {
+    gba_mix_stereo(acc.as_mut_ptr(), acc.len(), sample, pos, step, lvol, rvol)
+}
+
+/// Moves the mixer into a static and mixes from the VBlank interrupt.
+///
+/// The mixer runs from a VBlank hook added with
+/// [`interrupt::add_vblank_hook()`], so it runs alongside the game's own
+/// VBlank handler. Use [`with()`] to access the mixer afterwards.
+pub fn install(mixer: Mixer) -> Result<(), HookError> {
+    interrupt::free(|| unsafe {
+        *ptr::addr_of_mut!(MIXER) = Some(mixer);
+    });
+
+    interrupt::add_vblank_hook(vblank)
+}
+
+/// Runs `f` with the installed mixer, with interrupts disabled.
+pub fn with<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&mut Mixer) -> R,
+{
+    interrupt::free(|| unsafe { (*ptr::addr_of_mut!(MIXER)).as_mut().map(f) })
+}
+
+/// Mixes the next frame with the installed mixer.
+fn vblank() {
+    if let Some(mixer) = unsafe { (*ptr::addr_of_mut!(MIXER)).as_mut() } {
+        mixer.frame();
+    }
+}
@@ -0,0 +1,969 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! PSG channels.
+//!
+//! The four channels inherited from the Game Boy: two square waves (channel 1
+//! with a frequency sweep), a programmable 4-bit wave, and noise. Each is
+//! started by writing its frequency register with the restart flag, and is
+//! only heard once enabled for the speakers with [`PsgControl`].
+//!
+//! Most length and frequency fields are write-only and read back as 0.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbasoundchannel1tonesweep>
+//!
+//! ```rust
+//! use gba::sound::psg::{
+//!     Duty,
+//!     Envelope,
+//!     Frequency,
+//!     PsgChannel,
+//!     PsgControl,
+//!     SoundEffect,
+//!     SquareControl,
+//!     Sweep,
+//! };
+//!
+//! const COIN: SoundEffect = SoundEffect::Square1 {
+//!     sweep: Sweep::new().with_time(1).with_shift(2),
+//!     control: SquareControl::new()
+//!         .with_duty(Duty::Half)
+//!         .with_envelope(Envelope::new(12).with_step_time(2)),
+//!     frequency: Frequency::new(1899),
+//! };
+//!
+//! gba::sound::enable();
+//! PsgControl::new().with_volume(7, 7).with_output(PsgChannel::Square1, true, true).set();
+//!
+//! COIN.play();
+//! ```
+
+use crate::register::{ReadWrite, Register, WriteOnly};
+
+const SOUND1CNT_L: Register<Sweep, ReadWrite, 0x0400_0060> = unsafe { Register::new() };
+const SOUND1CNT_H: Register<SquareControl, ReadWrite, 0x0400_0062> = unsafe { Register::new() };
+const SOUND1CNT_X: Register<Frequency, ReadWrite, 0x0400_0064> = unsafe { Register::new() };
+const SOUND2CNT_L: Register<SquareControl, ReadWrite, 0x0400_0068> = unsafe { Register::new() };
+const SOUND2CNT_H: Register<Frequency, ReadWrite, 0x0400_006C> = unsafe { Register::new() };
+const SOUND3CNT_L: Register<WaveControl, ReadWrite, 0x0400_0070> = unsafe { Register::new() };
+const SOUND3CNT_H: Register<WaveOutput, ReadWrite, 0x0400_0072> = unsafe { Register::new() };
+const SOUND3CNT_X: Register<Frequency, ReadWrite, 0x0400_0074> = unsafe { Register::new() };
+const SOUND4CNT_L: Register<NoiseControl, ReadWrite, 0x0400_0078> = unsafe { Register::new() };
+const SOUND4CNT_H: Register<NoiseFrequency, ReadWrite, 0x0400_007C> = unsafe { Register::new() };
+const SOUNDCNT_L: Register<PsgControl, ReadWrite, 0x0400_0080> = unsafe { Register::new() };
+const WAVE_RAM0: Register<u32, WriteOnly, 0x0400_0090> = unsafe { Register::new() };
+const WAVE_RAM1: Register<u32, WriteOnly, 0x0400_0094> = unsafe { Register::new() };
+const WAVE_RAM2: Register<u32, WriteOnly, 0x0400_0098> = unsafe { Register::new() };
+const WAVE_RAM3: Register<u32, WriteOnly, 0x0400_009C> = unsafe { Register::new() };
+
+/// The restart flag of the frequency registers.
+const RESTART: u16 = 1 << 15;
+
+/// A PSG channel.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PsgChannel {
+    /// Square wave with frequency sweep
+    Square1 = 0,
+    /// Square wave
+    Square2 = 1,
+    /// Programmable wave
+    Wave = 2,
+    Noise = 3,
+}
+
+impl PsgChannel {
+    /// Checks if the channel is playing.
+    ///
+    /// A channel stops when its length expires, or when channel 1 sweeps past
+    /// the highest frequency.
+    pub fn is_playing(self) -> bool {
+        (super::SOUNDCNT_X.read() & (1 << self as u16)) != 0
+    }
+}
+
+/// The master volume and speaker outputs of the PSG channels, from
+/// SOUNDCNT_L.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct PsgControl(u16);
+
+impl PsgControl {
+    const VOLUME_MASK: u16 = 0b111;
+    const RIGHT_VOLUME_SHIFT: u16 = 0;
+    const LEFT_VOLUME_SHIFT: u16 = 4;
+    const RIGHT_SHIFT: u16 = 8;
+    const LEFT_SHIFT: u16 = 12;
+
+    /// Returns settings with the volume at its lowest and no channels
+    /// output.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current PSG settings.
+    pub fn get() -> Self {
+        SOUNDCNT_L.read()
+    }
+
+    /// Applies the PSG settings.
+    pub fn set(self) {
+        SOUNDCNT_L.write(self);
+    }
+
+    /// The master volume (0-7) of the left and right speakers.
+    pub const fn volume(self) -> (u8, u8) {
+        let left = (self.0 >> Self::LEFT_VOLUME_SHIFT) & Self::VOLUME_MASK;
+        let right = (self.0 >> Self::RIGHT_VOLUME_SHIFT) & Self::VOLUME_MASK;
+        (left as u8, right as u8)
+    }
+
+    /// Sets the master volume (0-7) of the left and right speakers.
+    pub const fn with_volume(self, left: u8, right: u8) -> Self {
+        let mask = Self::VOLUME_MASK << Self::LEFT_VOLUME_SHIFT
+            | Self::VOLUME_MASK << Self::RIGHT_VOLUME_SHIFT;
+        let left = (left as u16 & Self::VOLUME_MASK) << Self::LEFT_VOLUME_SHIFT;
+        let right = (right as u16 & Self::VOLUME_MASK) << Self::RIGHT_VOLUME_SHIFT;
+        Self((self.0 & !mask) | left | right)
+    }
+
+    /// Returns if the channel is output to the left and right speakers.
+    pub const fn output(self, channel: PsgChannel) -> (bool, bool) {
+        let left = 1 << (Self::LEFT_SHIFT + channel as u16);
+        let right = 1 << (Self::RIGHT_SHIFT + channel as u16);
+        ((self.0 & left) != 0, (self.0 & right) != 0)
+    }
+
+    /// Sets if the channel is output to the left and right speakers.
+    pub const fn with_output(self, channel: PsgChannel, left: bool, right: bool) -> Self {
+        self.with_flag(1 << (Self::LEFT_SHIFT + channel as u16), left)
+            .with_flag(1 << (Self::RIGHT_SHIFT + channel as u16), right)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for PsgControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PsgControl> for u16 {
+    fn from(value: PsgControl) -> Self {
+        value.0
+    }
+}
+
+/// The volume of the PSG channels relative to Direct Sound, set with
+/// [`DirectSoundControl`](super::direct::DirectSoundControl).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum PsgVolume {
+    #[default]
+    Quarter = 0,
+    Half = 1,
+    Full = 2,
+}
+
+/// The frequency sweep of channel 1, from SOUND1CNT_L.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct Sweep(u16);
+
+impl Sweep {
+    const SHIFT_MASK: u16 = 0b111;
+    const DECREASE: u16 = 1 << 3;
+    const TIME_SHIFT: u16 = 4;
+    const TIME_MASK: u16 = 0b111;
+
+    /// Returns a sweep that is disabled.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current sweep of channel 1.
+    pub fn get() -> Self {
+        SOUND1CNT_L.read()
+    }
+
+    /// Applies the sweep to channel 1.
+    pub fn set(self) {
+        SOUND1CNT_L.write(self);
+    }
+
+    /// The number of the shift (0-7).
+    pub const fn shift(self) -> u8 {
+        (self.0 & Self::SHIFT_MASK) as u8
+    }
+
+    /// Sets the number of the shift (0-7). Each step changes the frequency
+    /// rate by `rate >> shift`.
+    pub const fn with_shift(self, shift: u8) -> Self {
+        Self((self.0 & !Self::SHIFT_MASK) | (shift as u16 & Self::SHIFT_MASK))
+    }
+
+    /// Checks if the frequency decreases on each step.
+    pub const fn is_decreasing(self) -> bool {
+        (self.0 & Self::DECREASE) != 0
+    }
+
+    /// Sets if the frequency decreases, instead of increases, on each step.
+    pub const fn with_decrease(self, decrease: bool) -> Self {
+        if decrease {
+            Self(self.0 | Self::DECREASE)
+        } else {
+            Self(self.0 & !Self::DECREASE)
+        }
+    }
+
+    /// The time between steps (0-7).
+    pub const fn time(self) -> u8 {
+        ((self.0 >> Self::TIME_SHIFT) & Self::TIME_MASK) as u8
+    }
+
+    /// Sets the time between steps (0-7), in units of 1/128 s. A time of 0
+    /// disables the sweep.
+    pub const fn with_time(self, time: u8) -> Self {
+        let mask = Self::TIME_MASK << Self::TIME_SHIFT;
+        Self((self.0 & !mask) | ((time as u16 & Self::TIME_MASK) << Self::TIME_SHIFT))
+    }
+}
+
+impl From<u16> for Sweep {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Sweep> for u16 {
+    fn from(value: Sweep) -> Self {
+        value.0
+    }
+}
+
+/// A volume envelope for the square and noise channels.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct Envelope(u8);
+
+impl Envelope {
+    const STEP_MASK: u8 = 0b111;
+    const INCREASE: u8 = 1 << 3;
+    const VOLUME_SHIFT: u8 = 4;
+
+    /// Returns an envelope with a constant volume (0-15).
+    pub const fn new(volume: u8) -> Self {
+        Self((volume & 0xF) << Self::VOLUME_SHIFT)
+    }
+
+    /// The initial volume (0-15).
+    pub const fn volume(self) -> u8 {
+        self.0 >> Self::VOLUME_SHIFT
+    }
+
+    /// Checks if the volume increases on each step.
+    pub const fn is_increasing(self) -> bool {
+        (self.0 & Self::INCREASE) != 0
+    }
+
+    /// Sets if the volume increases, instead of decreases, on each step.
+    pub const fn with_increase(self, increase: bool) -> Self {
+        if increase {
+            Self(self.0 | Self::INCREASE)
+        } else {
+            Self(self.0 & !Self::INCREASE)
+        }
+    }
+
+    /// The time between steps (0-7).
+    pub const fn step_time(self) -> u8 {
+        self.0 & Self::STEP_MASK
+    }
+
+    /// Sets the time between steps (0-7), in units of 1/64 s. A time of 0
+    /// keeps the volume constant.
+    pub const fn with_step_time(self, time: u8) -> Self {
+        Self((self.0 & !Self::STEP_MASK) | (time & Self::STEP_MASK))
+    }
+}
+
+/// The duty cycle of a square wave.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Duty {
+    /// 12.5%
+    Eighth = 0,
+    /// 25%
+    Quarter = 1,
+    /// 50%
+    #[default]
+    Half = 2,
+    /// 75%
+    ThreeQuarters = 3,
+}
+
+/// The length, duty, and envelope of a square channel, from SOUND1CNT_H or
+/// SOUND2CNT_L.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct SquareControl(u16);
+
+impl SquareControl {
+    const LENGTH_MASK: u16 = 0b11_1111;
+    const DUTY_SHIFT: u16 = 6;
+    const DUTY_MASK: u16 = 0b11;
+    const ENVELOPE_SHIFT: u16 = 8;
+
+    /// Returns settings with a 12.5% duty cycle and no volume.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current settings of the channel.
+    pub fn get(channel: Square) -> Self {
+        match channel {
+            Square::One => SOUND1CNT_H.read(),
+            Square::Two => SOUND2CNT_L.read(),
+        }
+    }
+
+    /// Applies the settings to the channel.
+    ///
+    /// Envelope changes take effect when the channel is restarted.
+    pub fn set(self, channel: Square) {
+        match channel {
+            Square::One => SOUND1CNT_H.write(self),
+            Square::Two => SOUND2CNT_L.write(self),
+        }
+    }
+
+    /// Sets the length (0-63) of the sound, used if the frequency is
+    /// [timed](Frequency::with_timed). The sound lasts `(64 - length) / 256`
+    /// seconds. Write-only.
+    pub const fn with_length(self, length: u8) -> Self {
+        Self((self.0 & !Self::LENGTH_MASK) | (length as u16 & Self::LENGTH_MASK))
+    }
+
+    /// The duty cycle of the wave.
+    pub const fn duty(self) -> Duty {
+        match (self.0 >> Self::DUTY_SHIFT) & Self::DUTY_MASK {
+            0 => Duty::Eighth,
+            1 => Duty::Quarter,
+            2 => Duty::Half,
+            _ => Duty::ThreeQuarters,
+        }
+    }
+
+    /// Sets the duty cycle of the wave.
+    pub const fn with_duty(self, duty: Duty) -> Self {
+        let mask = Self::DUTY_MASK << Self::DUTY_SHIFT;
+        Self((self.0 & !mask) | ((duty as u16) << Self::DUTY_SHIFT))
+    }
+
+    /// The volume envelope.
+    pub const fn envelope(self) -> Envelope {
+        Envelope((self.0 >> Self::ENVELOPE_SHIFT) as u8)
+    }
+
+    /// Sets the volume envelope.
+    pub const fn with_envelope(self, envelope: Envelope) -> Self {
+        Self((self.0 & 0xFF) | ((envelope.0 as u16) << Self::ENVELOPE_SHIFT))
+    }
+}
+
+impl From<u16> for SquareControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SquareControl> for u16 {
+    fn from(value: SquareControl) -> Self {
+        value.0
+    }
+}
+
+/// The frequency of a square or wave channel, from SOUND1CNT_X,
+/// SOUND2CNT_H, or SOUND3CNT_X.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct Frequency(u16);
+
+impl Frequency {
+    const RATE_MASK: u16 = 0x7FF;
+    const TIMED: u16 = 1 << 14;
+
+    /// Returns a frequency from its rate (0-2047), which plays until
+    /// stopped.
+    ///
+    /// Square channels play at `131072 / (2048 - rate)` Hz. See
+    /// [`tone_rate()`] and [`Note::rate()`].
+    pub const fn new(rate: u16) -> Self {
+        Self(rate & Self::RATE_MASK)
+    }
+
+    /// Sets the rate (0-2047). Write-only.
+    pub const fn with_rate(self, rate: u16) -> Self {
+        Self((self.0 & !Self::RATE_MASK) | (rate & Self::RATE_MASK))
+    }
+
+    /// Checks if the sound stops when its length expires.
+    pub const fn is_timed(self) -> bool {
+        (self.0 & Self::TIMED) != 0
+    }
+
+    /// Sets if the sound stops when its length expires, instead of playing
+    /// continuously.
+    pub const fn with_timed(self, timed: bool) -> Self {
+        if timed {
+            Self(self.0 | Self::TIMED)
+        } else {
+            Self(self.0 & !Self::TIMED)
+        }
+    }
+}
+
+impl From<u16> for Frequency {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Frequency> for u16 {
+    fn from(value: Frequency) -> Self {
+        value.0
+    }
+}
+
+/// A square wave channel.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Square {
+    /// Channel 1, which has a frequency [sweep](Sweep).
+    One,
+    /// Channel 2.
+    Two,
+}
+
+impl Square {
+    /// Restarts the channel at the frequency, applying its length and
+    /// envelope.
+    pub fn start(self, frequency: Frequency) {
+        self.write_frequency(Frequency(frequency.0 | RESTART));
+    }
+
+    /// Changes the frequency of the channel without restarting it.
+    pub fn set_frequency(self, frequency: Frequency) {
+        self.write_frequency(frequency);
+    }
+
+    fn write_frequency(self, frequency: Frequency) {
+        match self {
+            Self::One => SOUND1CNT_X.write(frequency),
+            Self::Two => SOUND2CNT_H.write(frequency),
+        }
+    }
+}
+
+/// The bank layout of the wave channel.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum WaveBanks {
+    /// A 32-sample wave from the selected bank.
+    #[default]
+    One,
+    /// A 64-sample wave from both banks, starting with the selected bank.
+    Two,
+}
+
+/// The bank settings of the wave channel, from SOUND3CNT_L.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct WaveControl(u16);
+
+impl WaveControl {
+    const TWO_BANKS: u16 = 1 << 5;
+    const BANK: u16 = 1 << 6;
+    const PLAYBACK: u16 = 1 << 7;
+
+    /// Returns settings with one bank, bank 0 selected, and playback
+    /// disabled.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current wave settings.
+    pub fn get() -> Self {
+        SOUND3CNT_L.read()
+    }
+
+    /// Applies the wave settings.
+    pub fn set(self) {
+        SOUND3CNT_L.write(self);
+    }
+
+    /// The bank layout.
+    pub const fn banks(self) -> WaveBanks {
+        if (self.0 & Self::TWO_BANKS) != 0 {
+            WaveBanks::Two
+        } else {
+            WaveBanks::One
+        }
+    }
+
+    /// Sets the bank layout.
+    pub const fn with_banks(self, banks: WaveBanks) -> Self {
+        self.with_flag(Self::TWO_BANKS, matches!(banks, WaveBanks::Two))
+    }
+
+    /// The bank (0 or 1) selected for playback.
+    pub const fn bank(self) -> u8 {
+        if (self.0 & Self::BANK) != 0 {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Selects the bank (0 or 1) for playback. The other bank is the one
+    /// accessed through wave RAM.
+    pub const fn with_bank(self, bank: u8) -> Self {
+        self.with_flag(Self::BANK, (bank & 1) != 0)
+    }
+
+    /// Checks if the channel can play.
+    pub const fn is_playback_enabled(self) -> bool {
+        (self.0 & Self::PLAYBACK) != 0
+    }
+
+    /// Sets if the channel can play.
+    pub const fn with_playback(self, enabled: bool) -> Self {
+        self.with_flag(Self::PLAYBACK, enabled)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for WaveControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<WaveControl> for u16 {
+    fn from(value: WaveControl) -> Self {
+        value.0
+    }
+}
+
+/// The output volume of the wave channel.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(u16)]
+pub enum WaveVolume {
+    #[default]
+    Mute = 0,
+    Full = 1 << 13,
+    Half = 2 << 13,
+    Quarter = 3 << 13,
+    ThreeQuarters = 1 << 15,
+}
+
+/// The length and volume of the wave channel, from SOUND3CNT_H.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct WaveOutput(u16);
+
+impl WaveOutput {
+    const LENGTH_MASK: u16 = 0xFF;
+    const VOLUME_MASK: u16 = 0b111 << 13;
+
+    /// Returns settings with the channel muted.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current wave output settings.
+    pub fn get() -> Self {
+        SOUND3CNT_H.read()
+    }
+
+    /// Applies the wave output settings.
+    pub fn set(self) {
+        SOUND3CNT_H.write(self);
+    }
+
+    /// Sets the length (0-255) of the sound, used if the frequency is
+    /// [timed](Frequency::with_timed). The sound lasts `(256 - length) / 256`
+    /// seconds. Write-only.
+    pub const fn with_length(self, length: u8) -> Self {
+        Self((self.0 & !Self::LENGTH_MASK) | length as u16)
+    }
+
+    /// The output volume.
+    pub const fn volume(self) -> WaveVolume {
+        if (self.0 & WaveVolume::ThreeQuarters as u16) != 0 {
+            return WaveVolume::ThreeQuarters;
+        }
+
+        match (self.0 >> 13) & 0b11 {
+            1 => WaveVolume::Full,
+            2 => WaveVolume::Half,
+            3 => WaveVolume::Quarter,
+            _ => WaveVolume::Mute,
+        }
+    }
+
+    /// Sets the output volume.
+    pub const fn with_volume(self, volume: WaveVolume) -> Self {
+        Self((self.0 & !Self::VOLUME_MASK) | volume as u16)
+    }
+}
+
+impl From<u16> for WaveOutput {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<WaveOutput> for u16 {
+    fn from(value: WaveOutput) -> Self {
+        value.0
+    }
+}
+
+/// The programmable wave channel.
+pub mod wave {
+    use super::{
+        Frequency,
+        WaveControl,
+        RESTART,
+        SOUND3CNT_X,
+        WAVE_RAM0,
+        WAVE_RAM1,
+        WAVE_RAM2,
+        WAVE_RAM3,
+    };
+
+    /// Restarts the channel at the frequency.
+    ///
+    /// With one bank, the wave plays at `65536 / (2048 - rate)` Hz.
+    pub fn start(frequency: Frequency) {
+        SOUND3CNT_X.write(Frequency(frequency.0 | RESTART));
+    }
+
+    /// Changes the frequency of the channel without restarting it.
+    pub fn set_frequency(frequency: Frequency) {
+        SOUND3CNT_X.write(frequency);
+    }
+
+    /// Loads 32 4-bit samples into a bank (0 or 1).
+    ///
+    /// Samples are packed 2 per byte, high nibble first. Only the bank not
+    /// selected for playback can be written, so writing the selected bank
+    /// briefly switches playback to the other bank.
+    pub fn load(bank: u8, samples: &[u8; 16]) {
+        let control = WaveControl::get();
+        if control.bank() == (bank & 1) {
+            control.with_bank(bank ^ 1).set();
+        }
+
+        let word = |i: usize| {
+            u32::from_le_bytes([samples[i], samples[i + 1], samples[i + 2], samples[i + 3]])
+        };
+        WAVE_RAM0.write(word(0));
+        WAVE_RAM1.write(word(4));
+        WAVE_RAM2.write(word(8));
+        WAVE_RAM3.write(word(12));
+
+        control.set();
+    }
+}
+
+/// The length and envelope of the noise channel, from SOUND4CNT_L.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct NoiseControl(u16);
+
+impl NoiseControl {
+    const LENGTH_MASK: u16 = 0b11_1111;
+    const ENVELOPE_SHIFT: u16 = 8;
+
+    /// Returns settings with no volume.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the current noise settings.
+    pub fn get() -> Self {
+        SOUND4CNT_L.read()
+    }
+
+    /// Applies the noise settings.
+    ///
+    /// Envelope changes take effect when the channel is restarted.
+    pub fn set(self) {
+        SOUND4CNT_L.write(self);
+    }
+
+    /// Sets the length (0-63) of the sound, used if the frequency is
+    /// [timed](NoiseFrequency::with_timed). The sound lasts
+    /// `(64 - length) / 256` seconds. Write-only.
+    pub const fn with_length(self, length: u8) -> Self {
+        Self((self.0 & !Self::LENGTH_MASK) | (length as u16 & Self::LENGTH_MASK))
+    }
+
+    /// The volume envelope.
+    pub const fn envelope(self) -> Envelope {
+        Envelope((self.0 >> Self::ENVELOPE_SHIFT) as u8)
+    }
+
+    /// Sets the volume envelope.
+    pub const fn with_envelope(self, envelope: Envelope) -> Self {
+        Self((self.0 & 0xFF) | ((envelope.0 as u16) << Self::ENVELOPE_SHIFT))
+    }
+}
+
+impl From<u16> for NoiseControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NoiseControl> for u16 {
+    fn from(value: NoiseControl) -> Self {
+        value.0
+    }
+}
+
+/// The width of the noise generator.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum NoiseWidth {
+    /// White noise
+    #[default]
+    Bits15,
+    /// Periodic, metallic noise
+    Bits7,
+}
+
+/// The frequency of the noise channel, from SOUND4CNT_H.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct NoiseFrequency(u16);
+
+impl NoiseFrequency {
+    const RATIO_MASK: u16 = 0b111;
+    const WIDTH_7: u16 = 1 << 3;
+    const SHIFT_SHIFT: u16 = 4;
+    const SHIFT_MASK: u16 = 0b1111;
+    const TIMED: u16 = 1 << 14;
+
+    /// Returns the frequency from a dividing ratio (0-7) and shift (0-13).
+    ///
+    /// The noise is clocked at `524288 / ratio / 2^(shift + 1)` Hz, with a
+    /// ratio of 0 treated as 0.5.
+    pub const fn new(ratio: u8, shift: u8) -> Self {
+        let ratio = ratio as u16 & Self::RATIO_MASK;
+        let shift = (shift as u16 & Self::SHIFT_MASK) << Self::SHIFT_SHIFT;
+        Self(ratio | shift)
+    }
+
+    /// Returns the current noise frequency settings.
+    pub fn get() -> Self {
+        SOUND4CNT_H.read()
+    }
+
+    /// The dividing ratio.
+    pub const fn ratio(self) -> u8 {
+        (self.0 & Self::RATIO_MASK) as u8
+    }
+
+    /// The shift of the clock.
+    pub const fn shift(self) -> u8 {
+        ((self.0 >> Self::SHIFT_SHIFT) & Self::SHIFT_MASK) as u8
+    }
+
+    /// The width of the noise generator.
+    pub const fn width(self) -> NoiseWidth {
+        if (self.0 & Self::WIDTH_7) != 0 {
+            NoiseWidth::Bits7
+        } else {
+            NoiseWidth::Bits15
+        }
+    }
+
+    /// Sets the width of the noise generator.
+    pub const fn with_width(self, width: NoiseWidth) -> Self {
+        self.with_flag(Self::WIDTH_7, matches!(width, NoiseWidth::Bits7))
+    }
+
+    /// Checks if the sound stops when its length expires.
+    pub const fn is_timed(self) -> bool {
+        (self.0 & Self::TIMED) != 0
+    }
+
+    /// Sets if the sound stops when its length expires, instead of playing
+    /// continuously.
+    pub const fn with_timed(self, timed: bool) -> Self {
+        self.with_flag(Self::TIMED, timed)
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for NoiseFrequency {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NoiseFrequency> for u16 {
+    fn from(value: NoiseFrequency) -> Self {
+        value.0
+    }
+}
+
+/// The noise channel.
+pub mod noise {
+    use super::{NoiseFrequency, RESTART, SOUND4CNT_H};
+
+    /// Restarts the channel at the frequency, applying its length and
+    /// envelope.
+    pub fn start(frequency: NoiseFrequency) {
+        SOUND4CNT_H.write(NoiseFrequency(frequency.0 | RESTART));
+    }
+
+    /// Changes the frequency of the channel without restarting it.
+    pub fn set_frequency(frequency: NoiseFrequency) {
+        SOUND4CNT_H.write(frequency);
+    }
+}
+
+/// Returns the square channel rate for a frequency, in Hz.
+///
+/// Frequencies are limited to 64-131072 Hz. For the wave channel with one
+/// bank, use half the frequency.
+pub const fn tone_rate(hz: u32) -> u16 {
+    let hz = if hz < 64 {
+        64
+    } else if hz > 131_072 {
+        131_072
+    } else {
+        hz
+    };
+    (2048 - 131_072 / hz) as u16
+}
+
+/// A note of the chromatic scale.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Note {
+    C = 0,
+    CSharp,
+    D,
+    DSharp,
+    E,
+    F,
+    FSharp,
+    G,
+    GSharp,
+    A,
+    ASharp,
+    B,
+}
+
+impl Note {
+    /// The period of each note in octave 4, in units of 1/2^21 s.
+    const PERIODS: [u16; 12] =
+        [8013, 7566, 7144, 6742, 6362, 6005, 5666, 5346, 5048, 4766, 4499, 4246];
+
+    /// Returns the square channel rate of the note in an octave, where A4 is
+    /// 440 Hz.
+    ///
+    /// Octaves 2 to 9 are supported; for the wave channel with one bank, use
+    /// the octave above. Returns `None` for other octaves.
+    pub const fn rate(self, octave: u8) -> Option<u16> {
+        if octave < 2 || octave > 9 {
+            return None;
+        }
+
+        Some(2048 - (Self::PERIODS[self as usize] >> octave))
+    }
+}
+
+/// A sound effect that plays on a PSG channel with one call.
+///
+/// ```rust
+/// use gba::sound::psg::{Envelope, NoiseControl, NoiseFrequency, SoundEffect};
+///
+/// const EXPLOSION: SoundEffect = SoundEffect::Noise {
+///     control: NoiseControl::new().with_envelope(Envelope::new(15).with_step_time(3)),
+///     frequency: NoiseFrequency::new(5, 6),
+/// };
+///
+/// EXPLOSION.play();
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SoundEffect {
+    /// A square wave with a frequency sweep, on channel 1.
+    Square1 {
+        sweep: Sweep,
+        control: SquareControl,
+        frequency: Frequency,
+    },
+    /// A square wave, on channel 2.
+    Square2 {
+        control: SquareControl,
+        frequency: Frequency,
+    },
+    /// Noise, on channel 4.
+    Noise {
+        control: NoiseControl,
+        frequency: NoiseFrequency,
+    },
+}
+
+impl SoundEffect {
+    /// The channel the effect plays on.
+    pub const fn channel(&self) -> PsgChannel {
+        match self {
+            Self::Square1 { .. } => PsgChannel::Square1,
+            Self::Square2 { .. } => PsgChannel::Square2,
+            Self::Noise { .. } => PsgChannel::Noise,
+        }
+    }
+
+    /// Restarts the channel with the effect, replacing any sound playing on
+    /// it.
+    ///
+    /// The channel must be output with [`PsgControl`] to be heard.
+    pub fn play(&self) {
+        match *self {
+            Self::Square1 { sweep, control, frequency } => {
+                sweep.set();
+                control.set(Square::One);
+                Square::One.start(frequency);
+            }
+            Self::Square2 { control, frequency } => {
+                control.set(Square::Two);
+                Square::Two.start(frequency);
+            }
+            Self::Noise { control, frequency } => {
+                control.set();
+                noise::start(frequency);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Tracker music.
+//!
+//! Plays songs converted from MOD, S3M, and XM modules at build time by
+//! [`include_tracker!`](crate::include_tracker). Each channel of the song
+//! plays on a voice of the software [mixer](super::mixer), so at most
+//! [`MAX_VOICES`](super::mixer::MAX_VOICES) notes sound at once, shared with
+//! any sound effects.
+//!
+//! The player is implemented in [`gba_portable::sound::tracker`], which
+//! describes how pitch is handled.
+//!
+//! ```rust
+//! use gba::sound::mixer::{self, Mixer};
+//! use gba::sound::tracker::{Player, Song};
+//!
+//! static THEME: Song = gba::include_tracker!("music/theme.xm");
+//!
+//! let mut player = Player::new(&THEME);
+//! player.play();
+//!
+//! loop {
+//!     gba::bios::vblank();
+//!     mixer::with(|mixer| {
+//!         player.update(mixer);
+//!     });
+//! }
+//! ```
+
+pub use gba_portable::sound::tracker::{
+    Pattern,
+    Player,
+    Sample,
+    Song,
+    TrackerEvent,
+    MAX_CHANNELS,
+    NOTE_OFF,
+};
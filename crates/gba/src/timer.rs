@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2021 Tim Crawford <crawfxrd@gmail.com>
+
+//! Hardware timers.
+//!
+//! There are 4 16-bit timers, which count up from a reload value at the CPU
+//! frequency divided by a prescaler. On overflow, a timer reloads and can
+//! request an interrupt or increment the next timer (cascade). Timers 0 and 1
+//! are also used to clock the Direct Sound FIFOs.
+//!
+//! Ref: <https://problemkaputt.de/gbatek.htm#gbatimers>
+//!
+//! ```rust
+//! use gba::timer::{CallbackMode, Prescaler, Stopwatch, Timer0, Timer2, Timer3};
+//!
+//! // Measure a function in CPU cycles.
+//! let mut stopwatch = Stopwatch::new(Timer2::take().unwrap(), Timer3::take().unwrap());
+//! stopwatch.start();
+//! do_work();
+//! let cycles = stopwatch.stop();
+//!
+//! // Call `tick` once per second.
+//! let mut timer = Timer0::take().unwrap();
+//! timer.start_callback(16384, Prescaler::Div1024, CallbackMode::Periodic, tick);
+//! ```
+
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub use gba_portable::timer::{CPU_FREQUENCY, FRAME_CYCLES};
+
+use crate::interrupt::{self, Irq};
+
+/// The memory-mapped address of TM0CNT_L. The registers of each timer are 4
+/// bytes apart.
+const TIMER_BASE: u32 = 0x0400_0100;
+
+/// Set for each timer that has been taken.
+static TAKEN: [AtomicBool; 4] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+];
+/// Set for each timer that stops after its first overflow.
+static ONE_SHOT: [AtomicBool; 4] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+];
+/// The callbacks for each timer.
+static mut CALLBACKS: [Option<fn()>; 4] = [None; 4];
+
+/// Timer 0.
+pub type Timer0 = Timer<0>;
+/// Timer 1.
+pub type Timer1 = Timer<1>;
+/// Timer 2.
+pub type Timer2 = Timer<2>;
+/// Timer 3.
+pub type Timer3 = Timer<3>;
+
+/// The divider applied to the CPU clock.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Prescaler {
+    /// 16.78 MHz; 59.6 ns per tick
+    #[default]
+    Div1 = 0,
+    /// 262.2 kHz; 3.815 us per tick
+    Div64 = 1,
+    /// 65.54 kHz; 15.26 us per tick
+    Div256 = 2,
+    /// 16.38 kHz; 61.04 us per tick
+    Div1024 = 3,
+}
+
+impl Prescaler {
+    /// The number of CPU cycles per tick.
+    pub const fn cycles(self) -> u32 {
+        match self {
+            Self::Div1 => 1,
+            Self::Div64 => 64,
+            Self::Div256 => 256,
+            Self::Div1024 => 1024,
+        }
+    }
+}
+
+/// When a timer callback is called.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum CallbackMode {
+    /// Once, after which the timer is stopped.
+    OneShot,
+    /// On every overflow.
+    #[default]
+    Periodic,
+}
+
+/// The control settings of a timer, from TMxCNT_H.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct TimerControl(u16);
+
+impl TimerControl {
+    const PRESCALER_MASK: u16 = 0b11;
+    const CASCADE: u16 = 1 << 2;
+    const IRQ: u16 = 1 << 6;
+    const ENABLE: u16 = 1 << 7;
+
+    /// Returns settings with no prescaler, cascade, or IRQ.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// The divider applied to the CPU clock.
+    pub const fn prescaler(self) -> Prescaler {
+        match self.0 & Self::PRESCALER_MASK {
+            1 => Prescaler::Div64,
+            2 => Prescaler::Div256,
+            3 => Prescaler::Div1024,
+            _ => Prescaler::Div1,
+        }
+    }
+
+    /// Sets the divider applied to the CPU clock.
+    pub const fn with_prescaler(self, prescaler: Prescaler) -> Self {
+        Self((self.0 & !Self::PRESCALER_MASK) | prescaler as u16)
+    }
+
+    /// Checks if the timer counts overflows of the previous timer.
+    pub const fn cascade(self) -> bool {
+        (self.0 & Self::CASCADE) != 0
+    }
+
+    /// Sets if the timer counts overflows of the previous timer, instead of
+    /// using the prescaler. Not supported by timer 0.
+    pub const fn with_cascade(self, enabled: bool) -> Self {
+        self.with_flag(Self::CASCADE, enabled)
+    }
+
+    /// Checks if an interrupt is requested on overflow.
+    pub const fn irq(self) -> bool {
+        (self.0 & Self::IRQ) != 0
+    }
+
+    /// Sets if an interrupt is requested on overflow.
+    ///
+    /// The interrupt must also be enabled with
+    /// [`interrupt::enable()`](crate::interrupt::enable).
+    pub const fn with_irq(self, enabled: bool) -> Self {
+        self.with_flag(Self::IRQ, enabled)
+    }
+
+    /// Checks if the timer is running.
+    pub const fn is_enabled(self) -> bool {
+        (self.0 & Self::ENABLE) != 0
+    }
+
+    const fn with_flag(self, flag: u16, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | flag)
+        } else {
+            Self(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for TimerControl {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TimerControl> for u16 {
+    fn from(value: TimerControl) -> Self {
+        value.0
+    }
+}
+
+/// Exclusive access to a timer.
+///
+/// Dropping the timer stops it and allows it to be taken again.
+#[derive(Debug)]
+pub struct Timer<const N: usize> {
+    _private: (),
+}
+
+impl<const N: usize> Timer<N> {
+    /// The interrupt requested by the timer on overflow.
+    pub const IRQ: Irq = match N {
+        0 => Irq::Timer0,
+        1 => Irq::Timer1,
+        2 => Irq::Timer2,
+        _ => Irq::Timer3,
+    };
+
+    /// Returns the timer, if it has not already been taken.
+    pub fn take() -> Option<Self> {
+        let taken = TAKEN.get(N)?;
+
+        interrupt::free(|| {
+            if taken.load(Ordering::Relaxed) {
+                return None;
+            }
+            taken.store(true, Ordering::Relaxed);
+            Some(Self { _private: () })
+        })
+    }
+
+    /// Returns the control settings of the timer.
+    pub fn control(&self) -> TimerControl {
+        unsafe { TimerControl(self.register(2).read_volatile()) }
+    }
+
+    /// Sets the value loaded into the counter when the timer is started and
+    /// on each overflow.
+    ///
+    /// The counter register is write-only for the reload value.
+    pub fn set_reload(&mut self, reload: u16) {
+        unsafe {
+            self.register(0).write_volatile(reload);
+        }
+    }
+
+    /// Returns the current value of the counter.
+    pub fn counter(&self) -> u16 {
+        unsafe { self.register(0).read_volatile() }
+    }
+
+    /// Checks if the timer is running.
+    pub fn is_running(&self) -> bool {
+        self.control().is_enabled()
+    }
+
+    /// Starts the timer with the settings, loading the reload value.
+    ///
+    /// A running timer is restarted.
+    pub fn start(&mut self, control: TimerControl) {
+        self.stop();
+        unsafe {
+            self.register(2).write_volatile(control.0 | TimerControl::ENABLE);
+        }
+    }
+
+    /// Stops the timer. The counter keeps its value.
+    pub fn stop(&mut self) {
+        unsafe {
+            self.register(2).write_volatile(self.control().0 & !TimerControl::ENABLE);
+        }
+    }
+
+    /// Calls `callback` every `ticks` ticks of the prescaled clock, or once
+    /// after `ticks` ticks.
+    ///
+    /// A `ticks` value of 0 is 65536 ticks. This registers a handler for the
+    /// timer's interrupt and enables it.
+    pub fn start_callback(
+        &mut self,
+        ticks: u16,
+        prescaler: Prescaler,
+        mode: CallbackMode,
+        callback: fn(),
+    ) {
+        self.stop();
+
+        interrupt::free(|| unsafe {
+            ptr::addr_of_mut!(CALLBACKS[N]).write(Some(callback));
+        });
+        if let Some(one_shot) = ONE_SHOT.get(N) {
+            one_shot.store(mode == CallbackMode::OneShot, Ordering::Relaxed);
+        }
+
+        interrupt::set_handler(Self::IRQ, Some(dispatch::<N>));
+        interrupt::enable(Self::IRQ);
+
+        self.set_reload(0u16.wrapping_sub(ticks));
+        self.start(TimerControl::new().with_prescaler(prescaler).with_irq(true));
+    }
+
+    /// Stops the timer and removes its callback.
+    pub fn cancel_callback(&mut self) {
+        self.stop();
+
+        interrupt::disable(Self::IRQ);
+        interrupt::set_handler(Self::IRQ, None);
+        interrupt::free(|| unsafe {
+            ptr::addr_of_mut!(CALLBACKS[N]).write(None);
+        });
+    }
+
+    fn register(&self, offset: u32) -> *mut u16 {
+        (TIMER_BASE + 4 * N as u32 + offset) as *mut u16
+    }
+}
+
+impl<const N: usize> Drop for Timer<N> {
+    fn drop(&mut self) {
+        self.cancel_callback();
+
+        if let Some(taken) = TAKEN.get(N) {
+            taken.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Calls the callback of timer `N`, stopping the timer if it is one-shot.
+fn dispatch<const N: usize>() {
+    if ONE_SHOT.get(N).is_some_and(|one_shot| one_shot.load(Ordering::Relaxed)) {
+        let control = (TIMER_BASE + 4 * N as u32 + 2) as *mut u16;
+        unsafe {
+            control.write_volatile(control.read_volatile() & !TimerControl::ENABLE);
+        }
+    }
+
+    if let Some(callback) = unsafe { ptr::addr_of!(CALLBACKS[N]).read() } {
+        callback();
+    }
+}
+
+/// A 32-bit cycle counter made of two cascaded timers.
+///
+/// Counts CPU cycles for up to 256 seconds before wrapping.
+#[derive(Debug)]
+pub struct Stopwatch {
+    low: Timer2,
+    high: Timer3,
+}
+
+impl Stopwatch {
+    pub const fn new(low: Timer2, high: Timer3) -> Self {
+        Self { low, high }
+    }
+
+    /// Releases the timers.
+    pub fn into_inner(self) -> (Timer2, Timer3) {
+        (self.low, self.high)
+    }
+
+    /// Resets the count to 0 and starts counting.
+    pub fn start(&mut self) {
+        self.low.stop();
+        self.high.stop();
+        self.low.set_reload(0);
+        self.high.set_reload(0);
+
+        // The high timer must be running before the low timer overflows.
+        self.high.start(TimerControl::new().with_cascade(true));
+        self.low.start(TimerControl::new().with_prescaler(Prescaler::Div1));
+    }
+
+    /// Stops counting, returning the elapsed CPU cycles.
+    pub fn stop(&mut self) -> u32 {
+        self.low.stop();
+        self.high.stop();
+        self.elapsed()
+    }
+
+    /// Returns the CPU cycles counted since the stopwatch was started.
+    pub fn elapsed(&self) -> u32 {
+        // Re-read if the low timer overflowed between the reads.
+        loop {
+            let high = self.high.counter();
+            let low = self.low.counter();
+            if self.high.counter() == high {
+                return (u32::from(high) << 16) | u32::from(low);
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+#!/bin/sh
+# SPDX-License-Identifier: CC0-1.0
+# SPDX-FileCopyrightText: NONE
+
+# Run the tests of the crates that build for the host.
+#
+# The project's Cargo config builds for the GBA with build-std, which cannot
+# build test binaries. Cargo is run from outside the project so the config is
+# not used, with the pinned toolchain given explicitly.
+
+# shellcheck shell=dash
+
+set -Ee
+
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+TOOLCHAIN="$(sed -n 's/^channel = "\(.*\)"$/\1/p' "${ROOT}/rust-toolchain.toml")"
+
+cd "${TMPDIR:-/tmp}"
+cargo "+${TOOLCHAIN}" test \
+    --manifest-path "${ROOT}/Cargo.toml" \
+    --package gba-portable \
+    --package gba-proc-macros \
+    "$@"
@@ -5,13 +5,11 @@
 #![no_main]
 #![allow(clippy::collapsible_if)]
 
-mod mode4;
-
 use gba::color::Color;
+use gba::display::Mode4;
 use gba::input::{Input, Keys};
 use gba::interrupt::{self, Irq};
 use gba::{bios, entry};
-use mode4::Mode4;
 
 const LIGHT_STEEL_BLUE: Color = Color::new(0x16, 0x18, 0x1B);
 
@@ -84,27 +82,9 @@ impl Pixel {
     }
 
     fn update(&mut self, display: &Mode4, input: &Input) {
-        if input.pressed(Keys::RIGHT) {
-            if self.x < display.width() - 1 {
-                self.x += 1;
-            }
-        }
-        if input.pressed(Keys::LEFT) {
-            if self.x > 0 {
-                self.x -= 1;
-            }
-        }
-
-        if input.pressed(Keys::UP) {
-            if self.y > 0 {
-                self.y -= 1;
-            }
-        }
-        if input.pressed(Keys::DOWN) {
-            if self.y < display.height() - 1 {
-                self.y += 1;
-            }
-        }
+        let (dx, dy) = input.dpad();
+        self.x = self.x.saturating_add_signed(dx as isize).min(display.width() - 1);
+        self.y = self.y.saturating_add_signed(dy as isize).min(display.height() - 1);
 
         if input.just_pressed(Keys::START) {
             self.x = display.width() / 2;